@@ -14,6 +14,7 @@ pub const EXTEND_FIRST_SCORE: u32 = 20_000;
 pub const EXTEND_AFTER_SCORE: u32 = 50_000;
 
 pub const BASE_VOLUME: f32 = 1.0 / 4.0;
+pub const MAX_VOLUME: u32 = 10;  // Steps of the volume option, multiplied to `BASE_VOLUME`.
 
 pub const CHANNEL_COUNT: u32 = 3;
 pub const CH_SHOT: u32 = 0;
@@ -1,4 +1,4 @@
-use crate::app::game::enemy::traj_command::TrajCommand;
+use crate::app::game::enemy::traj_command::{parse_traj_commands, TrajCommand};
 use crate::app::game::enemy::FormationIndex;
 use crate::app::game::game_manager::GameManager;
 use crate::app::game::EventQueue;
@@ -7,9 +7,6 @@ use crate::framework::types::Vec2I;
 use crate::framework::{RendererTrait, VKey};
 use crate::util::math::ONE;
 
-use std::fs::File;
-use std::io::{prelude::*, BufReader};
-
 pub struct EditTrajManager {
     fi: FormationIndex,
     no: u32,
@@ -94,130 +91,23 @@ impl EditTrajManager {
 
     fn set_traj_attack(&mut self, game_manager: &mut GameManager, no: u32, flip_x: bool) {
         let filename = format!("debug/debug_traj{}.txt", no);
-        if let Some(traj_command_vec) = load_traj_command_file(&filename) {
-            let enemy_manager = game_manager.enemy_manager_mut();
-            if let Some(enemy) = enemy_manager.get_enemy_at_mut(&self.fi) {
-                if self.from_top {
-                    let pos = *enemy.raw_pos();
-                    enemy.set_pos(&Vec2I::new(pos.x, -16 * ONE));
+        match load_traj_command_file(&filename) {
+            Ok(traj_command_vec) => {
+                let enemy_manager = game_manager.enemy_manager_mut();
+                if let Some(enemy) = enemy_manager.get_enemy_at_mut(&self.fi) {
+                    if self.from_top {
+                        let pos = *enemy.raw_pos();
+                        enemy.set_pos(&Vec2I::new(pos.x, -16 * ONE));
+                    }
+                    enemy.set_table_attack(traj_command_vec, flip_x);
                 }
-                enemy.set_table_attack(traj_command_vec, flip_x);
             }
-        } else {
-            eprintln!("{} load failed", &filename);
+            Err(e) => eprintln!("{}", e),
         }
     }
 }
 
-fn load_traj_command_file(filename: &str) -> Option<Vec<TrajCommand>> {
-    match File::open(filename) {
-        Ok(file) => {
-            let reader = BufReader::new(file);
-            let mut vec = Vec::new();
-            let mut err = false;
-            let mut lineno = 0;
-            let one = ONE as f32;
-            for line in reader.lines() {
-                lineno += 1;
-                if let Ok(line) = line {
-                    if line.len() > 0 && line.starts_with("#") {
-                        continue;
-                    }
-
-                    let words: Vec<&str> = line.split_whitespace().collect();
-                    if words.len() >= 1 {
-                        match words[0] {
-                            "Pos" if words.len() >= 3 => {
-                                if let (Ok(x), Ok(y)) = (&words[1].parse::<f32>(), &words[2].parse::<f32>()) {
-                                    let ix = (x * one).round() as i32;
-                                    let iy = (y * one).round() as i32;
-                                    vec.push(TrajCommand::Pos(ix, iy));
-                                    continue;
-                                }
-                                println!("Line {}: number expected", lineno);
-                                err = true;
-                            }
-                            "Speed" if words.len() >= 2 => {
-                                if let Ok(speed) = &words[1].parse::<f32>() {
-                                    let ispeed = (speed * one).round() as i32;
-                                    vec.push(TrajCommand::Speed(ispeed));
-                                } else {
-                                    println!("Line {}: number expected", lineno);
-                                    err = true;
-                                }
-                            }
-                            "Angle" if words.len() >= 2 => {
-                                if let Ok(angle) = &words[1].parse::<f32>() {
-                                    let iangle = (angle * one).round() as i32;
-                                    vec.push(TrajCommand::Angle(iangle));
-                                } else {
-                                    println!("Line {}: number expected", lineno);
-                                    err = true;
-                                }
-                            }
-                            "VAngle" if words.len() >= 2 => {
-                                if let Ok(vangle) = &words[1].parse::<f32>() {
-                                    let ivangle = (vangle * one).round() as i32;
-                                    vec.push(TrajCommand::VAngle(ivangle));
-                                } else {
-                                    println!("Line {}: number expected", lineno);
-                                    err = true;
-                                }
-                            }
-                            "Delay" if words.len() >= 2 => {
-                                if let &Ok(delay) = &words[1].parse::<u32>() {
-                                    vec.push(TrajCommand::Delay(delay));
-                                } else {
-                                    println!("Line {}: number expected", lineno);
-                                    err = true;
-                                }
-                            }
-                            "DestAngle" if words.len() >= 3 => {
-                                if let (Ok(angle), Ok(radius)) = (&words[1].parse::<f32>(), &words[2].parse::<f32>()) {
-                                    let iangle = (angle * one).round() as i32;
-                                    let iradius = (radius * one).round() as i32;
-                                    vec.push(TrajCommand::DestAngle(iangle, iradius));
-                                    continue;
-                                }
-                                println!("Line {}: number expected", lineno);
-                                err = true;
-                            }
-                            "WaitYG" if words.len() >= 2 => {
-                                if let Ok(value) = &words[1].parse::<f32>() {
-                                    let ivalue = (value * one).round() as i32;
-                                    vec.push(TrajCommand::WaitYG(ivalue));
-                                } else {
-                                    println!("Line {}: number expected", lineno);
-                                    err = true;
-                                }
-                            }
-                            "AddPos" if words.len() >= 3 => {
-                                if let (Ok(x), Ok(y)) = (&words[1].parse::<f32>(), &words[2].parse::<f32>()) {
-                                    let ix = (x * one).round() as i32;
-                                    let iy = (y * one).round() as i32;
-                                    vec.push(TrajCommand::AddPos(ix, iy));
-                                    continue;
-                                }
-                                println!("Line {}: number expected", lineno);
-                                err = true;
-                            }
-                            "CopyFormationX" => {
-                                vec.push(TrajCommand::CopyFormationX);
-                            }
-                            _ => {
-                                println!("Line {}: Unhandled, {:?}", lineno, words);
-                                err = true;
-                            }
-                        }
-                    }
-                }
-            }
-            if err {
-                None
-            } else {
-                Some(vec)
-            }
-        }
-        Err(_err) => None,
-    }
+fn load_traj_command_file(filename: &str) -> Result<Vec<TrajCommand>, String> {
+    let text = std::fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+    parse_traj_commands(&text).map_err(|e| format!("{}: {}", filename, e))
 }
@@ -0,0 +1,81 @@
+use std::rc::Rc;
+
+use super::game::effect::StarManager;
+use super::game::enemy::StagePack;
+use super::game::game_manager::GameManager;
+use super::game::game_manager::Params as GameManagerParams;
+use super::game::score_holder::ScoreHolder;
+use super::game::GameConfig;
+use super::pilot::{HeuristicPilot, PilotTrait};
+
+use crate::framework::{RendererTrait, SystemTrait};
+use crate::util::pad::Pad;
+
+const DEMO_MAX_FRAMES: u32 = 60 * 60;
+
+// Demo play in the attract mode, the score doesn't count.
+pub(super) struct Demo {
+    game_manager: GameManager,
+    score_holder: ScoreHolder,
+    pilot: HeuristicPilot,
+    pad: Pad,
+    pad2: Pad,
+    count: u32,
+}
+
+impl Demo {
+    pub(super) fn new(stage_pack: Rc<StagePack>, seed: u64, high_score: u32) -> Self {
+        let mut game_manager = GameManager::new(stage_pack, GameConfig::default());
+        game_manager.restart(seed, 1);
+        Self {
+            game_manager,
+            score_holder: ScoreHolder { score: 0, high_score, score2: 0 },
+            pilot: HeuristicPilot::new(),
+            pad: Pad::new(),
+            pad2: Pad::new(),
+            count: 0,
+        }
+    }
+
+    // Returns false when the demo is over.
+    pub(super) fn update<S: SystemTrait>(&mut self, star_manager: &mut StarManager, system: &mut S) -> bool {
+        self.count += 1;
+        self.pad.update_with(self.pilot.update(&self.game_manager.observe()));
+
+        let mut params = GameManagerParams {
+            star_manager,
+            pad: &self.pad,
+            pad2: &self.pad2,
+            score_holder: &mut self.score_holder,
+        };
+        self.game_manager.update(&mut params, &mut MutedSystem(system));
+        self.count < DEMO_MAX_FRAMES && !self.game_manager.is_game_over()
+    }
+
+    pub(super) fn draw<R: RendererTrait>(&mut self, renderer: &mut R) {
+        self.game_manager.draw(renderer);
+
+        renderer.set_texture_color_mod("font", 255, 0, 0);
+        renderer.draw_str("font", (28 - 9) / 2 * 8, 3 * 8, "DEMO PLAY");
+    }
+
+    pub(super) fn score_holder(&self) -> &ScoreHolder {
+        &self.score_holder
+    }
+}
+
+// Sound effects are off in the demo.
+struct MutedSystem<'a, S: SystemTrait>(&'a mut S);
+
+impl<'a, S: SystemTrait> SystemTrait for MutedSystem<'a, S> {
+    fn get_u32(&self, key: &str) -> Option<u32> { self.0.get_u32(key) }
+    fn set_u32(&mut self, key: &str, value: u32) { self.0.set_u32(key, value) }
+    fn get_str(&self, key: &str) -> Option<String> { self.0.get_str(key) }
+    fn set_str(&mut self, key: &str, value: &str) { self.0.set_str(key, value) }
+
+    fn play_se(&mut self, _channel: u32, _filename: &str) {}
+
+    fn set_volume(&mut self, volume: u32) { self.0.set_volume(volume) }
+    fn set_window(&mut self, scale: u32, fullscreen: bool) { self.0.set_window(scale, fullscreen) }
+    fn pause_audio(&mut self, paused: bool) { self.0.pause_audio(paused) }
+}
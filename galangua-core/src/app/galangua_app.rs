@@ -1,32 +1,118 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::rc::Rc;
+
+use super::demo::Demo;
 use super::game::effect::StarManager;
+use super::game::enemy::StagePack;
+use super::game::game_config::MAX_STARTING_SHIPS;
 use super::game::game_manager::GameManager;
 use super::game::game_manager::Params as GameManagerParams;
+use super::game::high_score_table::HighScoreTable;
+use super::game::name_entry::{ordinal, NameEntry};
 use super::game::score_holder::ScoreHolder;
+use super::game::{Difficulty, GameConfig, GameStats};
+use super::key_config::KeyConfig;
+use super::pause_menu::{PauseAction, PauseMenu};
+use super::pilot::{Observation, PilotTrait};
+use super::settings::{Settings, MAX_SCALE};
 
 use crate::framework::{AppTrait, RendererTrait, SystemTrait, VKey};
+use crate::util::bindings::Bindings;
 use crate::util::fps_calc::{FpsCalc, TimerTrait};
-use crate::util::pad::{Pad, PadBit};
+use crate::util::pad::{Pad, PadBit, PadSource};
+use crate::util::replay::Replay;
 
 #[cfg(debug_assertions)]
 use super::debug::EditTrajManager;
 
 const KEY_HIGH_SCORE: &str = "highScore";
 const DEFAULT_HIGH_SCORE: u32 = 1000;
+const SNAPSHOT_VERSION: u32 = 4;
+const ATTRACT_PAGE_FRAMES: u32 = 8 * 60;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PlayMode {
+    OnePlayer,
+    Alternate,  // 2 players take turns.
+    Coop,       // 2 players fight simultaneously.
+}
+
+impl PlayMode {
+    fn label(self) -> &'static str {
+        match self {
+            PlayMode::OnePlayer => "1 PLAYER",
+            PlayMode::Alternate => "2 PLAYERS",
+            PlayMode::Coop => "2 PLAYERS CO-OP",
+        }
+    }
+}
+
+// Rows on the title screen.
+#[derive(Clone, Copy, PartialEq)]
+enum TitleItem {
+    Play(PlayMode),
+    Difficulty,
+    Ships,
+    KeyConfig,
+}
 
-#[derive(PartialEq)]
+const TITLE_ITEMS: [TitleItem; 6] = [
+    TitleItem::Play(PlayMode::OnePlayer),
+    TitleItem::Play(PlayMode::Alternate),
+    TitleItem::Play(PlayMode::Coop),
+    TitleItem::Difficulty,
+    TitleItem::Ships,
+    TitleItem::KeyConfig,
+];
+
+#[derive(Debug, PartialEq)]
 enum AppState {
     Title,
+    Demo,
     Game,
+    NameEntry,
+    KeyConfig,
 
     #[cfg(debug_assertions)]
     EditTraj,
 }
 
+// In-game state: `GameManager` and what it touches through `Params`.
+#[derive(Serialize)]
+struct SnapshotRef<'a> {
+    version: u32,
+    game_manager: &'a GameManager,
+    star_manager: &'a StarManager,
+    score_holder: &'a ScoreHolder,
+    frame_count: u32,
+    play_mode: PlayMode,
+    player_no: usize,
+    waiting_game_manager: Option<&'a GameManager>,
+    pending_name_entries: &'a [(usize, u32, u16)],
+}
+
+#[derive(Deserialize)]
+struct Snapshot {
+    version: u32,
+    game_manager: Box<GameManager>,  // Boxed to keep the stack small while deserializing.
+    star_manager: StarManager,
+    score_holder: ScoreHolder,
+    frame_count: u32,
+    play_mode: PlayMode,
+    player_no: usize,
+    waiting_game_manager: Option<Box<GameManager>>,
+    pending_name_entries: Vec<(usize, u32, u16)>,
+}
+
 pub struct GalanguaApp<T: TimerTrait, S: SystemTrait> {
     system: S,
+    seed: Option<u64>,  // Set explicitly, or by a replay.
+    game_seed: u64,  // Of the current or last game.
     state: AppState,
     count: u32,
     pad: Pad,
+    pad2: Pad,  // Player 2 in co-op, `pad` reads only player 1's keys then.
     pressed_key: Option<VKey>,
     fps_calc: FpsCalc<T>,
     game_manager: Option<GameManager>,
@@ -34,6 +120,28 @@ pub struct GalanguaApp<T: TimerTrait, S: SystemTrait> {
     frame_count: u32,
     score_holder: ScoreHolder,
     prev_high_score: u32,
+    quick_save: Option<String>,
+    stage_pack: Rc<StagePack>,
+    last_game_stats: Option<GameStats>,
+    high_score_table: HighScoreTable,
+    name_entry: Option<(usize, NameEntry)>,
+    demo: Option<Demo>,
+    demo_count: u64,
+    pilot: Option<Box<dyn PilotTrait>>,
+    // Scores (player no, score, stage) waiting for the name entry.
+    pending_name_entries: Vec<(usize, u32, u16)>,
+    play_mode: PlayMode,
+    title_cursor: usize,  // Index of `TITLE_ITEMS`.
+    settings: Settings,
+    game_config: Option<GameConfig>,  // Instead of the one by the settings.
+    window: Option<(u32, bool)>,  // Scale and fullscreen instead of the settings, until chosen in the options.
+    key_config: Option<KeyConfig>,  // On the title, or in the pause menu.
+    pause_menu: Option<PauseMenu>,
+    // Alternating play: the other player waits for the turn with its own `GameManager`,
+    // and the scores are swapped in `score_holder`.
+    player_no: usize,
+    waiting_game_manager: Option<GameManager>,
+    debug_overlay: bool,  // Shows FPS and the rank, on in debug builds.
 
     #[cfg(debug_assertions)]
     paused: bool,
@@ -42,20 +150,39 @@ pub struct GalanguaApp<T: TimerTrait, S: SystemTrait> {
 }
 
 impl<T: TimerTrait, S: SystemTrait> GalanguaApp<T, S> {
-    pub fn new(timer: T, system: S) -> Self {
+    pub fn new(timer: T, mut system: S) -> Self {
+        let high_score_table = HighScoreTable::load(&system);
         let high_score = system.get_u32(&KEY_HIGH_SCORE).or(Some(DEFAULT_HIGH_SCORE)).unwrap();
+        let high_score = std::cmp::max(high_score, high_score_table.top_score().unwrap_or(0));
 
-        let star_manager = StarManager::new();
+        let settings = Settings::load(&system);
+        settings.apply(&mut system);
+
+        let seed = rand::thread_rng().gen();
+        let star_manager = StarManager::new(seed);
         let score_holder = ScoreHolder {
             score: 0,
             high_score: high_score,
+            score2: 0,
         };
 
         Self {
             system,
+            seed: None,
+            game_seed: seed,
             state: AppState::Title,
             count: 0,
-            pad: Pad::new(),
+            pad: {
+                let mut pad = Pad::new();
+                pad.set_bindings(&settings.bindings);
+                pad
+            },
+            pad2: {
+                let mut pad2 = Pad::new();
+                pad2.set_source(PadSource::Player2);
+                pad2.set_bindings(&settings.bindings);
+                pad2
+            },
             pressed_key: None,
             fps_calc: FpsCalc::new(timer),
             game_manager: None,
@@ -63,6 +190,25 @@ impl<T: TimerTrait, S: SystemTrait> GalanguaApp<T, S> {
             frame_count: 0,
             score_holder,
             prev_high_score: 0,
+            quick_save: None,
+            stage_pack: Rc::new(StagePack::default()),
+            last_game_stats: None,
+            high_score_table,
+            name_entry: None,
+            demo: None,
+            demo_count: 0,
+            pilot: None,
+            pending_name_entries: Vec::new(),
+            play_mode: PlayMode::OnePlayer,
+            title_cursor: 0,
+            settings,
+            game_config: None,
+            window: None,
+            key_config: None,
+            pause_menu: None,
+            player_no: 0,
+            waiting_game_manager: None,
+            debug_overlay: cfg!(debug_assertions),
 
             #[cfg(debug_assertions)]
             paused: false,
@@ -71,13 +217,218 @@ impl<T: TimerTrait, S: SystemTrait> GalanguaApp<T, S> {
         }
     }
 
+    // Seed of the current or last game, `set_seed` with it reproduces the game.
+    pub fn seed(&self) -> u64 {
+        self.seed.unwrap_or(self.game_seed)
+    }
+
+    // Every game started afterwards uses this seed, instead of a fresh one for each.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = Some(seed);
+        self.game_seed = seed;
+        self.star_manager = StarManager::new(seed);
+    }
+
+    // Used from the next game.
+    pub fn set_stage_pack(&mut self, stage_pack: StagePack) {
+        self.stage_pack = Rc::new(stage_pack);
+    }
+
+    // Player 1 is driven by the pilot instead of the keyboard/joystick, while it is set.
+    pub fn set_pilot(&mut self, pilot: Option<Box<dyn PilotTrait>>) {
+        self.pilot = pilot;
+    }
+
+    pub fn observe(&self) -> Observation {
+        match &self.game_manager {
+            Some(game_manager) if self.state == AppState::Game => game_manager.observe(),
+            _ => Observation::default(),
+        }
+    }
+
+    // Records pad input from the title screen, until `take_replay` is called.
+    // A replay has one seed, so games in the recording share it.
+    pub fn start_recording(&mut self) {
+        let seed = self.seed.unwrap_or_else(|| rand::thread_rng().gen());
+        self.game_seed = seed;
+        self.star_manager = StarManager::new(seed);
+        self.reset_to_title();
+        self.demo_count = 0;
+        self.pad.start_recording(Replay::new(seed, Some(self.current_game_config())));
+        self.pad2.reset_trigger();
+    }
+
+    pub fn take_replay(&mut self) -> Option<Replay> {
+        self.pad.take_recording()
+    }
+
+    // Plays back from the title screen, live input resumes when the replay ends.
+    // Games are played by the rules of the replay, if it has.
+    pub fn start_replay(&mut self, replay: Replay) {
+        self.set_seed(replay.seed);
+        if let Some(config) = &replay.config {
+            self.game_config = Some(config.clone());
+        }
+        self.reset_to_title();
+        self.demo_count = 0;
+        self.pad.start_playback(replay);
+        self.pad2.reset_trigger();
+    }
+
+    // Serializes the in-game state into JSON, `None` if not in game.
+    pub fn save_snapshot(&self) -> Option<String> {
+        if self.state != AppState::Game {
+            return None;
+        }
+
+        let snapshot = SnapshotRef {
+            version: SNAPSHOT_VERSION,
+            game_manager: self.game_manager.as_ref().unwrap(),
+            star_manager: &self.star_manager,
+            score_holder: &self.score_holder,
+            frame_count: self.frame_count,
+            play_mode: self.play_mode,
+            player_no: self.player_no,
+            waiting_game_manager: self.waiting_game_manager.as_ref(),
+            pending_name_entries: &self.pending_name_entries,
+        };
+        serde_json::to_string(&snapshot).ok()
+    }
+
+    // Restores the state saved by `save_snapshot`, and continues the game from there.
+    pub fn load_snapshot(&mut self, json: &str) -> Result<(), String> {
+        let snapshot: Snapshot = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(format!("unsupported snapshot version: {}", snapshot.version));
+        }
+
+        if self.state != AppState::Game {
+            self.prev_high_score = self.score_holder.high_score;
+        }
+        let high_score = std::cmp::max(self.score_holder.high_score, snapshot.score_holder.high_score);
+        let mut game_manager = *snapshot.game_manager;
+        game_manager.set_stage_pack(self.stage_pack.clone());
+        self.game_manager = Some(game_manager);
+        self.waiting_game_manager = snapshot.waiting_game_manager.map(|mut game_manager| {
+            game_manager.set_stage_pack(self.stage_pack.clone());
+            *game_manager
+        });
+        self.play_mode = snapshot.play_mode;
+        self.set_game_pad_source();
+        self.player_no = snapshot.player_no;
+        self.pending_name_entries = snapshot.pending_name_entries;
+        self.star_manager = snapshot.star_manager;
+        self.score_holder = ScoreHolder {
+            high_score,
+            ..snapshot.score_holder
+        };
+        self.frame_count = snapshot.frame_count;
+        self.state = AppState::Game;
+        Ok(())
+    }
+
+    pub fn score(&self) -> u32 {
+        self.score_holder.score
+    }
+
+    pub fn high_score(&self) -> u32 {
+        self.score_holder.high_score
+    }
+
+    // Player (0 origin) of the current turn, always 0 in 1-player mode.
+    pub fn player_no(&self) -> usize {
+        self.player_no
+    }
+
+    // Selects the mode for the next game, like up/down key on the title screen.
+    pub fn set_play_mode(&mut self, play_mode: PlayMode) {
+        self.play_mode = play_mode;
+        self.title_cursor = TITLE_ITEMS.iter().position(|&item| item == TitleItem::Play(play_mode)).unwrap();
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    // Overrides the game options, e.g. from the command line, without saving them.
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        let starting_ships = self.current_game_config().starting_ships;
+        self.game_config = Some(GameConfig { starting_ships, ..GameConfig::preset(difficulty) });
+    }
+
+    pub fn set_starting_ships(&mut self, ships: u32) {
+        let starting_ships = ships.clamp(1, MAX_STARTING_SHIPS);
+        self.game_config = Some(GameConfig { starting_ships, ..self.current_game_config() });
+    }
+
+    // Custom rules for the next games, instead of the difficulty and ships of the settings.
+    pub fn set_game_config(&mut self, config: Option<GameConfig>) {
+        self.game_config = config;
+    }
+
+    // Overrides the window, e.g. from the command line, without saving it.
+    pub fn set_window(&mut self, scale: u32, fullscreen: bool) {
+        let scale = scale.clamp(1, MAX_SCALE);
+        self.window = Some((scale, fullscreen));
+        self.system.set_window(scale, fullscreen);
+    }
+
+    // Off to capture frames the same in any build.
+    pub fn set_debug_overlay(&mut self, on: bool) {
+        self.debug_overlay = on;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.pause_menu.is_some()
+    }
+
+    pub fn system_mut(&mut self) -> &mut S {
+        &mut self.system
+    }
+
+    pub fn high_score_table(&self) -> &HighScoreTable {
+        &self.high_score_table
+    }
+
+    // Statistics of the game in progress, or the last one finished.
+    pub fn game_stats(&self) -> Option<&GameStats> {
+        match &self.game_manager {
+            Some(game_manager) => Some(game_manager.stats()),
+            None => self.last_game_stats.as_ref(),
+        }
+    }
+
     fn update_main(&mut self) -> bool {
+        // The game goes on in the frame pausing, for the recorded input.
+        let paused = self.pause_menu.is_some();
         if self.pressed_key == Some(VKey::Escape) {
-            if self.state != AppState::Title {
-                self.back_to_title();
-            } else {
-                return false;
+            match self.state {
+                AppState::Title => return false,
+                AppState::Game => self.on_pause_key(),
+                _ => self.back_to_title(),
+            }
+        }
+        if paused {
+            self.update_pause_menu();
+            return true;
+        }
+
+        // Quick save/load only in game, other screens have their own state.
+        match self.pressed_key {
+            Some(VKey::F5) if self.state == AppState::Game => {
+                if let Some(json) = self.save_snapshot() {
+                    self.quick_save = Some(json);
+                }
             }
+            Some(VKey::F9) if self.state == AppState::Game => {
+                if let Some(json) = self.quick_save.take() {
+                    if let Err(e) = self.load_snapshot(&json) {
+                        eprintln!("quick load: {}", e);
+                    }
+                    self.quick_save = Some(json);
+                }
+            }
+            _ => {}
         }
 
         #[cfg(debug_assertions)]
@@ -94,38 +445,95 @@ impl<T: TimerTrait, S: SystemTrait> GalanguaApp<T, S> {
 
         match self.state {
             AppState::Title => {
-                self.count = self.count.wrapping_add(1);
+                // Attract loop: title, demo play, high score table, and title again.
+                self.count += 1;
+                if self.count == ATTRACT_PAGE_FRAMES {
+                    self.start_demo();
+                } else if self.count >= ATTRACT_PAGE_FRAMES * 2 {
+                    self.count = 0;
+                }
 
+                let item_count = TITLE_ITEMS.len();
+                if self.pad.is_trigger(PadBit::U) {
+                    self.title_cursor = (self.title_cursor + item_count - 1) % item_count;
+                    self.count = 0;
+                }
+                if self.pad.is_trigger(PadBit::D) {
+                    self.title_cursor = (self.title_cursor + 1) % item_count;
+                    self.count = 0;
+                }
+                let item = TITLE_ITEMS[self.title_cursor];
+                if let TitleItem::Play(mode) = item {
+                    self.play_mode = mode;
+                }
+                let dir = if self.pad.is_trigger(PadBit::L) { -1 } else if self.pad.is_trigger(PadBit::R) { 1 } else { 0 };
+                if dir != 0 {
+                    self.change_game_option(item, dir);
+                }
                 if self.pad.is_trigger(PadBit::A) {
-                    let mut game_manager = GameManager::new();
-                    game_manager.restart();
-                    self.game_manager = Some(game_manager);
-                    self.prev_high_score = self.score_holder.high_score;
-                    self.score_holder.reset_score();
-                    self.state = AppState::Game;
-                    self.frame_count = 0;
+                    // On the game options, starts with the last selected play mode.
+                    if item == TitleItem::KeyConfig {
+                        self.key_config = Some(KeyConfig::new(&self.settings.bindings));
+                        self.state = AppState::KeyConfig;
+                    } else {
+                        self.start_game();
+                    }
                 }
 
                 #[cfg(debug_assertions)]
                 if self.pressed_key == Some(VKey::E) {
                     self.state = AppState::EditTraj;
 
-                    let mut game_manager = GameManager::new();
+                    let mut game_manager = GameManager::new(self.stage_pack.clone(), self.current_game_config());
                     game_manager.start_edit_mode();
                     self.game_manager = Some(game_manager);
                     self.edit_traj_manager = Some(EditTrajManager::new());
                 }
             }
+            AppState::Demo => {
+                let demo = self.demo.as_mut().unwrap();
+                let playing = demo.update(&mut self.star_manager, &mut self.system);
+                if self.pressed_key.is_some() || self.pad.is_any_trigger() {
+                    self.reset_to_title();
+                } else if !playing {
+                    self.reset_to_title();
+                    // Next, high score table.
+                    self.count = ATTRACT_PAGE_FRAMES + 1;
+                }
+            }
             AppState::Game => {
                 self.frame_count += 1;
                 let mut params = GameManagerParams {
                     star_manager: &mut self.star_manager,
                     pad: &self.pad,
+                    pad2: &self.pad2,
                     score_holder: &mut self.score_holder,
                 };
                 let game_manager = self.game_manager.as_mut().unwrap();
                 game_manager.update(&mut params, &mut self.system);
                 if game_manager.is_finished() {
+                    self.finish_game();
+                } else if game_manager.is_turn_end() {
+                    self.switch_turn();
+                }
+            }
+            AppState::NameEntry => {
+                if self.name_entry.as_mut().unwrap().1.update(&self.pad) {
+                    let (_, name_entry) = self.name_entry.take().unwrap();
+                    self.high_score_table.insert(name_entry.entry());
+                    self.high_score_table.save(&mut self.system);
+                    if self.next_name_entry() {
+                        self.count = 0;
+                    } else {
+                        self.back_to_title();
+                    }
+                }
+            }
+            AppState::KeyConfig => {
+                let key_config = self.key_config.as_mut().unwrap();
+                if key_config.update(&self.pad) {
+                    let bindings = key_config.bindings().clone();
+                    self.set_bindings(bindings);
                     self.back_to_title();
                 }
             }
@@ -140,6 +548,7 @@ impl<T: TimerTrait, S: SystemTrait> GalanguaApp<T, S> {
                 let mut params = GameManagerParams {
                     star_manager: &mut self.star_manager,
                     pad: &self.pad,
+                    pad2: &self.pad2,
                     score_holder: &mut self.score_holder,
                 };
                 game_manager.update(&mut params, &mut self.system);
@@ -158,17 +567,66 @@ impl<T: TimerTrait, S: SystemTrait> GalanguaApp<T, S> {
         self.star_manager.draw(renderer);
         match self.state {
             AppState::Title => {
-                renderer.set_texture_color_mod("font", 255, 255, 255);
-                renderer.draw_str("font", 10 * 8, 8 * 8, "GALANGUA");
+                if self.count < ATTRACT_PAGE_FRAMES || self.high_score_table.entries().is_empty() {
+                    renderer.set_texture_color_mod("font", 255, 255, 255);
+                    renderer.draw_str("font", 10 * 8, 8 * 8, "GALANGUA");
+
+                    let config = self.current_game_config();
+                    for (i, item) in TITLE_ITEMS.iter().enumerate() {
+                        let label = match item {
+                            TitleItem::Play(mode) => String::from(mode.label()),
+                            TitleItem::Difficulty => {
+                                format!("DIFFICULTY {:>6}", config.difficulty().map_or("CUSTOM", Difficulty::label))
+                            }
+                            TitleItem::Ships => format!("SHIPS      {:>6}", config.starting_ships),
+                            TitleItem::KeyConfig => String::from("KEY CONFIG"),
+                        };
+                        let y = (13 + i as i32 * 2) * 8;
+                        renderer.draw_str("font", 8 * 8, y, &label);
+                        if i == self.title_cursor {
+                            renderer.draw_str("font", 6 * 8, y, ">");
+                        }
+                    }
+                } else {
+                    draw_high_score_table(renderer, &self.high_score_table);
+                }
 
                 if self.count & 32 == 0 {
-                    renderer.draw_str("font", 2 * 8, 25 * 8, "PRESS SPACE KEY TO START");
+                    renderer.set_texture_color_mod("font", 255, 255, 255);
+                    renderer.draw_str("font", 2 * 8, 27 * 8, "PRESS SPACE KEY TO START");
                 }
-                draw_scores(renderer, &self.score_holder, true);
+                draw_scores(renderer, &self.score_holder, &self.player_scores(), &[false, false]);
+            }
+            AppState::Demo => {
+                let demo = self.demo.as_mut().unwrap();
+                demo.draw(renderer);
+                draw_scores(renderer, demo.score_holder(), &[Some(demo.score_holder().score), None], &[false, false]);
             }
             AppState::Game => {
-                self.game_manager.as_mut().unwrap().draw(renderer);
-                draw_scores(renderer, &self.score_holder, (self.frame_count & 31) < 16);
+                if let Some(key_config) = &self.key_config {
+                    key_config.draw(renderer);
+                } else {
+                    self.game_manager.as_mut().unwrap().draw(renderer);
+                    let blink = (self.frame_count & 31) >= 16;
+                    let coop = self.play_mode == PlayMode::Coop;
+                    let blinks = [blink && (coop || self.player_no == 0), blink && (coop || self.player_no == 1)];
+                    draw_scores(renderer, &self.score_holder, &self.player_scores(), &blinks);
+                    if let Some(pause_menu) = &self.pause_menu {
+                        pause_menu.draw(renderer, &self.current_settings());
+                    }
+                }
+            }
+            AppState::NameEntry => {
+                let (player_no, name_entry) = self.name_entry.as_ref().unwrap();
+                name_entry.draw(renderer);
+                if self.play_mode != PlayMode::OnePlayer {
+                    renderer.set_texture_color_mod("font", 0, 255, 255);
+                    renderer.draw_str("font", 10 * 8, 5 * 8, &format!("PLAYER {}", player_no + 1));
+                }
+                draw_scores(renderer, &self.score_holder, &self.player_scores(), &[false, false]);
+            }
+            AppState::KeyConfig => {
+                self.key_config.as_ref().unwrap().draw(renderer);
             }
             #[cfg(debug_assertions)]
             AppState::EditTraj => {
@@ -179,23 +637,277 @@ impl<T: TimerTrait, S: SystemTrait> GalanguaApp<T, S> {
             }
         }
 
-        #[cfg(debug_assertions)]
-        {
+        if self.debug_overlay {
             renderer.set_texture_color_mod("font", 128, 128, 128);
-            renderer.draw_str("font", 23 * 8, 0 * 8, &format!("FPS{:2}", self.fps_calc.fps()));
+            renderer.draw_str("font", 23 * 8, 35 * 8, &format!("FPS{:2}", self.fps_calc.fps()));
+            if let (AppState::Game, Some(game_manager)) = (&self.state, &self.game_manager) {
+                let (rank, dynamic_rank) = game_manager.rank_detail();
+                let text = format!("RANK{:4} ({:+})", rank, dynamic_rank);
+                renderer.draw_str("font", (28 - text.len() as i32) * 8, 33 * 8, &text);
+            }
+        }
+    }
+
+    // Escape key in game: opens the pause menu, or goes back in it.
+    fn on_pause_key(&mut self) {
+        if self.key_config.take().is_some() {
+            return;
+        }
+        match &mut self.pause_menu {
+            None => self.set_paused(true),
+            Some(pause_menu) => {
+                if pause_menu.back() {
+                    self.set_paused(false);
+                }
+            }
+        }
+    }
+
+    fn update_pause_menu(&mut self) {
+        // Handled by `on_pause_key`, the menu may be closed already.
+        if self.pressed_key == Some(VKey::Escape) {
+            return;
+        }
+
+        if let Some(key_config) = &mut self.key_config {
+            if key_config.update(&self.pad) {
+                let bindings = key_config.bindings().clone();
+                self.set_bindings(bindings);
+                self.key_config = None;
+            }
+            return;
+        }
+
+        let current = self.current_settings();
+        let mut settings = current.clone();
+        let action = self.pause_menu.as_mut().unwrap().update(&self.pad, &mut settings);
+        if settings != current {
+            if let Some(window) = self.window {
+                if (settings.scale, settings.fullscreen) == window {
+                    // Keeps the saved window, other than the overridden one.
+                    settings.scale = self.settings.scale;
+                    settings.fullscreen = self.settings.fullscreen;
+                } else {
+                    self.window = None;
+                }
+            }
+            self.settings = settings;
+            self.current_settings().apply(&mut self.system);
+            self.settings.save(&mut self.system);
+        }
+        match action {
+            Some(PauseAction::Resume) => self.set_paused(false),
+            Some(PauseAction::Restart) => {
+                self.set_paused(false);
+                if self.score_holder.high_score > self.prev_high_score {
+                    self.on_high_score_updated();
+                }
+                self.start_game();
+            }
+            Some(PauseAction::Controls) => self.key_config = Some(KeyConfig::new(&self.settings.bindings)),
+            Some(PauseAction::QuitToTitle) => {
+                self.set_paused(false);
+                self.back_to_title();
+            }
+            None => {}
+        }
+    }
+
+    // Left/right key on the title screen.
+    // Changes the settings and saves them, or the overrides while they are set or a replay is played back.
+    fn change_game_option(&mut self, item: TitleItem, dir: i32) {
+        let mut config = self.current_game_config();
+        match item {
+            TitleItem::Difficulty => {
+                let all = &Difficulty::ALL;
+                let difficulty = config.difficulty().unwrap_or(Difficulty::Normal);
+                let index = all.iter().position(|&d| d == difficulty).unwrap();
+                let difficulty = all[(index as i32 + dir).rem_euclid(all.len() as i32) as usize];
+                config = GameConfig { starting_ships: config.starting_ships, ..GameConfig::preset(difficulty) };
+            }
+            TitleItem::Ships => {
+                let ships = config.starting_ships as i32 + dir;
+                config.starting_ships = ships.clamp(1, MAX_STARTING_SHIPS as i32) as u32;
+            }
+            _ => return,
+        }
+        self.count = 0;
+
+        match config.difficulty() {
+            Some(difficulty) if self.game_config.is_none() && !self.pad.is_playing_back() => {
+                self.settings.difficulty = difficulty;
+                self.settings.starting_lives = config.starting_ships;
+                self.settings.save(&mut self.system);
+            }
+            _ => self.game_config = Some(config),
+        }
+    }
+
+    // Settings in effect: the saved ones with the overridden window.
+    fn current_settings(&self) -> Settings {
+        let mut settings = self.settings.clone();
+        if let Some((scale, fullscreen)) = self.window {
+            settings.scale = scale;
+            settings.fullscreen = fullscreen;
+        }
+        settings
+    }
+
+    fn set_bindings(&mut self, bindings: Bindings) {
+        self.pad.set_bindings(&bindings);
+        self.pad2.set_bindings(&bindings);
+        self.settings.bindings = bindings;
+        self.settings.save(&mut self.system);
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.pause_menu = if paused { Some(PauseMenu::new()) } else { None };
+        self.key_config = None;
+        self.system.pause_audio(paused);
+        self.fps_calc.set_paused(paused);
+    }
+
+    fn start_demo(&mut self) {
+        self.demo_count = self.demo_count.wrapping_add(1);
+        let seed = self.fixed_seed().unwrap_or(self.game_seed).wrapping_add(self.demo_count);
+        self.demo = Some(Demo::new(self.stage_pack.clone(), seed, self.score_holder.high_score));
+        self.state = AppState::Demo;
+    }
+
+    // Explicit one or the one of the recording, otherwise each game draws a fresh seed.
+    fn fixed_seed(&self) -> Option<u64> {
+        self.seed.or_else(|| self.pad.recording_seed())
+    }
+
+    fn current_game_config(&self) -> GameConfig {
+        self.game_config.clone().unwrap_or_else(|| self.settings.game_config())
+    }
+
+    fn start_game(&mut self) {
+        let player_count = if self.play_mode == PlayMode::Coop { 2 } else { 1 };
+        let config = self.current_game_config();
+        let seed = self.fixed_seed().unwrap_or_else(|| rand::thread_rng().gen());
+        self.game_seed = seed;
+        let mut game_manager = GameManager::new(self.stage_pack.clone(), config.clone());
+        game_manager.restart(seed, player_count);
+        self.waiting_game_manager = None;
+        if self.play_mode == PlayMode::Alternate {
+            // Same seed for both players to be fair.
+            let mut game_manager2 = GameManager::new(self.stage_pack.clone(), config);
+            game_manager2.restart(seed, 1);
+            game_manager2.set_player_no(Some(1));
+            game_manager.set_player_no(Some(0));
+            self.waiting_game_manager = Some(game_manager2);
+        }
+        self.game_manager = Some(game_manager);
+        self.set_game_pad_source();
+        self.player_no = 0;
+        self.pending_name_entries.clear();
+        self.prev_high_score = self.score_holder.high_score;
+        self.score_holder.reset_score();
+        self.state = AppState::Game;
+        self.frame_count = 0;
+    }
+
+    // In co-op, player 2 uses `pad2` and `pad` reads only player 1's keys.
+    fn set_game_pad_source(&mut self) {
+        let source = if self.play_mode == PlayMode::Coop { PadSource::Player1 } else { PadSource::All };
+        self.pad.set_source(source);
+    }
+
+    // Hands the turn over to the other player, or back to the same one if the other is over.
+    fn switch_turn(&mut self) {
+        if let Some(waiting) = self.waiting_game_manager.as_mut() {
+            std::mem::swap(self.game_manager.as_mut().unwrap(), waiting);
+            self.swap_player();
+        }
+        self.game_manager.as_mut().unwrap().start_turn(&mut self.star_manager);
+    }
+
+    fn swap_player(&mut self) {
+        std::mem::swap(&mut self.score_holder.score, &mut self.score_holder.score2);
+        self.player_no ^= 1;
+    }
+
+    fn finish_game(&mut self) {
+        let stats = self.game_manager.take().unwrap().stats().clone();
+        self.pending_name_entries.push((self.player_no, self.score_holder.score, stats.stage_reached));
+        if self.play_mode == PlayMode::Coop {
+            self.pending_name_entries.push((1, self.score_holder.score2, stats.stage_reached));
+        }
+        self.last_game_stats = Some(stats);
+
+        // The other player goes on alone.
+        if let Some(game_manager) = self.waiting_game_manager.take() {
+            self.game_manager = Some(game_manager);
+            self.swap_player();
+            self.game_manager.as_mut().unwrap().start_turn(&mut self.star_manager);
+            return;
+        }
+
+        self.star_manager.set_stop(false);
+        self.pad.set_source(PadSource::All);
+        if self.next_name_entry() {
+            self.count = 0;
+            self.state = AppState::NameEntry;
+        } else {
+            self.back_to_title();
+        }
+    }
+
+    // Takes pending scores until one ranks in the table.
+    fn next_name_entry(&mut self) -> bool {
+        while !self.pending_name_entries.is_empty() {
+            let (player_no, score, stage) = self.pending_name_entries.remove(0);
+            if let Some(rank) = self.high_score_table.rank_of(score) {
+                self.name_entry = Some((player_no, NameEntry::new(rank, score, stage)));
+                return true;
+            }
+        }
+        false
+    }
+
+    fn player_scores(&self) -> [Option<u32>; 2] {
+        let mut scores = [None, None];
+        scores[self.player_no] = Some(self.score_holder.score);
+        if self.play_mode != PlayMode::OnePlayer {
+            scores[self.player_no ^ 1] = Some(self.score_holder.score2);
         }
+        scores
     }
 
     fn back_to_title(&mut self) {
         self.star_manager.set_stop(false);
 
+        if let Some(game_manager) = &self.game_manager {
+            self.last_game_stats = Some(game_manager.stats().clone());
+        }
+        // Quitting from the name entry registers the rest of names as is.
+        if self.state == AppState::NameEntry {
+            while let Some((_, name_entry)) = self.name_entry.take() {
+                self.high_score_table.insert(name_entry.entry());
+                self.next_name_entry();
+            }
+            self.high_score_table.save(&mut self.system);
+        }
+        self.pending_name_entries.clear();
+
         if self.score_holder.high_score > self.prev_high_score {
             self.on_high_score_updated();
         }
 
+        self.reset_to_title();
+    }
+
+    fn reset_to_title(&mut self) {
         self.state = AppState::Title;
         self.count = 0;
         self.game_manager = None;
+        self.waiting_game_manager = None;
+        self.demo = None;
+        self.key_config = None;
+        self.star_manager.set_stop(false);
+        self.pad.set_source(PadSource::All);
     }
 
     fn on_high_score_updated(&mut self) {
@@ -206,17 +918,37 @@ impl<T: TimerTrait, S: SystemTrait> GalanguaApp<T, S> {
 impl<R: RendererTrait, T: TimerTrait, S: SystemTrait> AppTrait<R> for GalanguaApp<T, S> {
     fn on_key(&mut self, vkey: VKey, down: bool) {
         self.pad.on_key(vkey, down);
+        self.pad2.on_key(vkey, down);
+        if let Some(key_config) = &mut self.key_config {
+            key_config.on_key(vkey, down);
+        }
         if down {
             self.pressed_key = Some(vkey);
         }
     }
 
-    fn on_joystick_axis(&mut self, axis_index: u8, dir: i8) {
-        self.pad.on_joystick_axis(axis_index, dir);
+    fn on_joystick_axis(&mut self, axis_index: u8, value: i16) {
+        self.pad.on_joystick_axis(axis_index, value);
+        self.pad2.on_joystick_axis(axis_index, value);
+        if let Some(key_config) = &mut self.key_config {
+            key_config.on_joystick_axis(axis_index, value);
+        }
+    }
+
+    fn on_joystick_hat(&mut self, hat_index: u8, x: i8, y: i8) {
+        self.pad.on_joystick_hat(hat_index, x, y);
+        self.pad2.on_joystick_hat(hat_index, x, y);
+        if let Some(key_config) = &mut self.key_config {
+            key_config.on_joystick_hat(hat_index, x, y);
+        }
     }
 
     fn on_joystick_button(&mut self, button_index: u8, down: bool) {
         self.pad.on_joystick_button(button_index, down);
+        self.pad2.on_joystick_button(button_index, down);
+        if let Some(key_config) = &mut self.key_config {
+            key_config.on_joystick_button(button_index, down);
+        }
     }
 
     fn init(&mut self, renderer: &mut R)
@@ -228,7 +960,21 @@ impl<R: RendererTrait, T: TimerTrait, S: SystemTrait> AppTrait<R> for GalanguaAp
     }
 
     fn update(&mut self) -> bool {
-        self.pad.update();
+        if self.pause_menu.is_some() {
+            // Not recorded, the game doesn't go on.
+            self.pad.update_live();
+            let result = self.update_main();
+            self.pressed_key = None;
+            return result;
+        }
+
+        // Player 2's input is recorded and played back along with player 1's.
+        let mut bits = None;
+        if let Some(mut pilot) = self.pilot.take() {
+            bits = Some(pilot.update(&self.observe()));
+            self.pilot = Some(pilot);
+        }
+        self.pad.update_pads(bits, Some(&mut self.pad2));
         let result = self.update_main();
         self.pressed_key = None;
         result
@@ -247,19 +993,390 @@ impl<R: RendererTrait, T: TimerTrait, S: SystemTrait> AppTrait<R> for GalanguaAp
     }
 }
 
+fn draw_high_score_table<R: RendererTrait>(renderer: &mut R, table: &HighScoreTable) {
+    renderer.set_texture_color_mod("font", 255, 0, 0);
+    renderer.draw_str("font", 4 * 8, 5 * 8, "THE GALACTIC HEROES");
+    renderer.set_texture_color_mod("font", 0, 255, 255);
+    renderer.draw_str("font", 8 * 8, 8 * 8, "SCORE  STAGE NAME");
+    renderer.set_texture_color_mod("font", 255, 255, 255);
+    for (i, entry) in table.entries().iter().enumerate() {
+        renderer.draw_str("font", 2 * 8, (10 + i as i32 * 2) * 8,
+                          &format!("{:>4} {:>7}  {:>3}   {}", ordinal(i), entry.score, entry.stage, entry.name));
+    }
+}
+
+// `scores` are of 1UP and 2UP, `hides` blinks the labels.
 fn draw_scores<R: RendererTrait>(
-    renderer: &mut R, score_holder: &ScoreHolder, show_1up: bool
+    renderer: &mut R, score_holder: &ScoreHolder, scores: &[Option<u32>; 2], hides: &[bool; 2]
 ) {
-    renderer.set_texture_color_mod("font", 255, 0, 0);
-    if show_1up {
-        renderer.draw_str("font", 2 * 8, 0 * 8, "1UP");
+    const MAX_DISP_SCORE: u32 = 9999999;
+    const COLUMNS: [i32; 2] = [0, 21];
+
+    for (i, score) in scores.iter().enumerate() {
+        if let Some(score) = score {
+            if !hides[i] {
+                renderer.set_texture_color_mod("font", 255, 0, 0);
+                renderer.draw_str("font", (COLUMNS[i] + 2) * 8, 0 * 8, &format!("{}UP", i + 1));
+            }
+            let score = std::cmp::min(*score, MAX_DISP_SCORE);
+            renderer.set_texture_color_mod("font", 255, 255, 255);
+            renderer.draw_str("font", COLUMNS[i] * 8, 1 * 8, &format!("{:6}0", score / 10));
+        }
     }
+
+    renderer.set_texture_color_mod("font", 255, 0, 0);
     renderer.draw_str("font", 9 * 8, 0 * 8, "HIGH SCORE");
     renderer.set_texture_color_mod("font", 255, 255, 255);
-
-    const MAX_DISP_SCORE: u32 = 9999999;
-    let score = std::cmp::min(score_holder.score, MAX_DISP_SCORE);
-    renderer.draw_str("font", 0 * 8, 1 * 8, &format!("{:6}0", score / 10));
     let high_score = std::cmp::min(score_holder.high_score, MAX_DISP_SCORE);
     renderer.draw_str("font", 10 * 8, 1 * 8, &format!("{:6}0", high_score / 10));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::pilot::HeuristicPilot;
+    use crate::headless::{HeadlessRenderer, HeadlessSystem, HeadlessTimer};
+
+    type App = GalanguaApp<HeadlessTimer, HeadlessSystem>;
+
+    fn run(app: &mut App, frame_from: u32, frame_to: u32) {
+        for frame in frame_from..frame_to {
+            AppTrait::<HeadlessRenderer>::on_key(app, VKey::Space, frame % 8 == 0);
+            AppTrait::<HeadlessRenderer>::on_key(app, VKey::Left, frame % 200 < 50);
+            // Player 2 in co-op.
+            AppTrait::<HeadlessRenderer>::on_key(app, VKey::W, frame % 8 == 4);
+            AppTrait::<HeadlessRenderer>::on_key(app, VKey::D, frame % 200 >= 100 && frame % 200 < 130);
+            AppTrait::<HeadlessRenderer>::update(app);
+        }
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let mut app = App::new(HeadlessTimer::new(), HeadlessSystem::new());
+        app.set_seed(1);
+        assert_eq!(None, app.save_snapshot());
+
+        run(&mut app, 0, 600);
+        let json = app.save_snapshot().unwrap();
+        run(&mut app, 600, 1200);
+
+        let mut restored = App::new(HeadlessTimer::new(), HeadlessSystem::new());
+        restored.load_snapshot(&json).unwrap();
+        run(&mut restored, 600, 1200);
+
+        assert_eq!(app.save_snapshot(), restored.save_snapshot());
+
+        // Older one lacks the dynamic rank.
+        let old = json.replacen(&format!("\"version\":{}", SNAPSHOT_VERSION), "\"version\":3", 1);
+        assert_ne!(json, old);
+        assert!(restored.load_snapshot(&old).is_err());
+    }
+
+    #[test]
+    fn test_quick_save_only_in_game() {
+        fn press(app: &mut App, vkey: VKey) {
+            AppTrait::<HeadlessRenderer>::on_key(app, vkey, true);
+            AppTrait::<HeadlessRenderer>::update(app);
+            AppTrait::<HeadlessRenderer>::on_key(app, vkey, false);
+        }
+
+        let mut app = App::new(HeadlessTimer::new(), HeadlessSystem::new());
+        app.set_seed(1);
+        press(&mut app, VKey::F5);
+        assert_eq!(None, app.quick_save);
+
+        run(&mut app, 0, 300);
+        press(&mut app, VKey::F5);
+        assert!(app.quick_save.is_some());
+
+        app.back_to_title();
+        press(&mut app, VKey::F9);
+        assert_eq!(AppState::Title, app.state);
+    }
+
+    #[test]
+    fn test_seed_for_each_game() {
+        let mut app = App::new(HeadlessTimer::new(), HeadlessSystem::new());
+        run(&mut app, 0, 1);
+        assert_eq!(AppState::Game, app.state);
+        let seed = app.seed();
+        app.back_to_title();
+        run(&mut app, 1, 2);  // Releases the key.
+        run(&mut app, 0, 1);
+        assert_ne!(seed, app.seed());
+
+        // Games in a recording use the seed of the replay.
+        app.start_recording();
+        run(&mut app, 0, 1);
+        let seed = app.seed();
+        app.back_to_title();
+        run(&mut app, 1, 2);  // Releases the key.
+        run(&mut app, 0, 1);
+        assert_eq!(seed, app.seed());
+        assert_eq!(seed, app.take_replay().unwrap().seed);
+    }
+
+    #[test]
+    fn test_pause_keeps_replay() {
+        fn press(app: &mut App, vkey: VKey) {
+            AppTrait::<HeadlessRenderer>::on_key(app, vkey, true);
+            AppTrait::<HeadlessRenderer>::update(app);
+            AppTrait::<HeadlessRenderer>::on_key(app, vkey, false);
+        }
+
+        let mut app = App::new(HeadlessTimer::new(), HeadlessSystem::new());
+        app.set_seed(1);
+        app.start_recording();
+        run(&mut app, 0, 300);
+        press(&mut app, VKey::Escape);
+        assert!(app.is_paused());
+        let frame_count = app.frame_count;
+        press(&mut app, VKey::Down);
+        press(&mut app, VKey::Up);
+        press(&mut app, VKey::Escape);
+        assert!(!app.is_paused());
+        assert_eq!(frame_count, app.frame_count);
+        run(&mut app, 300, 900);
+        let snapshot = app.save_snapshot();
+
+        // Paused frames are not recorded.
+        let replay = app.take_replay().unwrap();
+        assert_eq!(300 + 1 + 600, replay.len());
+        let mut replayed = App::new(HeadlessTimer::new(), HeadlessSystem::new());
+        replayed.start_replay(replay);
+        for _ in 0..901 {
+            AppTrait::<HeadlessRenderer>::update(&mut replayed);
+        }
+        assert_eq!(snapshot, replayed.save_snapshot());
+    }
+
+    #[test]
+    fn test_attract() {
+        let mut app = App::new(HeadlessTimer::new(), HeadlessSystem::new());
+        app.set_seed(1);
+        for _ in 0..ATTRACT_PAGE_FRAMES {
+            AppTrait::<HeadlessRenderer>::update(&mut app);
+        }
+        assert_eq!(AppState::Demo, app.state);
+
+        // Demo ends by itself, then the high score table.
+        let mut frame = 0;
+        while app.state == AppState::Demo {
+            assert!(frame < 60 * 60);
+            AppTrait::<HeadlessRenderer>::update(&mut app);
+            frame += 1;
+        }
+        assert_eq!(AppState::Title, app.state);
+        assert!(app.count > ATTRACT_PAGE_FRAMES);
+        assert_eq!(0, app.score());
+
+        // Any key returns to the title.
+        app.count = ATTRACT_PAGE_FRAMES - 1;
+        AppTrait::<HeadlessRenderer>::update(&mut app);
+        assert_eq!(AppState::Demo, app.state);
+        AppTrait::<HeadlessRenderer>::on_key(&mut app, VKey::Z, true);
+        AppTrait::<HeadlessRenderer>::update(&mut app);
+        assert_eq!(AppState::Title, app.state);
+        assert_eq!(0, app.count);
+    }
+
+    #[test]
+    fn test_pilot() {
+        let mut app = App::new(HeadlessTimer::new(), HeadlessSystem::new());
+        app.set_seed(1);
+        assert!(!app.observe().in_game);
+
+        app.set_pilot(Some(Box::new(HeuristicPilot::new())));
+        for _ in 0..1200 {
+            AppTrait::<HeadlessRenderer>::update(&mut app);
+        }
+        assert_eq!(AppState::Game, app.state);
+        assert!(app.score() > 0);
+
+        let observation = app.observe();
+        assert!(observation.in_game);
+        assert_eq!(1, observation.stage);
+        assert!(!observation.enemies.is_empty());
+    }
+
+    #[test]
+    fn test_coop() {
+        let mut app = App::new(HeadlessTimer::new(), HeadlessSystem::new());
+        app.set_seed(1);
+        app.set_play_mode(PlayMode::Coop);
+
+        run(&mut app, 0, 600);
+        assert_eq!(AppState::Game, app.state);
+        assert!(app.score_holder.score > 0);
+        assert!(app.score_holder.score2 > 0);
+
+        let json = app.save_snapshot().unwrap();
+        run(&mut app, 600, 1200);
+
+        let mut restored = App::new(HeadlessTimer::new(), HeadlessSystem::new());
+        restored.load_snapshot(&json).unwrap();
+        run(&mut restored, 600, 1200);
+
+        assert_eq!(app.save_snapshot(), restored.save_snapshot());
+    }
+
+    #[test]
+    fn test_game_options_saved() {
+        fn press(app: &mut App, vkey: VKey) {
+            AppTrait::<HeadlessRenderer>::on_key(app, vkey, true);
+            AppTrait::<HeadlessRenderer>::update(app);
+            AppTrait::<HeadlessRenderer>::on_key(app, vkey, false);
+        }
+        let difficulty_cursor = TITLE_ITEMS.iter().position(|&item| item == TitleItem::Difficulty).unwrap();
+        let saved = |app: &App| {
+            let settings = Settings::load(&app.system);
+            (settings.difficulty, settings.starting_lives)
+        };
+        let default_ships = Settings::default().starting_lives;
+
+        let mut app = App::new(HeadlessTimer::new(), HeadlessSystem::new());
+        app.title_cursor = difficulty_cursor;
+        press(&mut app, VKey::Right);
+        assert_eq!((Difficulty::Hard, default_ships), saved(&app));
+
+        // Overrides from the command line are changed instead, and not saved.
+        app.set_starting_ships(5);
+        AppTrait::<HeadlessRenderer>::update(&mut app);  // Releases the key.
+        press(&mut app, VKey::Right);
+        assert_eq!(Some(Difficulty::Arcade), app.current_game_config().difficulty());
+        assert_eq!(5, app.current_game_config().starting_ships);
+        assert_eq!((Difficulty::Hard, default_ships), saved(&app));
+
+        // Nor by the input of a replay.
+        let mut recorder = App::new(HeadlessTimer::new(), HeadlessSystem::new());
+        recorder.title_cursor = difficulty_cursor;
+        recorder.start_recording();
+        press(&mut recorder, VKey::Left);
+        let mut replay = recorder.take_replay().unwrap();
+        replay.config = None;
+
+        let mut replayed = App::new(HeadlessTimer::new(), HeadlessSystem::new());
+        replayed.title_cursor = difficulty_cursor;
+        replayed.start_replay(replay);
+        AppTrait::<HeadlessRenderer>::update(&mut replayed);
+        assert_eq!(Some(Difficulty::Easy), replayed.current_game_config().difficulty());
+        assert_eq!((Difficulty::Normal, default_ships), saved(&replayed));
+    }
+
+    #[test]
+    fn test_window_not_saved() {
+        fn press(app: &mut App, vkey: VKey) {
+            AppTrait::<HeadlessRenderer>::on_key(app, vkey, true);
+            AppTrait::<HeadlessRenderer>::update(app);
+            AppTrait::<HeadlessRenderer>::on_key(app, vkey, false);
+            AppTrait::<HeadlessRenderer>::update(app);
+        }
+        let saved = |app: &App| Settings::load(&app.system);
+        let default = Settings::default();
+
+        let mut app = App::new(HeadlessTimer::new(), HeadlessSystem::new());
+        app.set_window(100, true);
+        assert_eq!((MAX_SCALE, true), (app.current_settings().scale, app.current_settings().fullscreen));
+        run(&mut app, 0, 60);
+        press(&mut app, VKey::Escape);
+        press(&mut app, VKey::Down);
+        press(&mut app, VKey::Down);
+        press(&mut app, VKey::Space);  // Options.
+
+        // Changing the volume doesn't save the window.
+        press(&mut app, VKey::Left);
+        let settings = saved(&app);
+        assert_eq!(default.volume - 1, settings.volume);
+        assert_eq!((default.scale, false), (settings.scale, settings.fullscreen));
+        assert_eq!(MAX_SCALE, app.current_settings().scale);
+
+        // Chosen in the options, the window is saved.
+        press(&mut app, VKey::Down);
+        press(&mut app, VKey::Left);
+        let settings = saved(&app);
+        assert_eq!((MAX_SCALE - 1, true), (settings.scale, settings.fullscreen));
+        assert_eq!(None, app.window);
+    }
+
+    #[test]
+    fn test_replay_game_config() {
+        let mut app = App::new(HeadlessTimer::new(), HeadlessSystem::new());
+        app.set_seed(1);
+        app.set_difficulty(Difficulty::Hard);
+        app.set_starting_ships(5);
+        app.start_recording();
+        run(&mut app, 0, 900);
+        let snapshot = app.save_snapshot();
+        assert!(snapshot.is_some());
+
+        // Played back by the rules of the replay, not of the settings.
+        let mut replayed = App::new(HeadlessTimer::new(), HeadlessSystem::new());
+        replayed.start_replay(app.take_replay().unwrap());
+        for _ in 0..900 {
+            AppTrait::<HeadlessRenderer>::update(&mut replayed);
+        }
+        assert_eq!(snapshot, replayed.save_snapshot());
+    }
+
+    #[test]
+    fn test_coop_replay() {
+        let mut app = App::new(HeadlessTimer::new(), HeadlessSystem::new());
+        app.set_seed(1);
+        app.set_play_mode(PlayMode::Coop);
+        app.start_recording();
+        run(&mut app, 0, 900);
+        assert!(app.score_holder.score2 > 0);
+        let snapshot = app.save_snapshot();
+
+        // Player 2's input is played back, not read from the keys.
+        let mut replayed = App::new(HeadlessTimer::new(), HeadlessSystem::new());
+        replayed.set_play_mode(PlayMode::Coop);
+        replayed.start_replay(app.take_replay().unwrap());
+        for _ in 0..900 {
+            AppTrait::<HeadlessRenderer>::update(&mut replayed);
+        }
+        assert_eq!(snapshot, replayed.save_snapshot());
+    }
+
+    #[test]
+    fn test_two_players_alternate() {
+        let mut app = App::new(HeadlessTimer::new(), HeadlessSystem::new());
+        app.set_seed(1);
+        app.set_play_mode(PlayMode::Alternate);
+        run(&mut app, 0, 1);
+        assert_eq!(AppState::Game, app.state);
+
+        // Turn switches to player 2 on the first death, player 1 keeps the score.
+        let mut frame = 1;
+        while app.player_no() == 0 {
+            assert!(frame < 60 * 60);
+            let before = frame;
+            frame += 1;
+            run(&mut app, before, frame);
+        }
+        assert_eq!(0, app.score());
+        assert!(app.score_holder.score2 > 0);
+        let json = app.save_snapshot().unwrap();
+
+        let mut restored = App::new(HeadlessTimer::new(), HeadlessSystem::new());
+        restored.load_snapshot(&json).unwrap();
+        assert_eq!(1, restored.player_no());
+        assert_eq!(json, restored.save_snapshot().unwrap());
+
+        // After a game over, the other player goes on alone, and both scores are entered.
+        let mut scores = app.player_scores();
+        while app.state == AppState::Game {
+            assert!(frame < 60 * 60 * 30);
+            scores = app.player_scores();
+            let before = frame;
+            frame += 1;
+            run(&mut app, before, frame);
+        }
+        assert_eq!(AppState::NameEntry, app.state);
+        let (player_no, name_entry) = app.name_entry.as_ref().unwrap();
+        let mut entries = vec![(*player_no, name_entry.entry().score)];
+        entries.extend(app.pending_name_entries.iter().map(|&(player_no, score, _)| (player_no, score)));
+        entries.sort();
+        assert_eq!(vec![(0, scores[0].unwrap()), (1, scores[1].unwrap())], entries);
+    }
+}
@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+
+use crate::util::math::ONE;
+
+const STEP: i32 = 60;  // Steps in a rank unit: a frame alive raises one.
+const SHOT_COST: i32 = STEP / 4;  // Neutral at 25% hit ratio.
+const HIT_GAIN: i32 = STEP;
+const DEATH_DROP: i32 = ONE / 16 * STEP;
+
+// Rank by the play, added to the rank of the stage within -max..=max:
+// rises while alive (twice with a dual fighter) and by accurate shooting,
+// and falls after a death, losing half of the gain.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DynamicRank {
+    value: i32,  // In steps.
+    max: i32,    // In rank units, 0 to disable.
+}
+
+impl DynamicRank {
+    pub fn new(max: i32) -> Self {
+        Self { value: 0, max }
+    }
+
+    pub fn value(&self) -> i32 {
+        self.value / STEP
+    }
+
+    pub fn update(&mut self, dual: bool) {
+        self.add(if dual { 2 } else { 1 });
+    }
+
+    pub fn on_shot(&mut self, count: u32) {
+        self.add(-SHOT_COST * count as i32);
+    }
+
+    pub fn on_hit(&mut self) {
+        self.add(HIT_GAIN);
+    }
+
+    pub fn on_dead(&mut self) {
+        let gain = std::cmp::max(self.value, 0);
+        self.add(-gain / 2 - DEATH_DROP);
+    }
+
+    fn add(&mut self, diff: i32) {
+        let max = self.max * STEP;
+        self.value = (self.value + diff).clamp(-max, max);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rise_and_fall() {
+        let mut rank = DynamicRank::new(ONE / 4);
+        for _ in 0..10 * 60 {
+            rank.update(false);
+        }
+        assert_eq!(10, rank.value());
+        for _ in 0..5 * 60 {
+            rank.update(true);
+        }
+        assert_eq!(20, rank.value());
+        rank.on_shot(4);
+        rank.on_hit();
+        assert_eq!(20, rank.value());
+
+        rank.on_dead();
+        assert_eq!(10 - ONE / 16, rank.value());
+        for _ in 0..10 {
+            rank.on_dead();
+        }
+        assert_eq!(-ONE / 4, rank.value());
+
+        let mut disabled = DynamicRank::new(0);
+        disabled.update(true);
+        assert_eq!(0, disabled.value());
+    }
+}
@@ -1,6 +1,7 @@
 use array_macro::*;
 use rand::{Rng, SeedableRng};
 use rand_xoshiro::Xoshiro128Plus;
+use serde::{Deserialize, Serialize};
 
 use crate::app::consts;
 use crate::framework::types::Vec2I;
@@ -9,23 +10,27 @@ use crate::util::math::{round_up, ONE};
 
 const STAR_COUNT: usize = 256;
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Serialize, Deserialize)]
 enum State {
     Stop,
     Normal,
     Capturing,
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct StarManager {
+    rng: Xoshiro128Plus,
     state: State,
     frame_count: i32,
     scroll_vel: i32,
+    #[serde(with = "crate::util::serde_array")]
     stars: [Star; STAR_COUNT],
 }
 
 impl StarManager {
-    pub fn new() -> Self {
-        let mut rng = Xoshiro128Plus::from_seed(rand::thread_rng().gen());
+    // Stars have their own random sequence, not to disturb the game's one.
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Xoshiro128Plus::seed_from_u64(seed);
         let stars = array![|_i|
             Star {
                 pos: Vec2I::new(rng.gen_range(0, consts::WIDTH) * ONE,
@@ -36,6 +41,7 @@ impl StarManager {
         ; STAR_COUNT];
 
         Self {
+            rng,
             state: State::Normal,
             frame_count: 0,
             scroll_vel: 0,
@@ -50,7 +56,7 @@ impl StarManager {
         }
 
         let capturing = self.state == State::Capturing;
-        let mut rng = Xoshiro128Plus::from_seed(rand::thread_rng().gen());
+        let rng = &mut self.rng;
         let vy = if capturing { -3 * ONE } else { self.scroll_vel };
         for star in self.stars.iter_mut() {
             let mut y = star.pos.y + vy;
@@ -65,7 +71,7 @@ impl StarManager {
             star.pos.y = y;
             if warp {
                 star.pos.x = rng.gen_range(0, consts::WIDTH) * ONE;
-                star.c = choose_random_color(&mut rng);
+                star.c = choose_random_color(rng);
                 star.t = rng.gen_range(0, 64);
             }
         }
@@ -103,6 +109,7 @@ impl StarManager {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 struct Star {
     pos: Vec2I,
     t: i32,
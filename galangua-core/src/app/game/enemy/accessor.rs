@@ -1,15 +1,23 @@
 // Accessor of game information for Enemy.
 
-use super::{Enemy, FormationIndex};
+use rand_xoshiro::Xoshiro128Plus;
+use std::rc::Rc;
 
-use crate::app::game::CaptureState;
+use super::{Enemy, FormationIndex, StagePack};
+
+use crate::app::consts::*;
+use crate::app::game::{CaptureState, GameConfig};
 use crate::framework::types::Vec2I;
+use crate::util::math::ONE;
 
+// Players are indexed by `player_no`, there are two of them in simultaneous play.
 pub trait Accessor {
-    fn get_raw_player_pos(&self) -> &Vec2I;
-    fn get_dual_player_pos(&self) -> Option<Vec2I>;
-    fn is_player_dual(&self) -> bool;
-    fn can_player_capture(&self) -> bool;
+    fn player_count(&self) -> usize;
+    // `None` if the player is out of the game.
+    fn get_raw_player_pos(&self, player_no: usize) -> Option<&Vec2I>;
+    fn get_dual_player_pos(&self, player_no: usize) -> Option<Vec2I>;
+    fn is_player_dual(&self, player_no: usize) -> bool;
+    fn can_player_capture(&self, player_no: usize) -> bool;
     fn is_player_capture_completed(&self) -> bool;
     fn capture_state(&self) -> CaptureState;
     fn captured_fighter_index(&self) -> Option<FormationIndex>;
@@ -20,4 +28,32 @@ pub trait Accessor {
     fn pause_enemy_shot(&mut self, wait: u32);
     fn is_rush(&self) -> bool;
     fn get_stage_no(&self) -> u16;
+    fn rng(&mut self) -> &mut Xoshiro128Plus;
+    fn stage_pack(&self) -> &Rc<StagePack>;
+    fn game_config(&self) -> &GameConfig;
+
+    // 0 to ONE, enemies dive more often and faster and shoot faster as it rises.
+    fn rank(&self) -> i32 {
+        self.game_config().rank(self.get_stage_no())
+    }
+
+    // Player horizontally nearest to `pos`.
+    fn nearest_player_pos(&self, pos: &Vec2I) -> Vec2I {
+        (0..self.player_count())
+            .filter_map(|player_no| self.get_raw_player_pos(player_no))
+            .min_by_key(|player_pos| (player_pos.x - pos.x).abs())
+            .copied()
+            .unwrap_or_else(|| Vec2I::new(WIDTH / 2 * ONE, (HEIGHT - 16 - 8) * ONE))
+    }
+
+    // Fighters including dual ones, to be aimed at.
+    fn player_target_positions(&self) -> Vec<Vec2I> {
+        (0..self.player_count())
+            .flat_map(|player_no| {
+                let raw_pos = self.get_raw_player_pos(player_no).copied();
+                let dual_pos = raw_pos.and(self.get_dual_player_pos(player_no));
+                raw_pos.into_iter().chain(dual_pos)
+            })
+            .collect()
+    }
 }
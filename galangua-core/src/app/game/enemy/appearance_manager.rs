@@ -1,36 +1,34 @@
 use rand::seq::SliceRandom;
-use rand::{Rng, SeedableRng};
+use rand::Rng;
 use rand_xoshiro::Xoshiro128Plus;
+use serde::{Deserialize, Serialize};
 use std::cmp::min;
 
-use super::appearance_table::*;
 use super::enemy::{Enemy, EnemyState, EnemyType};
-use super::traj::Traj;
-use super::traj_command::TrajCommand;
+use super::stage_pack::{StagePack, UnitEntry, UNIT_COUNT};
+use super::traj::{Traj, TrajTable};
 use super::FormationIndex;
 
-use crate::app::util::unsafe_util::extend_lifetime;
 use crate::framework::types::{Vec2I, ZERO_VEC};
 use crate::util::math::ONE;
 
 const ASSAULT_FORMATION_Y: u8 = 6;
-const UNIT_COUNT: u32 = 5;
 const STEP_WAIT: u32 = 16 / 3;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 struct Info {
     time: u32,
     enemy_type: EnemyType,
     fi: FormationIndex,
     offset: Vec2I,
     flip_x: bool,
-    traj_table: &'static [TrajCommand],
+    traj_table: TrajTable,
     shot_enable: bool,
 }
 
 impl Info {
     pub fn new(time: u32, enemy_type: EnemyType, fi: FormationIndex, offset: Vec2I, flip_x: bool,
-               traj_table: &'static [TrajCommand]) -> Self {
+               traj_table: TrajTable) -> Self {
         Self {
             time, enemy_type, fi, offset, flip_x, traj_table,
             shot_enable: false,
@@ -38,6 +36,7 @@ impl Info {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct AppearanceManager {
     stage: u16,
     paused: bool,
@@ -47,8 +46,10 @@ pub struct AppearanceManager {
     time: u32,
     pub(super) done: bool,
     orders: Vec<Info>,
-    orders_ptr: &'static [Info],
+    order_index: usize,
     captured_fighter: Option<FormationIndex>,
+    #[serde(default)]
+    challenging: bool,
 }
 
 impl AppearanceManager {
@@ -62,8 +63,9 @@ impl AppearanceManager {
             time: 0,
             done: true,
             orders: Vec::new(),
-            orders_ptr: &[],
+            order_index: 0,
             captured_fighter: None,
+            challenging: false,
         }
     }
 
@@ -78,15 +80,19 @@ impl AppearanceManager {
         self.paused = value;
     }
 
-    pub fn update(&mut self, enemies: &[Option<Enemy>]) -> Option<Vec<Enemy>> {
+    pub fn update(
+        &mut self, enemies: &[Option<Enemy>], stage_pack: &StagePack, rng: &mut Xoshiro128Plus,
+    ) -> Option<Vec<Enemy>> {
         if self.done {
             return None;
         }
 
-        self.update_main(enemies)
+        self.update_main(enemies, stage_pack, rng)
     }
 
-    fn update_main(&mut self, enemies: &[Option<Enemy>]) -> Option<Vec<Enemy>> {
+    fn update_main(
+        &mut self, enemies: &[Option<Enemy>], stage_pack: &StagePack, rng: &mut Xoshiro128Plus,
+    ) -> Option<Vec<Enemy>> {
         if self.wait > 0 {
             self.wait -= 1;
             return None;
@@ -99,45 +105,48 @@ impl AppearanceManager {
                 }
                 self.wait_stationary = false;
             }
-            if self.unit >= UNIT_COUNT {
+            if self.unit >= UNIT_COUNT as u32 {
                 self.done = true;
                 return None;
             }
 
             if self.orders.is_empty() {
-                self.set_orders();
-                // orders is owned by vec, so it lives as long as self and not worry about that.
-                self.orders_ptr = unsafe { extend_lifetime(&self.orders) };
+                self.set_orders(stage_pack, rng);
+                self.order_index = 0;
 
                 self.time = 0;
             }
         }
 
-        if self.orders.is_empty() || self.orders_ptr[0].time < self.time {
+        if self.orders.is_empty() || self.orders[self.order_index].time < self.time {
             return None
         }
 
         let mut new_borns = Vec::new();
-        while self.orders_ptr[0].time == self.time {
-            let p = &self.orders_ptr[0];
+        while self.orders[self.order_index].time == self.time {
+            let p = &self.orders[self.order_index];
             let mut enemy = Enemy::new(p.enemy_type, &ZERO_VEC, 0, 0);
 
-            let mut traj = Traj::new(p.traj_table, &p.offset, p.flip_x, p.fi);
+            let mut traj = Traj::new(p.traj_table.clone(), &p.offset, p.flip_x, p.fi);
             traj.shot_enable = p.shot_enable;
-            enemy.set_appearance(traj);
+            if self.challenging {
+                enemy.set_fly_through(traj);
+            } else {
+                enemy.set_appearance(traj);
+            }
             enemy.formation_index = p.fi;
 
             new_borns.push(enemy);
 
-            self.orders_ptr = &self.orders_ptr[1..];
-            if self.orders_ptr.is_empty() {
+            self.order_index += 1;
+            if self.order_index >= self.orders.len() {
                 break;
             }
         }
 
         self.time += 1;
-        if self.orders_ptr.is_empty() {
-            self.orders_ptr = &[];
+        if self.order_index >= self.orders.len() {
+            self.order_index = 0;
             self.orders.clear();
 
             self.unit += 1;
@@ -149,15 +158,31 @@ impl AppearanceManager {
         Some(new_borns)
     }
 
-    fn set_orders(&mut self) {
-        self.create_orders();
-        self.set_shot_enables();
+    fn set_orders(&mut self, stage_pack: &StagePack, rng: &mut Xoshiro128Plus) {
+        // Challenging stage: No assault, no shot, and captured fighter waits for the next stage.
+        self.challenging = stage_pack.challenging_stage(self.stage).is_some();
+        self.create_orders(stage_pack, rng);
+        if !self.challenging {
+            self.set_shot_enables(stage_pack, rng);
+        }
+    }
+
+    fn unit_entry<'a>(&self, stage_pack: &'a StagePack) -> (&'a UnitEntry, &'a [EnemyType]) {
+        let unit = self.unit as usize;
+        if let Some(challenging_stage) = stage_pack.challenging_stage(self.stage) {
+            (&challenging_stage.units[unit], &challenging_stage.enemy_types[unit * 2..unit * 2 + 2])
+        } else {
+            let units = &stage_pack.units;
+            (&units[(self.stage as usize) % units.len()][unit], &stage_pack.enemy_types[unit * 2..unit * 2 + 2])
+        }
     }
 
-    fn create_orders(&mut self) {
+    fn create_orders(&mut self, stage_pack: &StagePack, rng: &mut Xoshiro128Plus) {
         let base = self.unit * 8;
-        let entry = &UNIT_TABLE[(self.stage as usize) % UNIT_TABLE.len()][self.unit as usize];
-        let assault_count = ASSAULT_TABLE[min(self.stage as usize, ASSAULT_TABLE.len() - 1)][self.unit as usize] as usize;
+        let (entry, _) = self.unit_entry(stage_pack);
+        let assault = &stage_pack.assault;
+        let assault_count = assault[min(self.stage as usize, assault.len() - 1)][self.unit as usize] as usize;
+        let order = &stage_pack.order;
 
         let div;
         match entry.pat {
@@ -165,24 +190,24 @@ impl AppearanceManager {
                 let flip = if entry.flip_x { 1 } else { 0 };
                 for count in 0..8 {
                     let side = count & 1;
-                    let fi = ORDER[(base + (count / 2 + (side ^ flip) * 4)) as usize];
-                    let info = self.create_info(fi, count);
+                    let fi = order[(base + (count / 2 + (side ^ flip) * 4)) as usize];
+                    let info = self.create_info(stage_pack, fi, count);
                     self.orders.push(info);
                 }
                 div = 2;
             }
             1 => {
                 for count in 0..8 {
-                    let fi = ORDER[(base + (count / 2 + (count & 1) * 4)) as usize];
-                    let info = self.create_info(fi, count);
+                    let fi = order[(base + (count / 2 + (count & 1) * 4)) as usize];
+                    let info = self.create_info(stage_pack, fi, count);
                     self.orders.push(info);
                 }
                 div = 1;
             }
             2 => {
                 for count in 0..8 {
-                    let fi = ORDER[(base + count) as usize];
-                    let info = self.create_info(fi, count);
+                    let fi = order[(base + count) as usize];
+                    let info = self.create_info(stage_pack, fi, count);
                     self.orders.push(info);
                 }
                 div = 1;
@@ -191,8 +216,8 @@ impl AppearanceManager {
                 let flip = if entry.flip_x { 1 } else { 0 };
                 for count in 0..8 {
                     let side = count & 1;
-                    let fi = ORDER[(base + (count / 2 + (side ^ flip) * 4)) as usize];
-                    let info = self.create_info(fi, count);
+                    let fi = order[(base + (count / 2 + (side ^ flip) * 4)) as usize];
+                    let info = self.create_info(stage_pack, fi, count);
                     self.orders.push(info);
                 }
                 div = 2;
@@ -204,58 +229,56 @@ impl AppearanceManager {
             }
         }
 
-        if assault_count > 0 {
-            let mut rng = Xoshiro128Plus::from_seed(rand::thread_rng().gen());
+        if assault_count > 0 && !self.challenging {
             let mut assault_index = 0;
             for i in 0..assault_count * 2 {
                 let lr = i & 1;
                 let n = self.orders.len() / 2;
                 let index = rng.gen_range(0, n + 1);
-                self.orders.push(self.orders[lr]);
+                self.orders.push(self.orders[lr].clone());
                 // Shift
                 for j in 0..(n - index) {
-                    self.orders[(n - j) * 2 + lr] = self.orders[(n - j - 1) * 2 + lr];
+                    self.orders[(n - j) * 2 + lr] = self.orders[(n - j - 1) * 2 + lr].clone();
                 }
 
                 let fi = gen_assault_index(assault_index);
                 let ins = index * 2 + lr;
-                self.orders[ins] = self.create_info(fi, ins as u32);
+                self.orders[ins] = self.create_info(stage_pack, fi, ins as u32);
                 assault_index += 1;
             }
 
             recalc_order_time(&mut self.orders, STEP_WAIT, div);
         }
 
-        if self.unit == UNIT_COUNT - 1 {
+        if self.unit == UNIT_COUNT as u32 - 1 && !self.challenging {
             if let Some(fi) = self.captured_fighter {
-                let mut info = self.create_info(fi, self.orders.len() as u32);
+                let mut info = self.create_info(stage_pack, fi, self.orders.len() as u32);
                 info.enemy_type = EnemyType::CapturedFighter;
                 self.orders.push(info);
             }
         }
     }
 
-    fn create_info(&self, fi: FormationIndex, count: u32) -> Info {
-        let entry = &UNIT_TABLE[(self.stage as usize) % UNIT_TABLE.len()][self.unit as usize];
-        let enemy_types = &ENEMY_TYPE_TABLE[(self.unit * 2) as usize ..
-                                            (self.unit * 2) as usize + 2];
+    fn create_info(&self, stage_pack: &StagePack, fi: FormationIndex, count: u32) -> Info {
+        let (entry, enemy_types) = self.unit_entry(stage_pack);
+        let table = stage_pack.traj(&entry.traj);
         match entry.pat {
             0 => {
                 let flip = if entry.flip_x { 1 } else { 0 };
                 let side = count & 1;
                 let enemy_type = enemy_types[(side ^ flip) as usize];
                 let time = (count / 2) * STEP_WAIT;
-                Info::new(time, enemy_type, fi, Vec2I::new(8 * ONE, 0), side == 0, entry.table)
+                Info::new(time, enemy_type, fi, Vec2I::new(8 * ONE, 0), side == 0, table)
             }
             1 => {
                 let enemy_type = enemy_types[(count & 1) as usize];
                 let time = count * STEP_WAIT;
-                Info::new(time, enemy_type, fi, Vec2I::new(8 * ONE, 0), entry.flip_x, entry.table)
+                Info::new(time, enemy_type, fi, Vec2I::new(8 * ONE, 0), entry.flip_x, table)
             }
             2 => {
                 let enemy_type = enemy_types[(count & 1) as usize];
                 let time = count * STEP_WAIT;
-                Info::new(time, enemy_type, fi, ZERO_VEC, entry.flip_x, entry.table)
+                Info::new(time, enemy_type, fi, ZERO_VEC, entry.flip_x, table)
             }
             3 | _ => {
                 let flip = if entry.flip_x { 1 } else { 0 };
@@ -263,13 +286,14 @@ impl AppearanceManager {
                 let enemy_type = enemy_types[(side ^ flip) as usize];
                 let flag = 1 - (side as i32) * 2;
                 let time = (count / 2) * STEP_WAIT;
-                Info::new(time, enemy_type, fi, Vec2I::new(flag * 8 * ONE, 0), entry.flip_x, entry.table)
+                Info::new(time, enemy_type, fi, Vec2I::new(flag * 8 * ONE, 0), entry.flip_x, table)
             }
         }
     }
 
-    fn set_shot_enables(&mut self) {
-        let count = SHOT_ENABLE_TABLE[min(self.stage as usize, SHOT_ENABLE_TABLE.len() - 1)][self.unit as usize];
+    fn set_shot_enables(&mut self, stage_pack: &StagePack, rng: &mut Xoshiro128Plus) {
+        let shot_enable = &stage_pack.shot_enable;
+        let count = shot_enable[min(self.stage as usize, shot_enable.len() - 1)][self.unit as usize];
         if count == 0 {
             return;
         }
@@ -279,8 +303,7 @@ impl AppearanceManager {
         for i in 0..orders.len() {
             nums.push(i);
         }
-        let mut rng = Xoshiro128Plus::from_seed(rand::thread_rng().gen());
-        nums.partial_shuffle(&mut rng, count as usize);
+        nums.partial_shuffle(rng, count as usize);
 
         for i in 0..count {
             orders[nums[i as usize]].shot_enable = true;
@@ -1,10 +1,10 @@
 use counted_array::counted_array;
 
 use super::enemy::EnemyType;
-use super::traj_command::TrajCommand;
-use super::traj_command_table::*;
 use super::FormationIndex;
 
+// Built-in stage layout, see `StagePack`.
+
 const fn pos(x: u8, y: u8) -> FormationIndex { FormationIndex(x, y) }
 
 pub(super) const ORDER: [FormationIndex; 40] = [
@@ -32,40 +32,40 @@ pub(super) const ENEMY_TYPE_TABLE: [EnemyType; 2 * 5] = [
     EnemyType::Bee, EnemyType::Bee,
 ];
 
-pub(super) struct UnitTableEntry<'a> {
+pub(super) struct UnitTableEntry {
     pub(super) pat: usize,
-    pub(super) table: &'a [TrajCommand],
+    pub(super) traj: &'static str,
     pub(super) flip_x: bool,
 }
 
 counted_array!(pub(super) const UNIT_TABLE: [[UnitTableEntry; 5]; _] = [
     [
-        UnitTableEntry { pat: 0, table: &COMMAND_TABLE1, flip_x: false },
-        UnitTableEntry { pat: 1, table: &COMMAND_TABLE2, flip_x: false },
-        UnitTableEntry { pat: 1, table: &COMMAND_TABLE2, flip_x: true },
-        UnitTableEntry { pat: 2, table: &COMMAND_TABLE1, flip_x: false },
-        UnitTableEntry { pat: 2, table: &COMMAND_TABLE1, flip_x: true },
+        UnitTableEntry { pat: 0, traj: "command1", flip_x: false },
+        UnitTableEntry { pat: 1, traj: "command2", flip_x: false },
+        UnitTableEntry { pat: 1, traj: "command2", flip_x: true },
+        UnitTableEntry { pat: 2, traj: "command1", flip_x: false },
+        UnitTableEntry { pat: 2, traj: "command1", flip_x: true },
     ],
     [
-        UnitTableEntry { pat: 0, table: &COMMAND_TABLE3, flip_x: true },
-        UnitTableEntry { pat: 3, table: &COMMAND_TABLE2, flip_x: false },
-        UnitTableEntry { pat: 3, table: &COMMAND_TABLE2, flip_x: true },
-        UnitTableEntry { pat: 3, table: &COMMAND_TABLE1, flip_x: false },
-        UnitTableEntry { pat: 3, table: &COMMAND_TABLE1, flip_x: true },
+        UnitTableEntry { pat: 0, traj: "command3", flip_x: true },
+        UnitTableEntry { pat: 3, traj: "command2", flip_x: false },
+        UnitTableEntry { pat: 3, traj: "command2", flip_x: true },
+        UnitTableEntry { pat: 3, traj: "command1", flip_x: false },
+        UnitTableEntry { pat: 3, traj: "command1", flip_x: true },
     ],
     [
-        UnitTableEntry { pat: 0, table: &COMMAND_TABLE1, flip_x: false },
-        UnitTableEntry { pat: 0, table: &COMMAND_TABLE2, flip_x: true },
-        UnitTableEntry { pat: 0, table: &COMMAND_TABLE2, flip_x: false },
-        UnitTableEntry { pat: 0, table: &COMMAND_TABLE1, flip_x: false },
-        UnitTableEntry { pat: 0, table: &COMMAND_TABLE1, flip_x: false },
+        UnitTableEntry { pat: 0, traj: "command1", flip_x: false },
+        UnitTableEntry { pat: 0, traj: "command2", flip_x: true },
+        UnitTableEntry { pat: 0, traj: "command2", flip_x: false },
+        UnitTableEntry { pat: 0, traj: "command1", flip_x: false },
+        UnitTableEntry { pat: 0, traj: "command1", flip_x: false },
     ],
     [
-        UnitTableEntry { pat: 0, table: &COMMAND_TABLE3, flip_x: true },
-        UnitTableEntry { pat: 3, table: &COMMAND_TABLE2, flip_x: false },
-        UnitTableEntry { pat: 3, table: &COMMAND_TABLE2, flip_x: true },
-        UnitTableEntry { pat: 3, table: &COMMAND_TABLE3, flip_x: false },
-        UnitTableEntry { pat: 3, table: &COMMAND_TABLE3, flip_x: true },
+        UnitTableEntry { pat: 0, traj: "command3", flip_x: true },
+        UnitTableEntry { pat: 3, traj: "command2", flip_x: false },
+        UnitTableEntry { pat: 3, traj: "command2", flip_x: true },
+        UnitTableEntry { pat: 3, traj: "command3", flip_x: false },
+        UnitTableEntry { pat: 3, traj: "command3", flip_x: true },
     ],
 ]);
 
@@ -90,3 +90,43 @@ counted_array!(pub(super) const SHOT_ENABLE_TABLE: [[u32; 5]; _] = [
     [4, 4, 4, 4, 4],
     [5, 5, 5, 5, 5],
 ]);
+
+pub(super) struct ChallengingStageTableEntry {
+    pub(super) enemy_types: [EnemyType; 2 * 5],
+    pub(super) units: [UnitTableEntry; 5],
+}
+
+counted_array!(pub(super) const CHALLENGING_STAGE_TABLE: [ChallengingStageTableEntry; _] = [
+    ChallengingStageTableEntry {
+        enemy_types: [
+            EnemyType::Bee, EnemyType::Bee,
+            EnemyType::Butterfly, EnemyType::Owl,
+            EnemyType::Butterfly, EnemyType::Butterfly,
+            EnemyType::Bee, EnemyType::Bee,
+            EnemyType::Bee, EnemyType::Bee,
+        ],
+        units: [
+            UnitTableEntry { pat: 0, traj: "challenge1", flip_x: false },
+            UnitTableEntry { pat: 1, traj: "challenge2", flip_x: false },
+            UnitTableEntry { pat: 1, traj: "challenge2", flip_x: true },
+            UnitTableEntry { pat: 2, traj: "challenge3", flip_x: false },
+            UnitTableEntry { pat: 2, traj: "challenge3", flip_x: true },
+        ],
+    },
+    ChallengingStageTableEntry {
+        enemy_types: [
+            EnemyType::Butterfly, EnemyType::Butterfly,
+            EnemyType::Owl, EnemyType::Butterfly,
+            EnemyType::Bee, EnemyType::Bee,
+            EnemyType::Butterfly, EnemyType::Bee,
+            EnemyType::Bee, EnemyType::Bee,
+        ],
+        units: [
+            UnitTableEntry { pat: 3, traj: "challenge3", flip_x: false },
+            UnitTableEntry { pat: 1, traj: "challenge4", flip_x: false },
+            UnitTableEntry { pat: 1, traj: "challenge4", flip_x: true },
+            UnitTableEntry { pat: 0, traj: "challenge1", flip_x: true },
+            UnitTableEntry { pat: 2, traj: "challenge2", flip_x: false },
+        ],
+    },
+]);
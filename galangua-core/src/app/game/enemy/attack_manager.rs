@@ -1,17 +1,21 @@
 use array_macro::*;
 use rand::seq::SliceRandom;
-use rand::{Rng, SeedableRng};
+use rand::Rng;
 use rand_xoshiro::Xoshiro128Plus;
+use serde::{Deserialize, Serialize};
 
 use super::enemy::{EnemyState, EnemyType};
 use super::formation::{X_COUNT, Y_COUNT};
 use super::{Accessor, FormationIndex};
+use crate::app::game::game_config::MAX_ATTACKER_COUNT;
 use crate::app::game::{CaptureState, EventQueue, EventType};
 use crate::app::util::unsafe_util::peep;
+use crate::util::math::ONE;
 
-const MAX_ATTACKER_COUNT: usize = 3;
-const WAIT: u32 = 30;
+const WAIT: u32 = 30;  // Frames between dives, shortened to `MIN_WAIT` at the max rank.
+const MIN_WAIT: u32 = 15;
 
+#[derive(Serialize, Deserialize)]
 pub struct AttackManager {
     enable: bool,
     paused: bool,
@@ -47,6 +51,11 @@ impl AttackManager {
         self.attackers.iter().all(|x| x.is_none())
     }
 
+    #[cfg(test)]
+    pub fn wait(&self) -> u32 {
+        self.wait
+    }
+
     pub fn update<A: Accessor>(&mut self, accessor: &mut A, event_queue: &mut EventQueue) {
         self.check_liveness(accessor);
 
@@ -59,14 +68,15 @@ impl AttackManager {
             return;
         }
 
-        if let Some(slot_index) = self.attackers.iter().position(|x| x.is_none()) {
+        let max_attackers = accessor.game_config().max_attackers;
+        if let Some(slot_index) = self.attackers.iter().take(max_attackers).position(|x| x.is_none()) {
             if let Some((formation_index, capture_attack)) = self.pick_attacker(accessor, event_queue) {
                 self.attackers[slot_index] = Some(formation_index);
                 if capture_attack {
                     event_queue.push(EventType::StartCaptureAttack(formation_index));
                 }
             }
-            self.wait = WAIT;
+            self.wait = WAIT - (WAIT - MIN_WAIT) * accessor.rank() as u32 / ONE as u32;
             self.cycle += 1;
         }
     }
@@ -88,11 +98,11 @@ impl AttackManager {
         let candidates = self.enum_sides(accessor);
         let fi = match self.cycle % 3 {
             2 => {
-                self.pick_random(&candidates, &mut [1])
+                self.pick_random(&candidates, &mut [1], accessor.rng())
                     .or_else(|| self.pick_captured_fighter(accessor))
             }
             0 | 1 | _ => {
-                self.pick_random(&candidates, &mut [2, 3, 4, 5])
+                self.pick_random(&candidates, &mut [2, 3, 4, 5], accessor.rng())
             }
         };
         if let Some(fi) = fi {
@@ -104,7 +114,7 @@ impl AttackManager {
             let capture_attack = enemy.enemy_type == EnemyType::Owl &&
                 (self.cycle / 3) & 1 != 0 &&
                 accessor.capture_state() == CaptureState::NoCapture &&
-                !accessor.is_player_dual();
+                (0..accessor.player_count()).any(|player_no| !accessor.is_player_dual(player_no));
             enemy.set_attack(capture_attack, accessor, event_queue);
 
             Some((fi, capture_attack))
@@ -113,9 +123,9 @@ impl AttackManager {
         }
     }
 
-    fn pick_random(&mut self, candidates: &[Option<[u8; 2]>; Y_COUNT], rows: &mut [u32]) -> Option<FormationIndex> {
-        let mut rng = Xoshiro128Plus::from_seed(rand::thread_rng().gen());
-        rows.shuffle(&mut rng);
+    fn pick_random(&mut self, candidates: &[Option<[u8; 2]>; Y_COUNT], rows: &mut [u32],
+                   rng: &mut Xoshiro128Plus) -> Option<FormationIndex> {
+        rows.shuffle(rng);
         for &row in rows.iter() {
             if let Some(pos) = candidates[row as usize] {
                 let index = rng.gen_range(0, 2);
@@ -1,8 +1,11 @@
+use serde::{Deserialize, Serialize};
+
 use crate::app::util::{CollBox, Collidable};
 use crate::framework::types::Vec2I;
 use crate::framework::RendererTrait;
 use crate::util::math::round_up;
 
+#[derive(Serialize, Deserialize)]
 pub struct EneShot {
     pub pos: Vec2I,
     pub vel: Vec2I,
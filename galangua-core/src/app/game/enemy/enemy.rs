@@ -1,11 +1,10 @@
-use rand::{Rng, SeedableRng};
-use rand_xoshiro::Xoshiro128Plus;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 use super::formation::Y_COUNT;
+use super::stage_pack::*;
 use super::tractor_beam::TractorBeam;
 use super::traj::Traj;
-use super::traj_command::TrajCommand;
-use super::traj_command_table::*;
 use super::{Accessor, FormationIndex};
 
 use crate::app::consts::*;
@@ -17,9 +16,12 @@ use crate::util::math::{
     atan2_lut, calc_velocity, clamp, diff_angle, normalize_angle, quantize_angle, round_up, square,
     ANGLE, ONE, ONE_BIT};
 
+#[cfg(debug_assertions)]
+use super::traj_command::TrajCommand;
+
 const OWL_DESTROY_SHOT_WAIT: u32 = 3 * 60;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum EnemyType {
     Bee,
     Butterfly,
@@ -27,7 +29,7 @@ pub enum EnemyType {
     CapturedFighter,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum EnemyState {
     None,
     Appearance,
@@ -38,7 +40,7 @@ pub enum EnemyState {
     Troop,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum CapturingState {
     None,
     Attacking,
@@ -53,8 +55,8 @@ pub struct DamageResult {
 
 const MAX_TROOPS: usize = 3;
 
+#[derive(Serialize, Deserialize)]
 pub struct Enemy {
-    vtable: &'static EnemyVtable,
     pub(super) enemy_type: EnemyType,
     state: EnemyState,
     pos: Vec2I,
@@ -66,7 +68,7 @@ pub struct Enemy {
     life: u32,
     traj: Option<Traj>,
     shot_wait: Option<u32>,
-    update_fn: fn(enemy: &mut Enemy, accessor: &mut dyn Accessor, event_queue: &mut EventQueue),
+    update_fn: UpdateFn,
     count: u32,
     attack_frame_count: u32,
     target_pos: Vec2I,
@@ -82,7 +84,6 @@ impl Enemy {
         let vtable = &ENEMY_VTABLE[enemy_type as usize];
 
         Self {
-            vtable,
             enemy_type,
             state: EnemyState::None,
             life: vtable.life,
@@ -93,7 +94,7 @@ impl Enemy {
             formation_index: FormationIndex(255, 255),  // Dummy
             traj: None,
             shot_wait: None,
-            update_fn: update_none,
+            update_fn: UpdateFn::None,
             count: 0,
             attack_frame_count: 0,
             target_pos: ZERO_VEC,
@@ -121,10 +122,22 @@ impl Enemy {
         self.state
     }
 
+    pub fn enemy_type(&self) -> EnemyType {
+        self.enemy_type
+    }
+
+    pub fn tractor_beam(&self) -> Option<&TractorBeam> {
+        self.tractor_beam.as_ref()
+    }
+
     pub fn is_disappeared(&self) -> bool {
         self.disappeared
     }
 
+    fn vtable(&self) -> &'static EnemyVtable {
+        &ENEMY_VTABLE[self.enemy_type as usize]
+    }
+
     fn is_ghost(&self) -> bool {
         self.life == 0
     }
@@ -132,7 +145,7 @@ impl Enemy {
     pub fn update<A: Accessor>(&mut self, accessor: &mut A, event_queue: &mut EventQueue) {
         let prev_pos = self.pos;
 
-        (self.update_fn)(self, accessor, event_queue);
+        (self.update_fn.func())(self, accessor, event_queue);
 
         self.pos += calc_velocity(self.angle + self.vangle / 2, self.speed);
         self.angle += self.vangle;
@@ -191,8 +204,7 @@ impl Enemy {
     pub fn update_attack(&mut self, accessor: &mut dyn Accessor, event_queue: &mut EventQueue) {
         self.attack_frame_count += 1;
 
-        let stage_no = accessor.get_stage_no();
-        let shot_count = std::cmp::min(2 + stage_no / 8 , 5) as u32;
+        let shot_count = accessor.game_config().attack_shot_count(accessor.get_stage_no());
         let shot_interval = 20 - shot_count * 2;
 
         if self.attack_frame_count <= shot_interval * shot_count && self.attack_frame_count % shot_interval == 0 {
@@ -213,7 +225,7 @@ impl Enemy {
             return;
         }
 
-        let sprite = (self.vtable.sprite_name)(self, pat);
+        let sprite = (self.vtable().sprite_name)(self, pat);
         let angle = quantize_angle(self.angle, ANGLE_DIV);
         let pos = self.pos();
         renderer.draw_sprite_rot(sprite, &(&pos + &Vec2I::new(-8, -8)), angle, None);
@@ -226,7 +238,7 @@ impl Enemy {
     pub fn set_damage<A: Accessor>(
         &mut self, power: u32, accessor: &mut A, event_queue: &mut EventQueue,
     ) -> DamageResult {
-        (self.vtable.set_damage)(self, power, accessor, event_queue)
+        (self.vtable().set_damage)(self, power, accessor, event_queue)
     }
 
     fn live_troops(&self, accessor: &dyn Accessor) -> bool {
@@ -237,11 +249,11 @@ impl Enemy {
 
     fn set_state(&mut self, state: EnemyState) {
         let update_fn = match state {
-            EnemyState::None | EnemyState::Troop => update_none,
-            EnemyState::Appearance => update_trajectory,
-            EnemyState::MoveToFormation => update_move_to_formation,
-            EnemyState::Assault => update_assault,
-            EnemyState::Formation => update_formation,
+            EnemyState::None | EnemyState::Troop => UpdateFn::None,
+            EnemyState::Appearance => UpdateFn::Trajectory,
+            EnemyState::MoveToFormation => UpdateFn::MoveToFormation,
+            EnemyState::Assault => UpdateFn::Assault,
+            EnemyState::Formation => UpdateFn::Formation,
             EnemyState::Attack => {
                 eprintln!("illegal state");
                 std::process::exit(1);
@@ -250,10 +262,7 @@ impl Enemy {
         self.set_state_with_fn(state, update_fn);
     }
 
-    fn set_state_with_fn(
-        &mut self, state: EnemyState,
-        update_fn: fn(enemy: &mut Enemy, accessor: &mut dyn Accessor, event_queue: &mut EventQueue),
-    ) {
+    fn set_state_with_fn(&mut self, state: EnemyState, update_fn: UpdateFn) {
         self.state = state;
         self.update_fn = update_fn;
     }
@@ -263,6 +272,12 @@ impl Enemy {
         self.set_state(EnemyState::Appearance);
     }
 
+    // For challenging stage: Disappear at the end of the trajectory.
+    pub fn set_fly_through(&mut self, traj: Traj) {
+        self.traj = Some(traj);
+        self.set_state_with_fn(EnemyState::Appearance, UpdateFn::FlyThrough);
+    }
+
     fn update_move_to_formation(&mut self, accessor: &dyn Accessor) -> bool {
         let target = accessor.get_formation_pos(&self.formation_index);
         let diff = &target - &self.pos;
@@ -283,7 +298,7 @@ impl Enemy {
     }
 
     pub fn set_attack<A: Accessor>(&mut self, capture_attack: bool, accessor: &mut A, event_queue: &mut EventQueue) {
-        (self.vtable.set_attack)(self, capture_attack, accessor);
+        (self.vtable().set_attack)(self, capture_attack, accessor);
 
         event_queue.push(EventType::PlaySe(CH_JINGLE, SE_ATTACK_START));
     }
@@ -295,13 +310,13 @@ impl Enemy {
 
     #[cfg(debug_assertions)]
     pub fn set_table_attack(&mut self, traj_command_vec: Vec<TrajCommand>, flip_x: bool) {
-        let mut traj = Traj::new_with_vec(traj_command_vec, &ZERO_VEC, flip_x, self.formation_index);
+        let mut traj = Traj::new(traj_command_vec.into(), &ZERO_VEC, flip_x, self.formation_index);
         traj.set_pos(&self.pos);
 
         self.count = 0;
         self.attack_frame_count = 0;
         self.traj = Some(traj);
-        self.set_state_with_fn(EnemyState::Attack, update_attack_traj);
+        self.set_state_with_fn(EnemyState::Attack, UpdateFn::AttackTraj);
     }
 
     fn choose_troops(&mut self, accessor: &mut dyn Accessor) {
@@ -356,9 +371,9 @@ impl Enemy {
         // No need to modify troops, because offset is calculated from previous position.
     }
 
-    fn rush_attack(&mut self) {
+    fn rush_attack(&mut self, accessor: &dyn Accessor) {
         let flip_x = self.formation_index.0 >= 5;
-        let table = self.vtable.rush_traj_table;
+        let table = accessor.stage_pack().traj(self.vtable().rush_traj);
         let mut traj = Traj::new(table, &ZERO_VEC, flip_x, self.formation_index);
         traj.set_pos(&self.pos);
 
@@ -366,7 +381,7 @@ impl Enemy {
         self.attack_frame_count = 0;
         self.traj = Some(traj);
 
-        self.set_state_with_fn(EnemyState::Attack, update_attack_traj);
+        self.set_state_with_fn(EnemyState::Attack, UpdateFn::AttackTraj);
     }
 }
 
@@ -388,22 +403,22 @@ impl Collidable for Enemy {
 struct EnemyVtable {
     life: u32,
     set_attack: fn(me: &mut Enemy, capture_attack: bool, accessor: &mut dyn Accessor),
-    rush_traj_table: &'static [TrajCommand],
+    rush_traj: &'static str,
     calc_point: fn(me: &Enemy) -> u32,
     sprite_name: fn(me: &Enemy, pat: usize) -> &str,
     set_damage: fn(me: &mut Enemy, power: u32, accessor: &mut dyn Accessor,
                    event_queue: &mut EventQueue) -> DamageResult,
 }
 
-fn bee_set_attack(me: &mut Enemy, _capture_attack: bool, _accessor: &mut dyn Accessor) {
+fn bee_set_attack(me: &mut Enemy, _capture_attack: bool, accessor: &mut dyn Accessor) {
     let flip_x = me.formation_index.0 >= 5;
-    let mut traj = Traj::new(&BEE_ATTACK_TABLE, &ZERO_VEC, flip_x, me.formation_index);
+    let mut traj = Traj::new(accessor.stage_pack().traj(BEE_ATTACK), &ZERO_VEC, flip_x, me.formation_index);
     traj.set_pos(&me.pos);
 
     me.count = 0;
     me.attack_frame_count = 0;
     me.traj = Some(traj);
-    me.set_state_with_fn(EnemyState::Attack, update_bee_attack);
+    me.set_state_with_fn(EnemyState::Attack, UpdateFn::BeeAttack);
 }
 
 fn update_bee_attack(me: &mut Enemy, accessor: &mut dyn Accessor, event_queue: &mut EventQueue) {
@@ -413,26 +428,26 @@ fn update_bee_attack(me: &mut Enemy, accessor: &mut dyn Accessor, event_queue: &
     if me.state != EnemyState::Attack {
         if accessor.is_rush() {
             let flip_x = me.formation_index.0 >= 5;
-            let mut traj = Traj::new(&BEE_ATTACK_RUSH_CONT_TABLE, &ZERO_VEC, flip_x, me.formation_index);
+            let mut traj = Traj::new(accessor.stage_pack().traj(BEE_ATTACK_RUSH_CONT), &ZERO_VEC, flip_x, me.formation_index);
             traj.set_pos(&me.pos);
 
             me.traj = Some(traj);
-            me.set_state_with_fn(EnemyState::Attack, update_attack_traj);
+            me.set_state_with_fn(EnemyState::Attack, UpdateFn::AttackTraj);
 
             event_queue.push(EventType::PlaySe(CH_JINGLE, SE_ATTACK_START));
         }
     }
 }
 
-fn butterfly_set_attack(me: &mut Enemy, _capture_attack: bool, _accessor: &mut dyn Accessor) {
+fn butterfly_set_attack(me: &mut Enemy, _capture_attack: bool, accessor: &mut dyn Accessor) {
     let flip_x = me.formation_index.0 >= 5;
-    let mut traj = Traj::new(&BUTTERFLY_ATTACK_TABLE, &ZERO_VEC, flip_x, me.formation_index);
+    let mut traj = Traj::new(accessor.stage_pack().traj(BUTTERFLY_ATTACK), &ZERO_VEC, flip_x, me.formation_index);
     traj.set_pos(&me.pos);
 
     me.count = 0;
     me.attack_frame_count = 0;
     me.traj = Some(traj);
-    me.set_state_with_fn(EnemyState::Attack, update_attack_traj);
+    me.set_state_with_fn(EnemyState::Attack, UpdateFn::AttackTraj);
 }
 
 fn bee_set_damage(me: &mut Enemy, power: u32, _accessor: &mut dyn Accessor,
@@ -442,7 +457,7 @@ fn bee_set_damage(me: &mut Enemy, power: u32, _accessor: &mut dyn Accessor,
         DamageResult { killed: false, point: 0 }
     } else {
         me.life = 0;
-        let point = (me.vtable.calc_point)(me);
+        let point = (me.vtable().calc_point)(me);
         DamageResult { killed: true, point }
     }
 }
@@ -458,7 +473,7 @@ fn owl_set_damage(me: &mut Enemy, power: u32, accessor: &mut dyn Accessor,
         if me.live_troops(accessor) {
             killed = false;  // Keep alive as a ghost.
         }
-        let point = (me.vtable.calc_point)(me);
+        let point = (me.vtable().calc_point)(me);
 
         // Release capturing.
         match me.capturing_state {
@@ -485,15 +500,15 @@ fn owl_set_damage(me: &mut Enemy, power: u32, accessor: &mut dyn Accessor,
     }
 }
 
-fn captured_fighter_set_attack(me: &mut Enemy, _capture_attack: bool, _accessor: &mut dyn Accessor) {
+fn captured_fighter_set_attack(me: &mut Enemy, _capture_attack: bool, accessor: &mut dyn Accessor) {
     let flip_x = me.formation_index.0 >= 5;
-    let mut traj = Traj::new(&OWL_ATTACK_TABLE, &ZERO_VEC, flip_x, me.formation_index);
+    let mut traj = Traj::new(accessor.stage_pack().traj(OWL_ATTACK), &ZERO_VEC, flip_x, me.formation_index);
     traj.set_pos(&me.pos);
 
     me.count = 0;
     me.attack_frame_count = 0;
     me.traj = Some(traj);
-    me.set_state_with_fn(EnemyState::Attack, update_attack_traj);
+    me.set_state_with_fn(EnemyState::Attack, UpdateFn::AttackTraj);
 }
 
 const BEE_SPRITE_NAMES: [&str; 2] = ["gopher1", "gopher2"];
@@ -505,7 +520,7 @@ const ENEMY_VTABLE: [EnemyVtable; 4] = [
     EnemyVtable {
         life: 1,
         set_attack: bee_set_attack,
-        rush_traj_table: &BEE_RUSH_ATTACK_TABLE,
+        rush_traj: BEE_RUSH_ATTACK,
         calc_point: |me: &Enemy| {
             if me.state == EnemyState::Formation { 50 } else { 100 }
         },
@@ -516,7 +531,7 @@ const ENEMY_VTABLE: [EnemyVtable; 4] = [
     EnemyVtable {
         life: 1,
         set_attack: butterfly_set_attack,
-        rush_traj_table: &BUTTERFLY_RUSH_ATTACK_TABLE,
+        rush_traj: BUTTERFLY_RUSH_ATTACK,
         calc_point: |me: &Enemy| {
             if me.state == EnemyState::Formation { 80 } else { 160 }
         },
@@ -538,11 +553,11 @@ const ENEMY_VTABLE: [EnemyVtable; 4] = [
                 me.choose_troops(accessor);
 
                 let flip_x = me.formation_index.0 >= 5;
-                let mut traj = Traj::new(&OWL_ATTACK_TABLE, &ZERO_VEC, flip_x, me.formation_index);
+                let mut traj = Traj::new(accessor.stage_pack().traj(OWL_ATTACK), &ZERO_VEC, flip_x, me.formation_index);
                 traj.set_pos(&me.pos);
 
                 me.traj = Some(traj);
-                update_attack_traj
+                UpdateFn::AttackTraj
             } else {
                 me.capturing_state = CapturingState::Attacking;
 
@@ -555,15 +570,15 @@ const ENEMY_VTABLE: [EnemyVtable; 4] = [
                     me.vangle = DLIMIT;
                 }
 
-                let player_pos = accessor.get_raw_player_pos();
+                let player_pos = accessor.nearest_player_pos(&me.pos);
                 me.target_pos = Vec2I::new(player_pos.x, (HEIGHT - 16 - 8 - 88) * ONE);
 
-                update_attack_capture
+                UpdateFn::AttackCapture
             };
 
             me.set_state_with_fn(EnemyState::Attack, update_fn);
         },
-        rush_traj_table: &OWL_RUSH_ATTACK_TABLE,
+        rush_traj: OWL_RUSH_ATTACK,
         calc_point: |me: &Enemy| {
             if me.state == EnemyState::Formation {
                 150
@@ -585,7 +600,7 @@ const ENEMY_VTABLE: [EnemyVtable; 4] = [
     EnemyVtable {
         life: 1,
         set_attack: captured_fighter_set_attack,
-        rush_traj_table: &OWL_RUSH_ATTACK_TABLE,
+        rush_traj: OWL_RUSH_ATTACK,
         calc_point: |me: &Enemy| {
             if me.state == EnemyState::Formation { 500 } else { 1000 }
         },
@@ -597,7 +612,7 @@ const ENEMY_VTABLE: [EnemyVtable; 4] = [
             } else {
                 me.life = 0;
                 event_queue.push(EventType::CapturedFighterDestroyed);
-                let point = (me.vtable.calc_point)(me);
+                let point = (me.vtable().calc_point)(me);
                 DamageResult { killed: true, point }
             }
         },
@@ -606,6 +621,52 @@ const ENEMY_VTABLE: [EnemyVtable; 4] = [
 
 ////////////////////////////////////////////////
 
+// Held as an enum instead of a function pointer, to be serializable.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+enum UpdateFn {
+    None,
+    Trajectory,
+    MoveToFormation,
+    Assault,
+    Assault2,
+    Formation,
+    BeeAttack,
+    AttackTraj,
+    AttackCapture,
+    AttackCaptureBeam,
+    AttackCaptureGoOut,
+    AttackCaptureStart,
+    AttackCaptureCloseBeam,
+    AttackCaptureCaptureDoneWait,
+    AttackCaptureBack,
+    AttackCapturePushUp,
+    FlyThrough,
+}
+
+impl UpdateFn {
+    fn func(self) -> fn(me: &mut Enemy, accessor: &mut dyn Accessor, event_queue: &mut EventQueue) {
+        match self {
+            UpdateFn::None => update_none,
+            UpdateFn::Trajectory => update_trajectory,
+            UpdateFn::MoveToFormation => update_move_to_formation,
+            UpdateFn::Assault => update_assault,
+            UpdateFn::Assault2 => update_assault2,
+            UpdateFn::Formation => update_formation,
+            UpdateFn::BeeAttack => update_bee_attack,
+            UpdateFn::AttackTraj => update_attack_traj,
+            UpdateFn::AttackCapture => update_attack_capture,
+            UpdateFn::AttackCaptureBeam => update_attack_capture_beam,
+            UpdateFn::AttackCaptureGoOut => update_attack_capture_go_out,
+            UpdateFn::AttackCaptureStart => update_attack_capture_start,
+            UpdateFn::AttackCaptureCloseBeam => update_attack_capture_close_beam,
+            UpdateFn::AttackCaptureCaptureDoneWait => update_attack_capture_capture_done_wait,
+            UpdateFn::AttackCaptureBack => update_attack_capture_back,
+            UpdateFn::AttackCapturePushUp => update_attack_capture_push_up,
+            UpdateFn::FlyThrough => update_fly_through,
+        }
+    }
+}
+
 fn update_none(_me: &mut Enemy, _accessor: &mut dyn Accessor, _event_queue: &mut EventQueue) {}
 
 fn update_trajectory(me: &mut Enemy, accessor: &mut dyn Accessor, event_queue: &mut EventQueue) {
@@ -639,16 +700,13 @@ fn update_trajectory(me: &mut Enemy, accessor: &mut dyn Accessor, event_queue: &
     if me.state == EnemyState::Appearance &&
         me.formation_index.1 >= Y_COUNT as u8  // Assault
     {
-        let mut rng = Xoshiro128Plus::from_seed(rand::thread_rng().gen());
-        let target_pos = [
-            Some(*accessor.get_raw_player_pos()),
-            accessor.get_dual_player_pos(),
-        ];
-        let count = target_pos.iter().flat_map(|x| x).count();
-        let target: &Vec2I = target_pos.iter()
-            .flat_map(|x| x).nth(rng.gen_range(0, count)).unwrap();
-
-        me.target_pos = *target;
+        let target_pos = accessor.player_target_positions();
+        if !target_pos.is_empty() {
+            let target = target_pos[accessor.rng().gen_range(0, target_pos.len())];
+            me.target_pos = target;
+        } else {
+            me.target_pos = accessor.nearest_player_pos(&me.pos);
+        }
         me.vangle = 0;
         me.set_state(EnemyState::Assault);
     } else {
@@ -656,6 +714,21 @@ fn update_trajectory(me: &mut Enemy, accessor: &mut dyn Accessor, event_queue: &
     }
 }
 
+fn update_fly_through(me: &mut Enemy, accessor: &mut dyn Accessor, _event_queue: &mut EventQueue) {
+    if let Some(traj) = &mut me.traj {
+        if traj.update(accessor) {
+            me.pos = traj.pos();
+            me.angle = traj.angle();
+            me.speed = traj.speed;
+            me.vangle = traj.vangle;
+            return;
+        }
+    }
+
+    me.traj = None;
+    me.disappeared = true;
+}
+
 fn update_move_to_formation(me: &mut Enemy, accessor: &mut dyn Accessor, _event_queue: &mut EventQueue) {
     if !me.update_move_to_formation(accessor) {
         me.release_troops(accessor);
@@ -676,7 +749,7 @@ fn update_assault(me: &mut Enemy, _accessor: &mut dyn Accessor, _event_queue: &m
         me.angle += DLIMIT;
     } else {
         me.angle += d;
-        me.update_fn = update_assault2;
+        me.update_fn = UpdateFn::Assault2;
     }
 }
 fn update_assault2(me: &mut Enemy, _accessor: &mut dyn Accessor, _event_queue: &mut EventQueue) {
@@ -721,7 +794,7 @@ fn update_attack_capture(me: &mut Enemy, _accessor: &mut dyn Accessor, _event_qu
 
         me.tractor_beam = Some(TractorBeam::new(&(&me.pos + &Vec2I::new(0, 8 * ONE))));
 
-        me.update_fn = update_attack_capture_beam;
+        me.update_fn = UpdateFn::AttackCaptureBeam;
         me.count = 0;
     }
 }
@@ -730,14 +803,15 @@ fn update_attack_capture_beam(me: &mut Enemy, accessor: &mut dyn Accessor, event
         if tractor_beam.closed() {
             me.tractor_beam = None;
             me.speed = 5 * ONE / 2;
-            me.update_fn = update_attack_capture_go_out;
-        } else if accessor.can_player_capture() &&
-                  tractor_beam.can_capture(accessor.get_raw_player_pos())
-        {
-            event_queue.push(EventType::CapturePlayer(&me.pos + &Vec2I::new(0, 16 * ONE)));
+            me.update_fn = UpdateFn::AttackCaptureGoOut;
+        } else if let Some(player_no) = (0..accessor.player_count()).find(|&player_no| {
+            accessor.can_player_capture(player_no) &&
+                accessor.get_raw_player_pos(player_no).is_some_and(|pos| tractor_beam.can_capture(pos))
+        }) {
+            event_queue.push(EventType::CapturePlayer(&me.pos + &Vec2I::new(0, 16 * ONE), player_no));
             tractor_beam.start_capture();
             me.capturing_state = CapturingState::BeamTracting;
-            me.update_fn = update_attack_capture_start;
+            me.update_fn = UpdateFn::AttackCaptureStart;
             me.count = 0;
         }
     }
@@ -749,7 +823,7 @@ fn update_attack_capture_go_out(me: &mut Enemy, accessor: &mut dyn Accessor, eve
         me.warp(offset);
 
         if accessor.is_rush() {
-            me.rush_attack();
+            me.rush_attack(accessor);
             event_queue.push(EventType::PlaySe(CH_JINGLE, SE_ATTACK_START));
         } else {
             me.set_state(EnemyState::MoveToFormation);
@@ -761,7 +835,7 @@ fn update_attack_capture_go_out(me: &mut Enemy, accessor: &mut dyn Accessor, eve
 fn update_attack_capture_start(me: &mut Enemy, accessor: &mut dyn Accessor, _event_queue: &mut EventQueue) {
     if accessor.is_player_capture_completed() {
         me.tractor_beam.as_mut().unwrap().close_capture();
-        me.update_fn = update_attack_capture_close_beam;
+        me.update_fn = UpdateFn::AttackCaptureCloseBeam;
         me.count = 0;
     }
 }
@@ -779,7 +853,7 @@ fn update_attack_capture_close_beam(me: &mut Enemy, _accessor: &mut dyn Accessor
             event_queue.push(EventType::CapturePlayerCompleted);
 
             me.copy_angle_to_troops = false;
-            me.update_fn = update_attack_capture_capture_done_wait;
+            me.update_fn = UpdateFn::AttackCaptureCaptureDoneWait;
             me.count = 0;
         }
     }
@@ -788,14 +862,14 @@ fn update_attack_capture_capture_done_wait(me: &mut Enemy, _accessor: &mut dyn A
     me.count += 1;
     if me.count >= 120 {
         me.speed = 5 * ONE / 2;
-        me.update_fn = update_attack_capture_back;
+        me.update_fn = UpdateFn::AttackCaptureBack;
     }
 }
 fn update_attack_capture_back(me: &mut Enemy, accessor: &mut dyn Accessor, _event_queue: &mut EventQueue) {
     if !me.update_move_to_formation(accessor) {
         me.speed = 0;
         me.angle = normalize_angle(me.angle);
-        me.update_fn = update_attack_capture_push_up;
+        me.update_fn = UpdateFn::AttackCapturePushUp;
     }
 }
 fn update_attack_capture_push_up(me: &mut Enemy, accessor: &mut dyn Accessor, event_queue: &mut EventQueue) {
@@ -831,7 +905,7 @@ fn update_attack_traj(me: &mut Enemy, accessor: &mut dyn Accessor, event_queue:
         } else if accessor.is_rush() {
             // Rush mode: Continue attacking
             me.remove_destroyed_troops(accessor);
-            me.rush_attack();
+            me.rush_attack(accessor);
             event_queue.push(EventType::PlaySe(CH_JINGLE, SE_ATTACK_START));
         }
     }
@@ -1,6 +1,7 @@
 use array_macro::*;
-use rand::{Rng, SeedableRng};
+use rand::Rng;
 use rand_xoshiro::Xoshiro128Plus;
+use serde::{Deserialize, Serialize};
 
 use super::appearance_manager::AppearanceManager;
 use super::attack_manager::AttackManager;
@@ -17,18 +18,23 @@ use crate::framework::types::Vec2I;
 use crate::framework::RendererTrait;
 use crate::util::math::{atan2_lut, calc_velocity, clamp, ANGLE, ONE};
 
-const MAX_ENEMY_COUNT: usize = 70;
-const MAX_SHOT_COUNT: usize = 12;
+#[cfg(debug_assertions)]
+use super::stage_pack::StagePack;
+
+pub const MAX_ENEMY_COUNT: usize = 70;
+pub const MAX_SHOT_COUNT: usize = 12;
 const RUSH_THRESHOLD: u32 = 5;
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Serialize, Deserialize)]
 enum StageState {
     APPEARANCE,
     NORMAL,
     RUSH,
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct EnemyManager {
+    #[serde(with = "crate::util::serde_array")]
     enemies: [Option<Enemy>; MAX_ENEMY_COUNT],
     alive_enemy_count: u32,
     shots: [Option<EneShot>; MAX_SHOT_COUNT],
@@ -38,6 +44,10 @@ pub struct EnemyManager {
     attack_manager: AttackManager,
     stage_state: StageState,
     frame_count: u32,
+    #[serde(default)]
+    appeared_count: u32,
+    #[serde(default)]
+    hit_count: u32,
 }
 
 impl EnemyManager {
@@ -52,6 +62,8 @@ impl EnemyManager {
             attack_manager: AttackManager::new(),
             stage_state: StageState::APPEARANCE,
             frame_count: 0,
+            appeared_count: 0,
+            hit_count: 0,
         }
     }
 
@@ -66,6 +78,23 @@ impl EnemyManager {
         self.shot_paused_count = 0;
         self.stage_state = StageState::APPEARANCE;
         self.frame_count = 0;
+        self.appeared_count = 0;
+        self.hit_count = 0;
+    }
+
+    // Count of enemies appeared and destroyed in this stage.
+    pub fn appeared_count(&self) -> u32 {
+        self.appeared_count
+    }
+
+    pub fn hit_count(&self) -> u32 {
+        self.hit_count
+    }
+
+    // Frames till the next dive.
+    #[cfg(test)]
+    pub fn attack_wait(&self) -> u32 {
+        self.attack_manager.wait()
     }
 
     pub fn all_destroyed(&self) -> bool {
@@ -79,7 +108,7 @@ impl EnemyManager {
             self.shot_paused_count -= 1;
         }
 
-        self.update_appearance();
+        self.update_appearance(accessor);
         self.update_formation();
         self.update_attackers(accessor, event_queue);
         self.update_enemies(accessor, event_queue);
@@ -120,6 +149,7 @@ impl EnemyManager {
             let result = enemy.set_damage(power, accessor, event_queue);
 
             if result.point > 0 {
+                self.hit_count += 1;
                 event_queue.push(EventType::AddScore(result.point));
                 let angle = enemy.angle();
                 event_queue.push(EventType::EnemyExplosion(pos, angle, enemy.enemy_type));
@@ -150,11 +180,14 @@ impl EnemyManager {
         return None;
     }
 
-    fn update_appearance(&mut self) {
+    fn update_appearance<T: Accessor>(&mut self, accessor: &mut T) {
         let prev_done = self.appearance_manager.done;
-        if let Some(new_borns) = self.appearance_manager.update(&self.enemies) {
+        let stage_pack = accessor.stage_pack().clone();
+        if let Some(new_borns) = self.appearance_manager.update(&self.enemies, &stage_pack, accessor.rng()) {
             for enemy in new_borns {
-                self.spawn(enemy);
+                if self.spawn(enemy) {
+                    self.appeared_count += 1;
+                }
             }
         }
         if !prev_done && self.appearance_manager.done {
@@ -240,13 +273,14 @@ impl EnemyManager {
         self.shot_paused_count = wait;
     }
 
-    pub fn spawn_shot(&mut self, pos: &Vec2I, target_pos: &[Option<Vec2I>], speed: i32) {
+    pub fn spawn_shot(
+        &mut self, pos: &Vec2I, target_pos: &[Option<Vec2I>], speed: i32, rng: &mut Xoshiro128Plus,
+    ) {
         if self.shot_paused_count > 0 {
             return;
         }
 
         if let Some(index) = self.shots.iter().position(|x| x.is_none()) {
-            let mut rng = Xoshiro128Plus::from_seed(rand::thread_rng().gen());
             let count = target_pos.iter().flat_map(|x| x).count();
             let target: &Vec2I = target_pos.iter()
                 .flat_map(|x| x).nth(rng.gen_range(0, count)).unwrap();
@@ -274,6 +308,10 @@ impl EnemyManager {
         &self.enemies
     }
 
+    pub fn shots(&self) -> impl Iterator<Item = &EneShot> {
+        self.shots.iter().flatten()
+    }
+
     pub fn get_enemy_at(&self, formation_index: &FormationIndex) -> Option<&Enemy> {
         let index = calc_array_index(formation_index);
         self.enemies[index].as_ref()
@@ -295,7 +333,7 @@ impl EnemyManager {
     // Debug
 
     #[cfg(debug_assertions)]
-    pub fn reset_stable(&mut self) {
+    pub fn reset_stable(&mut self, stage_pack: &StagePack) {
         self.enemies = array![None; MAX_ENEMY_COUNT];
         self.shots = Default::default();
 
@@ -310,8 +348,8 @@ impl EnemyManager {
 
         for unit in 0..5 {
             for i in 0..8 {
-                let index = super::appearance_table::ORDER[unit * 8 + i];
-                let enemy_type = super::appearance_table::ENEMY_TYPE_TABLE[unit * 2 + (i / 4)];
+                let index = stage_pack.order[unit * 8 + i];
+                let enemy_type = stage_pack.enemy_types[unit * 2 + (i / 4)];
                 let pos = self.formation.pos(&index);
                 let mut enemy = Enemy::new(enemy_type, &pos, 0, 0);
                 enemy.formation_index = index;
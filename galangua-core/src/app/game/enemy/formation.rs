@@ -1,4 +1,5 @@
 use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
 
 use super::FormationIndex;
 
@@ -34,12 +35,13 @@ lazy_static! {
     };
 }
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize)]
 enum MovingPat {
     Slide,
     Scale,
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct Formation {
     xtbl: [i32; X_COUNT],
     ytbl: [i32; Y_COUNT],
@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 mod accessor;
 mod appearance_manager;
 mod appearance_table;
@@ -6,14 +8,16 @@ mod ene_shot;
 mod enemy;
 mod enemy_manager;
 mod formation;
+mod stage_pack;
 mod tractor_beam;
 mod traj;
 pub mod traj_command;
 mod traj_command_table;
 
 pub use self::accessor::Accessor;
-pub use self::enemy::{Enemy, EnemyType};
-pub use self::enemy_manager::EnemyManager;
+pub use self::enemy::{Enemy, EnemyState, EnemyType};
+pub use self::enemy_manager::{EnemyManager, MAX_ENEMY_COUNT, MAX_SHOT_COUNT};
+pub use self::stage_pack::StagePack;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct FormationIndex(pub u8, pub u8);  // x, y
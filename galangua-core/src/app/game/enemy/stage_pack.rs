@@ -0,0 +1,276 @@
+use counted_array::counted_array;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+use super::appearance_table::*;
+use super::enemy::EnemyType;
+use super::formation::{X_COUNT, Y_COUNT};
+use super::traj::TrajTable;
+use super::traj_command::{format_traj_commands, validate_traj_commands, TrajCommand};
+use super::traj_command_table::*;
+use super::FormationIndex;
+
+pub(super) const UNIT_COUNT: usize = 5;
+const UNIT_ENEMY_COUNT: usize = 8;
+const MAX_ASSAULT_COUNT: u32 = (X_COUNT / 2) as u32;
+const CHALLENGING_STAGE_INTERVAL: u16 = 4;
+const FIRST_CHALLENGING_STAGE: u16 = 2;  // 0 origin
+
+// Trajectories which enemies use on attack, must be defined in every pack.
+pub(super) const BEE_ATTACK: &str = "bee_attack";
+pub(super) const BEE_ATTACK_RUSH_CONT: &str = "bee_attack_rush_cont";
+pub(super) const BUTTERFLY_ATTACK: &str = "butterfly_attack";
+pub(super) const OWL_ATTACK: &str = "owl_attack";
+pub(super) const BEE_RUSH_ATTACK: &str = "bee_rush_attack";
+pub(super) const BUTTERFLY_RUSH_ATTACK: &str = "butterfly_rush_attack";
+pub(super) const OWL_RUSH_ATTACK: &str = "owl_rush_attack";
+
+const ATTACK_TRAJ_NAMES: [&str; 7] = [
+    BEE_ATTACK, BEE_ATTACK_RUSH_CONT, BUTTERFLY_ATTACK, OWL_ATTACK,
+    BEE_RUSH_ATTACK, BUTTERFLY_RUSH_ATTACK, OWL_RUSH_ATTACK,
+];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(super) struct UnitEntry {
+    pub(super) pat: usize,
+    pub(super) traj: String,
+    pub(super) flip_x: bool,
+}
+
+// Enemies and trajectories for a challenging stage,
+// trajectories must go out of the screen at the end.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(super) struct ChallengingStage {
+    pub(super) enemy_types: [EnemyType; UNIT_COUNT * 2],
+    pub(super) units: [UnitEntry; UNIT_COUNT],
+}
+
+// Stage layout and flight paths.
+//
+//   order: Formation index for each enemy, 8 per unit.
+//   enemy_types: Two enemy types for each unit.
+//   units: Appearance pattern (0-3) and trajectory for each unit, a row per stage (cycled).
+//   assault: Assault enemy count for each unit, a row per stage (last one repeated).
+//   shot_enable: Shooting enemy count for each unit, a row per stage (last one repeated).
+//   challenging_stages: Layout of challenging stages, every 4 stages from the 3rd (cycled, optional).
+//   trajectories: Named command tables, in fixed point (ONE = 256).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StagePack {
+    pub(super) order: Vec<FormationIndex>,
+    pub(super) enemy_types: [EnemyType; UNIT_COUNT * 2],
+    pub(super) units: Vec<[UnitEntry; UNIT_COUNT]>,
+    pub(super) assault: Vec<[u32; UNIT_COUNT]>,
+    pub(super) shot_enable: Vec<[u32; UNIT_COUNT]>,
+    #[serde(default)]
+    pub(super) challenging_stages: Vec<ChallengingStage>,
+    pub(super) trajectories: BTreeMap<String, TrajTable>,
+}
+
+impl StagePack {
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        let stage_pack: Self = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        stage_pack.validate()?;
+        Ok(stage_pack)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    // Trajectory in text format, see traj_command.rs.
+    pub fn traj_text(&self, name: &str) -> Option<String> {
+        self.trajectories.get(name).map(|table| format_traj_commands(table))
+    }
+
+    pub(super) fn traj(&self, name: &str) -> TrajTable {
+        self.trajectories[name].clone()
+    }
+
+    pub fn is_challenging_stage(&self, stage: u16) -> bool {
+        self.challenging_stage(stage).is_some()
+    }
+
+    pub(super) fn challenging_stage(&self, stage: u16) -> Option<&ChallengingStage> {
+        if self.challenging_stages.is_empty() || stage % CHALLENGING_STAGE_INTERVAL != FIRST_CHALLENGING_STAGE {
+            return None;
+        }
+        let n = (stage / CHALLENGING_STAGE_INTERVAL) as usize;
+        Some(&self.challenging_stages[n % self.challenging_stages.len()])
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.order.len() != UNIT_COUNT * UNIT_ENEMY_COUNT {
+            return Err(format!("order: {} entries expected, but {}",
+                               UNIT_COUNT * UNIT_ENEMY_COUNT, self.order.len()));
+        }
+        let mut used = HashSet::new();
+        for (i, fi) in self.order.iter().enumerate() {
+            if fi.0 as usize >= X_COUNT || fi.1 as usize >= Y_COUNT {
+                return Err(format!("order[{}]: ({}, {}) is out of formation", i, fi.0, fi.1));
+            }
+            if !used.insert((fi.0, fi.1)) {
+                return Err(format!("order[{}]: ({}, {}) is duplicated", i, fi.0, fi.1));
+            }
+        }
+
+        if let Some(i) = self.enemy_types.iter().position(|t| *t == EnemyType::CapturedFighter) {
+            return Err(format!("enemy_types[{}]: CapturedFighter cannot appear", i));
+        }
+
+        if self.units.is_empty() || self.assault.is_empty() || self.shot_enable.is_empty() {
+            return Err(String::from("units, assault and shot_enable must not be empty"));
+        }
+        for (i, row) in self.units.iter().enumerate() {
+            for (j, entry) in row.iter().enumerate() {
+                if entry.pat > 3 {
+                    return Err(format!("units[{}][{}]: unknown pat {}", i, j, entry.pat));
+                }
+                if !self.trajectories.contains_key(&entry.traj) {
+                    return Err(format!("units[{}][{}]: unknown trajectory `{}`", i, j, entry.traj));
+                }
+            }
+        }
+        for (i, challenging_stage) in self.challenging_stages.iter().enumerate() {
+            if let Some(j) = challenging_stage.enemy_types.iter().position(|t| *t == EnemyType::CapturedFighter) {
+                return Err(format!("challenging_stages[{}].enemy_types[{}]: CapturedFighter cannot appear", i, j));
+            }
+            for (j, entry) in challenging_stage.units.iter().enumerate() {
+                if entry.pat > 3 {
+                    return Err(format!("challenging_stages[{}].units[{}]: unknown pat {}", i, j, entry.pat));
+                }
+                if !self.trajectories.contains_key(&entry.traj) {
+                    return Err(format!("challenging_stages[{}].units[{}]: unknown trajectory `{}`",
+                                       i, j, entry.traj));
+                }
+            }
+        }
+        for (i, row) in self.assault.iter().enumerate() {
+            if let Some(j) = row.iter().position(|&count| count > MAX_ASSAULT_COUNT) {
+                return Err(format!("assault[{}][{}]: must be {} or less", i, j, MAX_ASSAULT_COUNT));
+            }
+        }
+        for (i, row) in self.shot_enable.iter().enumerate() {
+            if let Some(j) = row.iter().position(|&count| count > UNIT_ENEMY_COUNT as u32) {
+                return Err(format!("shot_enable[{}][{}]: must be {} or less", i, j, UNIT_ENEMY_COUNT));
+            }
+        }
+
+        for name in ATTACK_TRAJ_NAMES.iter() {
+            if !self.trajectories.contains_key(*name) {
+                return Err(format!("trajectories: `{}` is required", name));
+            }
+        }
+        for (name, table) in self.trajectories.iter() {
+            if table.is_empty() {
+                return Err(format!("trajectories: `{}` is empty", name));
+            }
+            validate_traj_commands(table).map_err(|e| format!("trajectories: `{}`{}", name, e))?;
+        }
+        Ok(())
+    }
+}
+
+// Built-in tables.
+impl Default for StagePack {
+    fn default() -> Self {
+        Self {
+            order: ORDER.to_vec(),
+            enemy_types: ENEMY_TYPE_TABLE,
+            units: UNIT_TABLE.iter().map(to_unit_entries).collect(),
+            assault: ASSAULT_TABLE.to_vec(),
+            shot_enable: SHOT_ENABLE_TABLE.to_vec(),
+            challenging_stages: CHALLENGING_STAGE_TABLE.iter().map(|entry| ChallengingStage {
+                enemy_types: entry.enemy_types,
+                units: to_unit_entries(&entry.units),
+            }).collect(),
+            trajectories: TRAJ_TABLE.iter()
+                .map(|(name, table)| (String::from(*name), TrajTable::Static(table)))
+                .collect(),
+        }
+    }
+}
+
+fn to_unit_entries(row: &[UnitTableEntry; UNIT_COUNT]) -> [UnitEntry; UNIT_COUNT] {
+    let mut entries = row.iter().map(|entry| UnitEntry {
+        pat: entry.pat,
+        traj: String::from(entry.traj),
+        flip_x: entry.flip_x,
+    });
+    [(); UNIT_COUNT].map(|_| entries.next().unwrap())
+}
+
+counted_array!(const TRAJ_TABLE: [(&str, &[TrajCommand]); _] = [
+    ("command1", &COMMAND_TABLE1),
+    ("command2", &COMMAND_TABLE2),
+    ("command3", &COMMAND_TABLE3),
+    (BEE_ATTACK, &BEE_ATTACK_TABLE),
+    (BEE_ATTACK_RUSH_CONT, &BEE_ATTACK_RUSH_CONT_TABLE),
+    (BUTTERFLY_ATTACK, &BUTTERFLY_ATTACK_TABLE),
+    (OWL_ATTACK, &OWL_ATTACK_TABLE),
+    (BEE_RUSH_ATTACK, &BEE_RUSH_ATTACK_TABLE),
+    (BUTTERFLY_RUSH_ATTACK, &BUTTERFLY_RUSH_ATTACK_TABLE),
+    (OWL_RUSH_ATTACK, &OWL_RUSH_ATTACK_TABLE),
+    ("challenge1", &CHALLENGE_TABLE1),
+    ("challenge2", &CHALLENGE_TABLE2),
+    ("challenge3", &CHALLENGE_TABLE3),
+    ("challenge4", &CHALLENGE_TABLE4),
+]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_roundtrip() {
+        let json = StagePack::default().to_json();
+        let stage_pack = StagePack::from_json(&json).unwrap();
+        assert_eq!(json, stage_pack.to_json());
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut stage_pack = StagePack::default();
+        stage_pack.units[1][2].traj = String::from("nothing");
+        assert_eq!(Err(String::from("units[1][2]: unknown trajectory `nothing`")),
+                   StagePack::from_json(&stage_pack.to_json()).map(|_| ()));
+
+        let mut stage_pack = StagePack::default();
+        stage_pack.order[3] = stage_pack.order[0];
+        assert_eq!(Err(String::from("order[3]: (4, 2) is duplicated")),
+                   StagePack::from_json(&stage_pack.to_json()).map(|_| ()));
+
+        let mut stage_pack = StagePack::default();
+        stage_pack.trajectories.remove(OWL_ATTACK);
+        assert_eq!(Err(String::from("trajectories: `owl_attack` is required")),
+                   StagePack::from_json(&stage_pack.to_json()).map(|_| ()));
+
+        assert!(StagePack::from_json("{}").unwrap_err().contains("missing field"));
+    }
+
+    #[test]
+    fn test_challenging_stage() {
+        let stage_pack = StagePack::default();
+        let stages: Vec<u16> = (0..12).filter(|&stage| stage_pack.challenging_stage(stage).is_some()).collect();
+        assert_eq!(vec![2, 6, 10], stages);
+
+        let mut stage_pack = StagePack::default();
+        stage_pack.challenging_stages.clear();
+        assert!(stage_pack.challenging_stage(2).is_none());
+    }
+
+    #[test]
+    fn test_traj_text() {
+        let mut stage_pack = StagePack::default();
+        let text = stage_pack.traj_text(BEE_ATTACK).unwrap();
+        stage_pack.trajectories.insert(String::from("command1"), TrajTable::from(Vec::new()));
+        let json = stage_pack.to_json().replace("\"command1\": []", &format!("\"command1\": {:?}", text));
+        let stage_pack = StagePack::from_json(&json).unwrap();
+        assert_eq!(&BEE_ATTACK_TABLE[..], &stage_pack.traj("command1")[..]);
+
+        let json = json.replace("\"Speed      2\\n", "\"Sped       2\\n");
+        assert!(StagePack::from_json(&json).unwrap_err().contains("Line 1, column 1: unknown command `Sped`"));
+    }
+}
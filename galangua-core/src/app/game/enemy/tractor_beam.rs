@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 use crate::framework::types::Vec2I;
 use crate::framework::RendererTrait;
 use crate::util::math::{round_up, ONE};
@@ -18,7 +20,7 @@ const Y_OFFSET_TABLE: [i32; 29] = [
     65, 68, 70, 73, 76,
 ];
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 enum State {
     Opening,
     Full,
@@ -27,7 +29,7 @@ enum State {
     Capturing,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct TractorBeam {
     pos: Vec2I,
     state: State,
@@ -104,6 +106,10 @@ impl TractorBeam {
         self.state == State::Closed
     }
 
+    pub fn is_full(&self) -> bool {
+        self.state == State::Full
+    }
+
     pub fn can_capture(&self, pos: &Vec2I) -> bool {
         const RANGE: i32 = 24 * ONE;
         if self.state == State::Full {
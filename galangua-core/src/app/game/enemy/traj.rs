@@ -1,15 +1,96 @@
-use super::traj_command::TrajCommand;
+use serde::de::{self, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::ops::Deref;
+use std::rc::Rc;
+
 use super::traj_command::TrajCommand::*;
+use super::traj_command::{parse_traj_commands, TrajCommand, TrajCond};
 use super::{Accessor, FormationIndex};
 
 use crate::app::consts::*;
 use crate::framework::types::{Vec2I, ZERO_VEC};
-use crate::util::math::{calc_velocity, ANGLE, COS_TABLE, ONE, SIN_TABLE};
+use crate::util::math::{atan2_lut, calc_velocity, clamp, diff_angle, ANGLE, COS_TABLE, ONE, SIN_TABLE};
+
+// Guard against a loop without wait.
+const MAX_COMMANDS_PER_FRAME: u32 = 256;
+
+// Command table, built in or owned.
+// Serialized as its commands, so deserialized one is always owned.
+// Also deserialized from a string in text format (see traj_command.rs).
+#[derive(Clone, Debug)]
+pub enum TrajTable {
+    Static(&'static [TrajCommand]),
+    Shared(Rc<[TrajCommand]>),
+}
 
-#[cfg(debug_assertions)]
-use crate::app::util::unsafe_util::extend_lifetime;
+impl Deref for TrajTable {
+    type Target = [TrajCommand];
+    fn deref(&self) -> &Self::Target {
+        match self {
+            TrajTable::Static(table) => table,
+            TrajTable::Shared(table) => table,
+        }
+    }
+}
+
+impl From<Vec<TrajCommand>> for TrajTable {
+    fn from(vec: Vec<TrajCommand>) -> Self {
+        TrajTable::Shared(Rc::from(vec))
+    }
+}
+
+impl Serialize for TrajTable {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for TrajTable {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(TrajTableVisitor)
+    }
+}
+
+struct TrajTableVisitor;
+
+impl<'de> Visitor<'de> for TrajTableVisitor {
+    type Value = TrajTable;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a command list or text")
+    }
+
+    fn visit_str<E: de::Error>(self, text: &str) -> Result<Self::Value, E> {
+        parse_traj_commands(text).map(TrajTable::from).map_err(E::custom)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+        Vec::<TrajCommand>::deserialize(de::value::SeqAccessDeserializer::new(seq)).map(TrajTable::from)
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+enum WaitPred {
+    YG(i32),
+    YL(i32),
+    XG(i32),
+    XL(i32),
+}
+
+impl WaitPred {
+    fn check(&self, pos: &Vec2I) -> bool {
+        match *self {
+            WaitPred::YG(value) => pos.y >= value,
+            WaitPred::YL(value) => pos.y <= value,
+            WaitPred::XG(value) => pos.x >= value,
+            WaitPred::XL(value) => pos.x <= value,
+        }
+    }
+}
 
 // Trajectory
+#[derive(Serialize, Deserialize)]
 pub struct Traj {
     pos: Vec2I,
     angle: i32,
@@ -20,19 +101,19 @@ pub struct Traj {
     fi: FormationIndex,
     pub(super) shot_enable: bool,
 
-    command_table: &'static [TrajCommand],
+    command_table: TrajTable,
+    command_index: usize,
     delay: u32,
-    wait_pred: Option<Box<dyn Fn(&Vec2I) -> bool>>,
+    wait_pred: Option<WaitPred>,
     shot: Option<u32>,
-
-    #[cfg(debug_assertions)]
-    command_table_vec: Option<Vec<TrajCommand>>,
+    #[serde(default)]
+    repeats: Vec<(usize, u32)>,  // command index of `Repeat`, and remaining count
+    #[serde(default)]
+    home: Option<i32>,  // max angular velocity
 }
 
 impl Traj {
-    pub fn new(command_table: &'static [TrajCommand], offset: &Vec2I, flip_x: bool,
-               fi: FormationIndex,
-    ) -> Self {
+    pub fn new(command_table: TrajTable, offset: &Vec2I, flip_x: bool, fi: FormationIndex) -> Self {
         let offset = if flip_x { Vec2I::new(-offset.x, offset.y) } else { *offset };
         Self {
             pos: ZERO_VEC,
@@ -44,28 +125,16 @@ impl Traj {
             fi,
             shot_enable: true,
 
-            command_table: command_table,
+            command_table,
+            command_index: 0,
             delay: 0,
             wait_pred: None,
             shot: None,
-
-            #[cfg(debug_assertions)]
-            command_table_vec: None,
+            repeats: Vec::new(),
+            home: None,
         }
     }
 
-    #[cfg(debug_assertions)]
-    pub fn new_with_vec(command_table_vec: Vec<TrajCommand>, offset: &Vec2I, flip_x: bool,
-                        fi: FormationIndex,
-    ) -> Self {
-        // command_table is owned by vec, so it lives as long as self and not worry about that.
-        let command_table = unsafe { extend_lifetime(&command_table_vec) };
-
-        let mut me = Self::new(command_table, offset, flip_x, fi);
-        me.command_table_vec = Some(command_table_vec);
-        me
-    }
-
     pub fn pos(&self) -> Vec2I {
         let a: usize = (((self.angle + ONE / 2) & ((ANGLE - 1) * ONE)) / ONE) as usize;
         let cs = COS_TABLE[a];
@@ -95,10 +164,16 @@ impl Traj {
     pub fn update(&mut self, accessor: &dyn Accessor) -> bool {
         self.handle_command(accessor);
 
+        if let Some(dlimit) = self.home {
+            let diff = &accessor.nearest_player_pos(&self.pos) - &self.pos;
+            let target_angle = atan2_lut(-diff.y, diff.x);
+            self.vangle = clamp(diff_angle(target_angle, self.angle), -dlimit, dlimit);
+        }
+
         self.pos += calc_velocity(self.angle + self.vangle / 2, self.speed);
         self.angle += self.vangle;
 
-        !self.command_table.is_empty() || self.delay > 0
+        self.command_index < self.command_table.len() || self.delay > 0
     }
 
     fn handle_command(&mut self, accessor: &dyn Accessor) {
@@ -107,22 +182,23 @@ impl Traj {
             return;
         }
         if let Some(wait_pred) = &self.wait_pred {
-            if !(wait_pred)(&self.pos) {
+            if !wait_pred.check(&self.pos) {
                 return;
             }
+            self.wait_pred = None;
+        }
+        if self.home.take().is_some() {
+            self.vangle = 0;
         }
 
-        if !self.command_table.is_empty() {
-            let mut i = 0;
-            while i < self.command_table.len() {
-                let command = &self.command_table[i];
-                i += 1;
-                if !self.handle_one_command(command, accessor) {
-                    break;
-                }
+        let mut count = 0;
+        while self.command_index < self.command_table.len() && count < MAX_COMMANDS_PER_FRAME {
+            let command = self.command_table[self.command_index].clone();
+            self.command_index += 1;
+            count += 1;
+            if !self.handle_one_command(&command, accessor) {
+                break;
             }
-
-            self.command_table = &self.command_table[i..];
         }
     }
 
@@ -154,10 +230,8 @@ impl Traj {
                 return false;
             }
             Accelerate => {
-                const MAX_STAGE: i32 = 64;
                 const MAX_SPEED: i32 = 5 * ONE;
-                let stage_no = accessor.get_stage_no();
-                self.speed += (MAX_SPEED - self.speed) * std::cmp::min(stage_no as i32, MAX_STAGE) / MAX_STAGE;
+                self.speed += (MAX_SPEED - self.speed) * accessor.rank() / ONE;
             }
             DestAngle(mut dest_angle, radius) => {
                 if self.flip_x {
@@ -177,7 +251,19 @@ impl Traj {
                 return false;
             }
             WaitYG(value) => {
-                self.wait_pred = Some(Box::new(move |&pos| pos.y >= value));
+                self.wait_pred = Some(WaitPred::YG(value));
+                return false;
+            }
+            WaitYL(value) => {
+                self.wait_pred = Some(WaitPred::YL(value));
+                return false;
+            }
+            WaitXG(value) => {
+                self.wait_pred = Some(if self.flip_x { WaitPred::XL(WIDTH * ONE - value) } else { WaitPred::XG(value) });
+                return false;
+            }
+            WaitXL(value) => {
+                self.wait_pred = Some(if self.flip_x { WaitPred::XG(WIDTH * ONE - value) } else { WaitPred::XL(value) });
                 return false;
             }
             AddPos(mut x, y) => {
@@ -196,7 +282,232 @@ impl Traj {
                     self.shot = Some(delay);
                 }
             }
+            Label(_) => {}
+            Jump(label) => {
+                self.jump(label);
+            }
+            JumpIf(label, cond) => {
+                if self.check_cond(cond, accessor) {
+                    self.jump(label);
+                }
+            }
+            Repeat(label, count) => {
+                let index = self.command_index - 1;
+                let remaining = match self.repeats.iter_mut().find(|(repeat_index, _)| *repeat_index == index) {
+                    Some((_, remaining)) => {
+                        *remaining -= 1;
+                        *remaining
+                    }
+                    None => {
+                        self.repeats.push((index, count.saturating_sub(1)));
+                        count.saturating_sub(1)
+                    }
+                };
+                if remaining > 0 {
+                    self.jump(label);
+                } else {
+                    self.repeats.retain(|(repeat_index, _)| *repeat_index != index);
+                }
+            }
+            Home(frames, dlimit) => {
+                self.home = Some(dlimit);
+                self.delay = frames;
+                return false;
+            }
         }
         true
     }
+
+    fn jump(&mut self, label: u32) {
+        let from = self.command_index - 1;
+        // Unknown label ends the trajectory.
+        let to = find_label(&self.command_table, label).unwrap_or(self.command_table.len());
+        self.command_index = to;
+
+        // Jumping out of the block of a `Repeat` forgets its count.
+        let table = &self.command_table;
+        self.repeats.retain(|&(index, _)| {
+            let start = match table.get(index) {
+                Some(Repeat(label, _)) => find_label(table, *label).unwrap_or(index),
+                _ => index,
+            };
+            let block = start..=index;
+            !block.contains(&from) || block.contains(&to)
+        });
+    }
+
+    fn check_cond(&self, cond: TrajCond, accessor: &dyn Accessor) -> bool {
+        match cond {
+            TrajCond::PlayerLeft | TrajCond::PlayerRight => {
+                let left = accessor.nearest_player_pos(&self.pos).x < self.pos.x;
+                left == ((cond == TrajCond::PlayerLeft) ^ self.flip_x)
+            }
+            TrajCond::StageGE(stage) => accessor.get_stage_no() >= stage,
+        }
+    }
+}
+
+fn find_label(table: &[TrajCommand], label: u32) -> Option<usize> {
+    table.iter().position(|command| *command == Label(label))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro128Plus;
+
+    use super::super::{Enemy, StagePack};
+    use crate::app::game::{CaptureState, GameConfig};
+
+    struct TestAccessor {
+        player_pos: Vec2I,
+        stage: u16,
+        config: GameConfig,
+    }
+
+    impl TestAccessor {
+        fn new(player_x: i32) -> Self {
+            Self { player_pos: Vec2I::new(player_x, (HEIGHT - 32) * ONE), stage: 0, config: GameConfig::default() }
+        }
+    }
+
+    impl Accessor for TestAccessor {
+        fn player_count(&self) -> usize { 1 }
+        fn get_raw_player_pos(&self, _player_no: usize) -> Option<&Vec2I> { Some(&self.player_pos) }
+        fn get_dual_player_pos(&self, _player_no: usize) -> Option<Vec2I> { None }
+        fn is_player_dual(&self, _player_no: usize) -> bool { false }
+        fn can_player_capture(&self, _player_no: usize) -> bool { false }
+        fn is_player_capture_completed(&self) -> bool { false }
+        fn capture_state(&self) -> CaptureState { CaptureState::NoCapture }
+        fn captured_fighter_index(&self) -> Option<FormationIndex> { None }
+        fn get_enemies(&self) -> &[Option<Enemy>] { &[] }
+        fn get_enemy_at(&self, _formation_index: &FormationIndex) -> Option<&Enemy> { None }
+        fn get_enemy_at_mut(&mut self, _formation_index: &FormationIndex) -> Option<&mut Enemy> { None }
+        fn get_formation_pos(&self, _formation_index: &FormationIndex) -> Vec2I { ZERO_VEC }
+        fn pause_enemy_shot(&mut self, _wait: u32) {}
+        fn is_rush(&self) -> bool { false }
+        fn get_stage_no(&self) -> u16 { self.stage }
+        fn rng(&mut self) -> &mut Xoshiro128Plus { unreachable!() }
+        fn stage_pack(&self) -> &Rc<StagePack> { unreachable!() }
+        fn game_config(&self) -> &GameConfig { &self.config }
+    }
+
+    fn new_traj(commands: Vec<TrajCommand>, flip_x: bool) -> Traj {
+        Traj::new(TrajTable::from(commands), &ZERO_VEC, flip_x, FormationIndex(0, 0))
+    }
+
+    #[test]
+    fn test_accelerate() {
+        let mut accessor = TestAccessor::new(WIDTH / 2 * ONE);
+        let mut traj = new_traj(vec![Accelerate, Delay(1), Accelerate], false);
+        traj.update(&accessor);
+        assert_eq!(0, traj.speed);
+
+        accessor.stage = accessor.config.rank_max_stage / 2;
+        traj.update(&accessor);
+        traj.update(&accessor);
+        assert_eq!(5 * ONE / 2, traj.speed);
+    }
+
+    #[test]
+    fn test_jump() {
+        let accessor = TestAccessor::new(WIDTH / 2 * ONE);
+        let mut traj = new_traj(vec![Jump(1), AddPos(ONE, 0), Label(1), AddPos(0, ONE)], false);
+        assert!(!traj.update(&accessor));
+        assert_eq!(Vec2I::new(0, ONE), traj.pos());
+    }
+
+    #[test]
+    fn test_jump_if() {
+        let commands = vec![
+            JumpIf(1, TrajCond::PlayerLeft), AddPos(ONE, 0), Label(1),
+            JumpIf(2, TrajCond::StageGE(3)), AddPos(0, ONE), Label(2),
+        ];
+        let run = |player_x: i32, stage: u16, flip_x: bool| {
+            let mut accessor = TestAccessor::new(player_x);
+            accessor.stage = stage;
+            let mut traj = new_traj(commands.clone(), flip_x);
+            traj.set_pos(&Vec2I::new(WIDTH / 2 * ONE, 0));
+            traj.update(&accessor);
+            &traj.pos() - &Vec2I::new(WIDTH / 2 * ONE, 0)
+        };
+
+        assert_eq!(Vec2I::new(0, ONE), run(0, 0, false));
+        assert_eq!(Vec2I::new(ONE, ONE), run(WIDTH * ONE, 0, false));
+        assert_eq!(Vec2I::new(ONE, 0), run(WIDTH * ONE, 3, false));
+        // Mirrored: the player on the left is on the right side of the flipped trajectory.
+        assert_eq!(Vec2I::new(-ONE, 0), run(0, 3, true));
+        assert_eq!(Vec2I::new(0, 0), run(WIDTH * ONE, 3, true));
+    }
+
+    #[test]
+    fn test_nested_repeat() {
+        let accessor = TestAccessor::new(WIDTH / 2 * ONE);
+        let mut traj = new_traj(vec![
+            Label(1), Label(2), AddPos(ONE, 0), Repeat(2, 3),
+            AddPos(0, ONE), Repeat(1, 2),
+        ], false);
+        assert!(!traj.update(&accessor));
+        assert_eq!(Vec2I::new(6 * ONE, 2 * ONE), traj.pos());
+        assert!(traj.repeats.is_empty());
+    }
+
+    #[test]
+    fn test_jump_out_of_repeat() {
+        // The inner block is left by `JumpIf` on its second run, every time.
+        let accessor = TestAccessor::new(ONE * 3 / 2);
+        let mut traj = new_traj(vec![
+            Label(1),
+            Label(2), AddPos(ONE, 0), JumpIf(3, TrajCond::PlayerLeft), Repeat(2, 5),
+            Label(3), AddPos(-2 * ONE, ONE), Repeat(1, 3),
+        ], false);
+        assert!(!traj.update(&accessor));
+        assert_eq!(Vec2I::new(0, 3 * ONE), traj.pos());
+        assert!(traj.repeats.is_empty());
+    }
+
+    #[test]
+    fn test_home() {
+        let run = |player_x: i32| {
+            let accessor = TestAccessor::new(player_x);
+            let mut traj = new_traj(vec![Speed(ONE), Home(3, 2 * ONE), Delay(5)], false);
+            traj.set_pos(&Vec2I::new(WIDTH / 2 * ONE, 100 * ONE));
+            for _ in 0..4 {
+                traj.update(&accessor);
+            }
+            let angle = traj.angle();
+            traj.update(&accessor);  // Homing ends.
+            assert_eq!(0, traj.vangle);
+            assert_eq!(angle, traj.angle());
+            angle
+        };
+
+        assert_eq!(-4 * 2 * ONE, run(0));
+        assert_eq!(4 * 2 * ONE, run(WIDTH * ONE));
+    }
+
+    #[test]
+    fn test_wait() {
+        let accessor = TestAccessor::new(WIDTH / 2 * ONE);
+        let mut traj = new_traj(vec![Angle(128 * ONE), Speed(ONE), WaitYG(3 * ONE), Speed(0)], false);
+        for _ in 0..10 {
+            traj.update(&accessor);
+        }
+        assert_eq!(Vec2I::new(0, 3 * ONE), traj.pos());
+
+        let run = |flip_x: bool| {
+            let mut traj = new_traj(vec![
+                Pos(0, 0), Delay(2), Angle(64 * ONE), Speed(ONE), WaitXG(3 * ONE), Speed(0),
+            ], flip_x);
+            let mut positions = Vec::new();
+            for _ in 0..10 {
+                traj.update(&accessor);
+                positions.push(traj.pos().x);
+            }
+            positions
+        };
+        // Waits 2 frames, then moves until x reaches 3.
+        assert_eq!(vec![0, 0, 0, ONE, 2 * ONE, 3 * ONE, 3 * ONE, 3 * ONE, 3 * ONE, 3 * ONE], run(false));
+        assert_eq!(WIDTH * ONE - 3 * ONE, run(true)[9]);
+    }
 }
@@ -1,4 +1,8 @@
-#[derive(Clone, Debug, PartialEq)]
+use serde::{Deserialize, Serialize};
+
+use crate::util::math::ONE;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum TrajCommand {
     Pos(i32, i32),
     Speed(i32),
@@ -8,7 +12,309 @@ pub enum TrajCommand {
     Accelerate,
     DestAngle(i32, i32),
     WaitYG(i32),  // wait until y is greater than
+    WaitYL(i32),  // wait until y is less than
+    WaitXG(i32),  // wait until x is greater than (mirrored on flip)
+    WaitXL(i32),  // wait until x is less than (mirrored on flip)
     AddPos(i32, i32),
     CopyFormationX,
     Shot(u32),
+    Label(u32),
+    Jump(u32),
+    JumpIf(u32, TrajCond),
+    Repeat(u32, u32),  // jump to label until the block is run n times
+    Home(u32, i32),  // steer toward the player for frames, with max angular velocity
+}
+
+// Condition for `JumpIf`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TrajCond {
+    PlayerLeft,  // player is on the left side (mirrored on flip)
+    PlayerRight,
+    StageGE(u16),  // stage number (0 origin) is greater than or equal to
+}
+
+// Text format:
+//
+//   One command per line, name followed by its arguments separated by spaces.
+//   `#` starts a comment until the end of line, empty lines are ignored.
+//
+//     Pos        x y        Set position
+//     Speed      speed      Set speed (dots per frame)
+//     Angle      angle      Set angle (256 = 360 degrees)
+//     VAngle     vangle     Set angular velocity
+//     Delay      frames     Wait for frames
+//     Accelerate            Speed up toward 5 dots per frame, more in later stages
+//     DestAngle  angle r    Rotate toward the formation position
+//     WaitYG     y          Wait until y is greater than
+//     WaitYL     y          Wait until y is less than
+//     WaitXG     x          Wait until x is greater than
+//     WaitXL     x          Wait until x is less than
+//     AddPos     x y        Move position
+//     CopyFormationX        Set x to the formation position
+//     Shot       frames     Shoot after frames
+//     Label      label      Mark a jump target
+//     Jump       label      Continue from the label
+//     JumpIf     label cond Jump if the condition holds:
+//                             PlayerLeft, PlayerRight, StageGE n (stage 0 origin)
+//     Repeat     label n    Jump back to the label, until the block is run n times
+//     Home       frames va  Steer toward the player for frames, turning va at most a frame
+//
+//   Positions, speed and angles are in dots, fractions allowed (stored in fixed point, ONE = 256),
+//   frames, labels and counts are integers.
+//   On flip, x and angles are mirrored, also PlayerLeft and PlayerRight are swapped.
+
+pub fn parse_traj_commands(text: &str) -> Result<Vec<TrajCommand>, String> {
+    let mut commands = Vec::new();
+    let mut labels = Vec::new();
+    let mut references = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap();
+        let mut words = Words::new(i + 1, line);
+        let (column, name) = match words.next_word() {
+            Some(word) => word,
+            None => continue,
+        };
+
+        let command = match name {
+            "Pos" => TrajCommand::Pos(words.fixed()?, words.fixed()?),
+            "Speed" => TrajCommand::Speed(words.fixed()?),
+            "Angle" => TrajCommand::Angle(words.fixed()?),
+            "VAngle" => TrajCommand::VAngle(words.fixed()?),
+            "Delay" => TrajCommand::Delay(words.uint()?),
+            "Accelerate" => TrajCommand::Accelerate,
+            "DestAngle" => TrajCommand::DestAngle(words.fixed()?, words.fixed()?),
+            "WaitYG" => TrajCommand::WaitYG(words.fixed()?),
+            "WaitYL" => TrajCommand::WaitYL(words.fixed()?),
+            "WaitXG" => TrajCommand::WaitXG(words.fixed()?),
+            "WaitXL" => TrajCommand::WaitXL(words.fixed()?),
+            "AddPos" => TrajCommand::AddPos(words.fixed()?, words.fixed()?),
+            "CopyFormationX" => TrajCommand::CopyFormationX,
+            "Shot" => TrajCommand::Shot(words.uint()?),
+            "Label" => {
+                let label = words.uint()?;
+                if labels.contains(&label) {
+                    return Err(format!("Line {}, column {}: label {} is duplicated", words.lineno, column, label));
+                }
+                labels.push(label);
+                TrajCommand::Label(label)
+            }
+            "Jump" | "JumpIf" | "Repeat" => {
+                let label_column = words.column();
+                let label = words.uint()?;
+                references.push((words.lineno, label_column, label));
+                match name {
+                    "Jump" => TrajCommand::Jump(label),
+                    "JumpIf" => TrajCommand::JumpIf(label, words.cond()?),
+                    _ => TrajCommand::Repeat(label, words.uint()?),
+                }
+            }
+            "Home" => TrajCommand::Home(words.uint()?, words.fixed()?),
+            _ => return Err(format!("Line {}, column {}: unknown command `{}`", words.lineno, column, name)),
+        };
+        words.finish(name)?;
+        commands.push(command);
+    }
+
+    if let Some((lineno, column, label)) = references.iter().find(|(_, _, label)| !labels.contains(label)) {
+        return Err(format!("Line {}, column {}: label {} is not defined", lineno, column, label));
+    }
+    Ok(commands)
+}
+
+// Check that every jump has its label, for commands not from text.
+pub fn validate_traj_commands(commands: &[TrajCommand]) -> Result<(), String> {
+    let mut labels = Vec::new();
+    for (i, command) in commands.iter().enumerate() {
+        if let TrajCommand::Label(label) = *command {
+            if labels.contains(&label) {
+                return Err(format!("[{}]: label {} is duplicated", i, label));
+            }
+            labels.push(label);
+        }
+    }
+    for (i, command) in commands.iter().enumerate() {
+        match *command {
+            TrajCommand::Jump(label) | TrajCommand::JumpIf(label, _) | TrajCommand::Repeat(label, _)
+                if !labels.contains(&label) =>
+            {
+                return Err(format!("[{}]: label {} is not defined", i, label));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+pub fn format_traj_commands(commands: &[TrajCommand]) -> String {
+    let mut text = String::new();
+    for command in commands {
+        let line = match *command {
+            TrajCommand::Pos(x, y) => format!("Pos        {} {}", fixed(x), fixed(y)),
+            TrajCommand::Speed(speed) => format!("Speed      {}", fixed(speed)),
+            TrajCommand::Angle(angle) => format!("Angle      {}", fixed(angle)),
+            TrajCommand::VAngle(vangle) => format!("VAngle     {}", fixed(vangle)),
+            TrajCommand::Delay(frames) => format!("Delay      {}", frames),
+            TrajCommand::Accelerate => String::from("Accelerate"),
+            TrajCommand::DestAngle(angle, r) => format!("DestAngle  {} {}", fixed(angle), fixed(r)),
+            TrajCommand::WaitYG(y) => format!("WaitYG     {}", fixed(y)),
+            TrajCommand::WaitYL(y) => format!("WaitYL     {}", fixed(y)),
+            TrajCommand::WaitXG(x) => format!("WaitXG     {}", fixed(x)),
+            TrajCommand::WaitXL(x) => format!("WaitXL     {}", fixed(x)),
+            TrajCommand::AddPos(x, y) => format!("AddPos     {} {}", fixed(x), fixed(y)),
+            TrajCommand::CopyFormationX => String::from("CopyFormationX"),
+            TrajCommand::Shot(frames) => format!("Shot       {}", frames),
+            TrajCommand::Label(label) => format!("Label      {}", label),
+            TrajCommand::Jump(label) => format!("Jump       {}", label),
+            TrajCommand::JumpIf(label, cond) => {
+                let cond = match cond {
+                    TrajCond::PlayerLeft => String::from("PlayerLeft"),
+                    TrajCond::PlayerRight => String::from("PlayerRight"),
+                    TrajCond::StageGE(stage) => format!("StageGE {}", stage),
+                };
+                format!("JumpIf     {} {}", label, cond)
+            }
+            TrajCommand::Repeat(label, count) => format!("Repeat     {} {}", label, count),
+            TrajCommand::Home(frames, vangle) => format!("Home       {} {}", frames, fixed(vangle)),
+        };
+        text.push_str(&line);
+        text.push('\n');
+    }
+    text
+}
+
+// Exact, fixed point values have at most 8 fractional digits in decimal.
+fn fixed(value: i32) -> String {
+    format!("{}", value as f64 / ONE as f64)
+}
+
+// Words in a line, with their column (1-based).
+struct Words<'a> {
+    lineno: usize,
+    words: Vec<(usize, &'a str)>,
+    index: usize,
+    end_column: usize,
+}
+
+impl<'a> Words<'a> {
+    fn new(lineno: usize, line: &'a str) -> Self {
+        let mut words = Vec::new();
+        let mut start = None;
+        for (column, (i, c)) in line.char_indices().enumerate() {
+            if c.is_whitespace() {
+                if let Some((start_column, start_i)) = start.take() {
+                    words.push((start_column, &line[start_i..i]));
+                }
+            } else if start.is_none() {
+                start = Some((column + 1, i));
+            }
+        }
+        if let Some((start_column, start_i)) = start {
+            words.push((start_column, &line[start_i..]));
+        }
+        Self { lineno, words, index: 0, end_column: line.trim_end().chars().count() + 1 }
+    }
+
+    fn column(&self) -> usize {
+        self.words.get(self.index).map_or(self.end_column, |(column, _)| *column)
+    }
+
+    fn next_word(&mut self) -> Option<(usize, &'a str)> {
+        let word = self.words.get(self.index).copied();
+        if word.is_some() {
+            self.index += 1;
+        }
+        word
+    }
+
+    fn expect_word(&mut self, what: &str) -> Result<(usize, &'a str), String> {
+        self.next_word().ok_or_else(|| format!("Line {}, column {}: {} expected", self.lineno, self.end_column, what))
+    }
+
+    fn fixed(&mut self) -> Result<i32, String> {
+        let (column, word) = self.expect_word("number")?;
+        word.parse::<f64>().ok().filter(|v| v.is_finite())
+            .map(|v| (v * ONE as f64).round())
+            .filter(|v| *v >= i32::MIN as f64 && *v <= i32::MAX as f64)
+            .map(|v| v as i32)
+            .ok_or_else(|| format!("Line {}, column {}: number expected, but `{}`", self.lineno, column, word))
+    }
+
+    fn uint<T: std::str::FromStr>(&mut self) -> Result<T, String> {
+        let (column, word) = self.expect_word("integer")?;
+        word.parse::<T>()
+            .map_err(|_| format!("Line {}, column {}: integer expected, but `{}`", self.lineno, column, word))
+    }
+
+    fn cond(&mut self) -> Result<TrajCond, String> {
+        let (column, word) = self.expect_word("condition")?;
+        match word {
+            "PlayerLeft" => Ok(TrajCond::PlayerLeft),
+            "PlayerRight" => Ok(TrajCond::PlayerRight),
+            "StageGE" => Ok(TrajCond::StageGE(self.uint()?)),
+            _ => Err(format!("Line {}, column {}: unknown condition `{}`", self.lineno, column, word)),
+        }
+    }
+
+    fn finish(&mut self, name: &str) -> Result<(), String> {
+        match self.next_word() {
+            Some((column, _)) => Err(format!("Line {}, column {}: too many arguments for `{}`",
+                                             self.lineno, column, name)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::traj_command_table::*;
+
+    #[test]
+    fn test_parse() {
+        let text = "# comment\nSpeed 2.5\n\n  VAngle -3  # turn\nDelay 10\nCopyFormationX\n";
+        assert_eq!(Ok(vec![TrajCommand::Speed(640), TrajCommand::VAngle(-3 * ONE),
+                           TrajCommand::Delay(10), TrajCommand::CopyFormationX]),
+                   parse_traj_commands(text));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(Err(String::from("Line 2, column 3: unknown command `Spd`")),
+                   parse_traj_commands("Speed 1\n  Spd 1"));
+        assert_eq!(Err(String::from("Line 1, column 9: number expected, but `1.x`")),
+                   parse_traj_commands("Pos 1   1.x"));
+        assert_eq!(Err(String::from("Line 1, column 7: integer expected, but `-1`")),
+                   parse_traj_commands("Delay -1"));
+        assert_eq!(Err(String::from("Line 1, column 9: number expected")),
+                   parse_traj_commands("AddPos 1 # 2"));
+        assert_eq!(Err(String::from("Line 2, column 10: unknown condition `Player`")),
+                   parse_traj_commands("Label 1\nJumpIf 1 Player"));
+        assert_eq!(Err(String::from("Line 1, column 6: label 2 is not defined")),
+                   parse_traj_commands("Jump 2\nLabel 1"));
+        assert_eq!(Err(String::from("Line 1, column 12: too many arguments for `Accelerate`")),
+                   parse_traj_commands("Accelerate 1"));
+    }
+
+    #[test]
+    fn test_parse_flow() {
+        let text = "Label 1\nJumpIf 2 StageGE 3\nHome 30 1.5\nRepeat 1 4\nLabel 2\nJumpIf 1 PlayerLeft\n";
+        let commands = vec![
+            TrajCommand::Label(1), TrajCommand::JumpIf(2, TrajCond::StageGE(3)),
+            TrajCommand::Home(30, 384), TrajCommand::Repeat(1, 4),
+            TrajCommand::Label(2), TrajCommand::JumpIf(1, TrajCond::PlayerLeft),
+        ];
+        assert_eq!(Ok(commands.clone()), parse_traj_commands(text));
+        assert_eq!(Ok(commands.clone()), parse_traj_commands(&format_traj_commands(&commands)));
+        assert_eq!(Ok(()), validate_traj_commands(&commands));
+        assert_eq!(Err(String::from("[1]: label 3 is not defined")),
+                   validate_traj_commands(&[TrajCommand::Label(1), TrajCommand::Jump(3)]));
+    }
+
+    #[test]
+    fn test_format_roundtrip() {
+        for table in [&COMMAND_TABLE1[..], &COMMAND_TABLE2, &COMMAND_TABLE3, &BEE_ATTACK_TABLE,
+                      &BUTTERFLY_ATTACK_TABLE, &OWL_ATTACK_TABLE, &OWL_RUSH_ATTACK_TABLE].iter() {
+            assert_eq!(Ok(table.to_vec()), parse_traj_commands(&format_traj_commands(table)));
+        }
+    }
 }
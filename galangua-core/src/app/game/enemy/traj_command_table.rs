@@ -212,3 +212,59 @@ counted_array!(pub const OWL_RUSH_ATTACK_TABLE: [TrajCommand; _] = [
     CopyFormationX,
     Angle((ANGLE / 2) * ONE),
 ]);
+
+// Challenging stage: Fly through the screen, without going to the formation.
+
+counted_array!(pub const CHALLENGE_TABLE1: [TrajCommand; _] = [
+    Pos((WIDTH / 2 + 24) * ONE, -8 * ONE),
+    Speed(3 * ONE),
+    Angle((ANGLE / 2) * ONE),
+    VAngle(0),
+    Delay(30),
+    VAngle(3 * ONE),
+    Delay(85),
+    VAngle(0),
+    WaitYG((HEIGHT + 16) * ONE),
+]);
+
+counted_array!(pub const CHALLENGE_TABLE2: [TrajCommand; _] = [
+    Pos(-8 * ONE, 200 * ONE),
+    Speed(3 * ONE),
+    Angle((ANGLE / 4) * ONE),
+    VAngle(-ONE),
+    Delay(64),
+    VAngle(0),
+    WaitYL(-16 * ONE),
+]);
+
+counted_array!(pub const CHALLENGE_TABLE3: [TrajCommand; _] = [
+    Pos((WIDTH / 2 - 40) * ONE, -8 * ONE),
+    Speed(5 * ONE / 2),
+    Angle((ANGLE / 2) * ONE),
+    Label(1),
+    VAngle(2 * ONE),
+    Delay(12),
+    VAngle(-2 * ONE),
+    Delay(24),
+    VAngle(2 * ONE),
+    Delay(12),
+    Repeat(1, 3),
+    VAngle(0),
+    WaitYG((HEIGHT + 16) * ONE),
+]);
+
+counted_array!(pub const CHALLENGE_TABLE4: [TrajCommand; _] = [
+    Pos((WIDTH + 8) * ONE, 96 * ONE),
+    Speed(3 * ONE),
+    Angle((-ANGLE / 4) * ONE),
+    VAngle(0),
+    Delay(20),
+    VAngle(-2 * ONE),
+    Delay(64),
+    VAngle(0),
+    Delay(10),
+    VAngle(2 * ONE),
+    Delay(64),
+    VAngle(0),
+    WaitXL(-16 * ONE),
+]);
@@ -39,7 +39,7 @@ impl Index<usize> for EventQueue {
 
 #[derive(Clone, Copy, Debug)]
 pub enum EventType {
-    MyShot(Vec2I, bool, i32),
+    MyShot(Vec2I, bool, i32, usize),  // pos, dual, angle, player_no
     AddScore(u32),
     EneShot(Vec2I),
     EarnPointEffect(EarnedPointType, Vec2I),
@@ -48,7 +48,7 @@ pub enum EventType {
     DeadPlayer,
     StartCaptureAttack(FormationIndex),
     EndCaptureAttack,
-    CapturePlayer(Vec2I),
+    CapturePlayer(Vec2I, usize),  // capture pos, player_no
     CapturePlayerCompleted,
     CaptureSequenceEnded,
     SpawnCapturedFighter(Vec2I, FormationIndex),
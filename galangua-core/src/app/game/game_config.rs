@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+
+use crate::app::consts::*;
+use crate::util::math::ONE;
+
+pub const DEFAULT_STARTING_SHIPS: u32 = 3;
+pub const MAX_STARTING_SHIPS: u32 = 5;
+pub const MAX_ATTACKER_COUNT: usize = 5;
+const MAX_ATTACK_SHOTS: u32 = 8;  // Shot interval gets 0 beyond this.
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+    Arcade,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard, Difficulty::Arcade];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "EASY",
+            Difficulty::Normal => "NORMAL",
+            Difficulty::Hard => "HARD",
+            Difficulty::Arcade => "ARCADE",
+        }
+    }
+
+    // Case insensitive, e.g. from the command line.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|difficulty| difficulty.label().eq_ignore_ascii_case(name))
+    }
+}
+
+// Rules of a game, like DIP switches of the arcade.
+// The rank is how hard enemies get as stages go, 0 to ONE.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameConfig {
+    pub starting_ships: u32,
+    pub extend_first_score: u32,  // 0: no extend.
+    pub extend_after_score: u32,  // Every multiple of it after the first, 0: only the first.
+    pub rank_stage_offset: u16,   // Added to the stage for the rank, to start harder.
+    pub rank_max_stage: u16,      // Stage where the rank reaches the max.
+    pub max_attackers: usize,     // Enemies diving at once, 1..=MAX_ATTACKER_COUNT.
+    pub ene_shot_speed: [i32; 2],  // Bullet speed at rank 0 and at the max.
+    pub attack_shots: [u32; 2],   // Bullets of a dive at the first stage and at most, one more every 8 stages.
+    #[serde(default)]
+    pub dynamic_rank: i32,        // Max change of the rank by the play, 0: by the stage only.
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self::preset(Difficulty::Normal)
+    }
+}
+
+impl GameConfig {
+    pub fn preset(difficulty: Difficulty) -> Self {
+        let normal = Self {
+            starting_ships: DEFAULT_STARTING_SHIPS,
+            extend_first_score: EXTEND_FIRST_SCORE,
+            extend_after_score: EXTEND_AFTER_SCORE,
+            rank_stage_offset: 0,
+            rank_max_stage: 64,
+            max_attackers: 3,
+            ene_shot_speed: [ENE_SHOT_SPEED1, ENE_SHOT_SPEED2],
+            attack_shots: [2, 5],
+            dynamic_rank: ONE / 4,
+        };
+        match difficulty {
+            Difficulty::Normal => normal,
+            Difficulty::Easy => Self {
+                extend_first_score: 10_000,
+                extend_after_score: 40_000,
+                rank_max_stage: 96,
+                max_attackers: 2,
+                ene_shot_speed: [20 * ONE / 10, 30 * ONE / 10],
+                attack_shots: [1, 3],
+                dynamic_rank: ONE / 8,
+                ..normal
+            },
+            Difficulty::Hard => Self {
+                extend_first_score: 30_000,
+                extend_after_score: 100_000,
+                rank_stage_offset: 16,
+                rank_max_stage: 48,
+                max_attackers: 4,
+                ene_shot_speed: [30 * ONE / 10, 45 * ONE / 10],
+                attack_shots: [3, 6],
+                ..normal
+            },
+            // Factory setting of the original: extends at 20,000 and 70,000 and every 70,000,
+            // and the rank rises fast and follows the play more.
+            Difficulty::Arcade => Self {
+                extend_after_score: 70_000,
+                rank_max_stage: 32,
+                max_attackers: 4,
+                attack_shots: [2, 6],
+                dynamic_rank: ONE / 2,
+                ..normal
+            },
+        }
+    }
+
+    // Preset it is made from, apart from the starting ships. `None` for custom rules.
+    pub fn difficulty(&self) -> Option<Difficulty> {
+        Difficulty::ALL.iter().copied()
+            .find(|&difficulty| *self == Self { starting_ships: self.starting_ships, ..Self::preset(difficulty) })
+    }
+
+    // Keeps values in the range the game can handle.
+    pub fn validated(mut self) -> Self {
+        self.starting_ships = self.starting_ships.clamp(1, MAX_STARTING_SHIPS);
+        self.rank_max_stage = self.rank_max_stage.max(1);
+        self.max_attackers = self.max_attackers.clamp(1, MAX_ATTACKER_COUNT);
+        self.attack_shots[1] = self.attack_shots[1].min(MAX_ATTACK_SHOTS);
+        self.attack_shots[0] = self.attack_shots[0].min(self.attack_shots[1]);
+        self.dynamic_rank = self.dynamic_rank.clamp(0, ONE);
+        self
+    }
+
+    pub fn rank(&self, stage: u16) -> i32 {
+        let stage = stage.saturating_add(self.rank_stage_offset).min(self.rank_max_stage);
+        stage as i32 * ONE / self.rank_max_stage as i32
+    }
+
+    pub fn ene_shot_speed(&self, rank: i32) -> i32 {
+        let [min, max] = self.ene_shot_speed;
+        (max - min) * rank / ONE + min
+    }
+
+    pub fn attack_shot_count(&self, stage: u16) -> u32 {
+        let stage = stage.saturating_add(self.rank_stage_offset);
+        std::cmp::min(self.attack_shots[0] + stage as u32 / 8, self.attack_shots[1])
+    }
+
+    // Score to get the next ship after `score`, `None` if no more.
+    pub fn next_extend_score(&self, score: u32) -> Option<u32> {
+        if self.extend_first_score == 0 {
+            None
+        } else if score < self.extend_first_score {
+            Some(self.extend_first_score)
+        } else if self.extend_after_score == 0 {
+            None
+        } else {
+            let after = self.extend_after_score;
+            Some(score.div_ceil(after) * after)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normal_rank() {
+        // Same as before the presets.
+        let config = GameConfig::preset(Difficulty::Normal);
+        assert_eq!(0, config.rank(0));
+        assert_eq!(ONE / 2, config.rank(32));
+        assert_eq!(ONE, config.rank(100));
+        assert_eq!(ENE_SHOT_SPEED1, config.ene_shot_speed(config.rank(0)));
+        assert_eq!(ENE_SHOT_SPEED2, config.ene_shot_speed(config.rank(64)));
+        assert_eq!(2, config.attack_shot_count(0));
+        assert_eq!(5, config.attack_shot_count(40));
+    }
+
+    #[test]
+    fn test_extend() {
+        let config = GameConfig::preset(Difficulty::Arcade);
+        assert_eq!(Some(20_000), config.next_extend_score(0));
+        assert_eq!(Some(70_000), config.next_extend_score(20_010));
+        assert_eq!(Some(140_000), config.next_extend_score(70_010));
+
+        let config = GameConfig { extend_after_score: 0, ..config };
+        assert_eq!(None, config.next_extend_score(20_010));
+
+        assert_eq!(Some(Difficulty::Arcade), GameConfig { starting_ships: 5, ..GameConfig::preset(Difficulty::Arcade) }.difficulty());
+        assert_eq!(None, config.difficulty());
+        assert_eq!(Some(Difficulty::Hard), Difficulty::from_name("hard"));
+        assert_eq!(None, Difficulty::from_name("insane"));
+    }
+}
@@ -1,25 +1,36 @@
+use rand::SeedableRng;
+use rand_xoshiro::Xoshiro128Plus;
+use serde::{Deserialize, Serialize};
+use std::rc::Rc;
+
+use super::dynamic_rank::DynamicRank;
 use super::effect::{Effect, StageIndicator, StarManager};
 use super::enemy::Accessor as AccessorForEnemy;
-use super::enemy::{Enemy, EnemyManager, FormationIndex};
+use super::enemy::{Enemy, EnemyManager, FormationIndex, StagePack};
 use super::event_queue::{EventQueue, EventType};
+use super::game_config::GameConfig;
+use super::game_stats::GameStats;
 use super::player::Accessor as AccessorForPlayer;
 use super::player::{MyShot, Player};
 use super::score_holder::ScoreHolder;
 use super::CaptureState;
 
 use crate::app::consts::*;
+use crate::app::pilot::{EnemyObservation, Observation, ShotObservation, TractorBeamObservation};
 use crate::app::util::unsafe_util::peep;
 use crate::app::util::{CollBox, Collidable};
 use crate::framework::types::Vec2I;
 use crate::framework::{RendererTrait, SystemTrait};
-use crate::util::pad::Pad;
-use crate::util::math::ONE;
+use crate::util::pad::{Pad, PadBit};
+use crate::util::math::{round_up, round_up_i32, ANGLE, ONE};
 
 const MYSHOT_COUNT: usize = 2;
+const COOP_HOME_OFFSET: i32 = 32;
 const MAX_EFFECT_COUNT: usize = 16;
-const DEFAULT_LEFT_SHIP: u32 = 3;
+const CHALLENGING_HIT_POINT: u32 = 100;
+const CHALLENGING_PERFECT_BONUS: u32 = 10_000;
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 enum GameState {
     StartStage,
     Playing,
@@ -30,7 +41,10 @@ enum GameState {
     Captured,
     Recapturing,
     StageClear,
+    ChallengeResult,
+    TurnEnd,
     GameOver,
+    Result,
     Finished,
 
     #[cfg(debug_assertions)]
@@ -40,57 +54,131 @@ enum GameState {
 pub struct Params<'a> {
     pub star_manager: &'a mut StarManager,
     pub pad: &'a Pad,
+    pub pad2: &'a Pad,  // For player 2 in simultaneous play.
     pub score_holder: &'a mut ScoreHolder,
 }
 
+// Fighter, shots and stock of a player.
+#[derive(Serialize, Deserialize)]
+struct PlayerSlot {
+    player: Player,
+    myshots: [Option<MyShot>; MYSHOT_COUNT],
+    left_ship: u32,
+}
+
+impl PlayerSlot {
+    fn new(player_no: usize, home_x: i32, left_ship: u32) -> Self {
+        Self {
+            player: Player::new(player_no, home_x),
+            myshots: Default::default(),
+            left_ship,
+        }
+    }
+}
+
+// Events counted until taken, e.g. for the reward in learning.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EventTally {
+    pub score: u32,  // Sum of `AddScore`.
+    pub dead: u32,   // Count of `DeadPlayer`.
+}
+
+// Effects and events are transient, so they are not included in a snapshot.
+#[derive(Serialize, Deserialize)]
 pub struct GameManager {
     state: GameState,
     count: u32,
     stage_indicator: StageIndicator,
-    player: Player,
-    myshots: [Option<MyShot>; MYSHOT_COUNT],
+    players: Vec<PlayerSlot>,
     enemy_manager: EnemyManager,
+    #[serde(skip)]
     effects: [Option<Effect>; MAX_EFFECT_COUNT],
+    #[serde(skip, default = "EventQueue::new")]
     event_queue: EventQueue,
     stage: u16,
-    left_ship: u32,
     capture_state: CaptureState,
     capture_enemy_fi: FormationIndex,
+    capture_player: usize,  // Captured one, or rescuing one while recapturing.
+    #[serde(skip)]
+    hit_player: usize,  // Who gets the score of events in handling.
+    #[serde(skip)]
+    event_tally: EventTally,
+    rng: Xoshiro128Plus,
+    #[serde(skip)]
+    stage_pack: Rc<StagePack>,
+    #[serde(default)]
+    stats: GameStats,
+    #[serde(default)]
+    player_no: Option<usize>,
+    config: GameConfig,
+    dynamic_rank: DynamicRank,
 }
 
 impl GameManager {
-    pub fn new() -> Self {
+    pub fn new(stage_pack: Rc<StagePack>, config: GameConfig) -> Self {
+        let config = config.validated();
         Self {
             state: GameState::Playing,
             count: 0,
             stage_indicator: StageIndicator::new(),
-            player: Player::new(),
-            myshots: Default::default(),
+            players: vec![PlayerSlot::new(0, WIDTH / 2, config.starting_ships)],
             enemy_manager: EnemyManager::new(),
             event_queue: EventQueue::new(),
             effects: Default::default(),
 
             stage: 0,
-            left_ship: 0,
             capture_state: CaptureState::NoCapture,
             capture_enemy_fi: FormationIndex(0, 0),
+            capture_player: 0,
+            hit_player: 0,
+            event_tally: EventTally::default(),
+            rng: Xoshiro128Plus::seed_from_u64(0),
+            stage_pack,
+            stats: GameStats::default(),
+            player_no: None,
+            dynamic_rank: DynamicRank::new(config.dynamic_rank),
+            config,
         }
     }
 
+    // Stage pack is not included in a snapshot, so set it again after restoring.
+    pub fn set_stage_pack(&mut self, stage_pack: Rc<StagePack>) {
+        self.stage_pack = stage_pack;
+    }
+
     #[cfg(debug_assertions)]
     pub fn enemy_manager_mut(&mut self) -> &mut EnemyManager {
         &mut self.enemy_manager
     }
 
-    pub fn restart(&mut self) {
+    pub fn stats(&self) -> &GameStats {
+        &self.stats
+    }
+
+    // Player number (0 origin) in alternating play: the turn ends on death instead of restarting.
+    pub fn set_player_no(&mut self, player_no: Option<usize>) {
+        self.player_no = player_no;
+    }
+
+    // Same seed and same input reproduce the same game.
+    // Two players fight simultaneously when `player_count` is 2.
+    pub fn restart(&mut self, seed: u64, player_count: usize) {
+        self.rng = Xoshiro128Plus::seed_from_u64(seed);
         self.stage = 0;
         self.stage_indicator.set_stage(self.stage + 1);
-        self.left_ship = DEFAULT_LEFT_SHIP;
+        self.stats = GameStats::default();
+        self.stats.stage_reached = self.stage + 1;
+        self.dynamic_rank = DynamicRank::new(self.config.dynamic_rank);
 
         self.event_queue.clear();
-        self.player = Player::new();
+        self.players = if player_count >= 2 {
+            vec![PlayerSlot::new(0, WIDTH / 2 - COOP_HOME_OFFSET, self.config.starting_ships),
+                 PlayerSlot::new(1, WIDTH / 2 + COOP_HOME_OFFSET, self.config.starting_ships)]
+        } else {
+            vec![PlayerSlot::new(0, WIDTH / 2, self.config.starting_ships)]
+        };
+        self.capture_player = 0;
 
-        self.myshots = Default::default();
         self.effects = Default::default();
 
         self.state = GameState::StartStage;
@@ -102,11 +190,10 @@ impl GameManager {
         self.stage = 0;
         self.stage_indicator.set_stage(self.stage + 1);
 
-        self.enemy_manager.reset_stable();
+        self.enemy_manager.reset_stable(&self.stage_pack);
         self.event_queue.clear();
-        self.player = Player::new();
+        self.players = vec![PlayerSlot::new(0, WIDTH / 2, self.config.starting_ships)];
 
-        self.myshots = Default::default();
         self.effects = Default::default();
 
         self.state = GameState::EditTraj;
@@ -116,6 +203,62 @@ impl GameManager {
         self.state == GameState::Finished
     }
 
+    // After "GAME OVER" is shown.
+    pub fn is_game_over(&self) -> bool {
+        self.state == GameState::Result || self.state == GameState::Finished
+    }
+
+    // Seen from player 1, for a pilot.
+    pub fn observe(&self) -> Observation {
+        let slot = &self.players[0];
+        let enemies = self.enemy_manager.get_enemies().iter().flatten()
+            .filter(|enemy| !enemy.is_disappeared());
+        Observation {
+            in_game: !self.is_game_over(),
+            player_pos: if slot.player.active() { Some(round_up(slot.player.raw_pos())) } else { None },
+            dual: slot.player.is_dual(),
+            enemies: enemies.clone()
+                .map(|enemy| EnemyObservation {
+                    enemy_type: enemy.enemy_type(),
+                    state: enemy.state(),
+                    pos: enemy.pos(),
+                    angle: round_up_i32(enemy.angle()) & (ANGLE - 1),
+                })
+                .collect(),
+            ene_shots: self.enemy_manager.shots()
+                .map(|shot| ShotObservation { pos: shot.pos(), vel: shot.vel })
+                .collect(),
+            tractor_beams: enemies
+                .filter_map(|enemy| enemy.tractor_beam())
+                .map(|beam| TractorBeamObservation { pos: beam.pos(), full: beam.is_full() })
+                .collect(),
+            stage: self.stage + 1,
+            left_ship: slot.left_ship,
+        }
+    }
+
+    // Rank and the change by the play in it, for the debug overlay.
+    pub fn rank_detail(&self) -> (i32, i32) {
+        (AccessorForEnemy::rank(self), self.dynamic_rank.value())
+    }
+
+    pub fn take_event_tally(&mut self) -> EventTally {
+        std::mem::take(&mut self.event_tally)
+    }
+
+    pub fn is_turn_end(&self) -> bool {
+        self.state == GameState::TurnEnd
+    }
+
+    // Called when the turn comes round in alternating play.
+    pub fn start_turn(&mut self, star_manager: &mut StarManager) {
+        if self.state == GameState::TurnEnd {
+            self.restart_player();
+        } else {
+            star_manager.set_stop(false);
+        }
+    }
+
     pub fn update<S: SystemTrait>(&mut self, params: &mut Params, system: &mut S) {
         self.update_common(params, system);
 
@@ -134,8 +277,23 @@ impl GameManager {
                 }
             }
             GameState::Playing => {
-                if self.enemy_manager.all_destroyed() {
-                    self.state = GameState::StageClear;
+                if self.players.iter().any(|slot| slot.player.active()) {
+                    let dual = self.players.iter().any(|slot| slot.player.is_dual());
+                    self.dynamic_rank.update(dual);
+                }
+
+                // Another fighter lost while capturing or recapturing in simultaneous play.
+                if self.players.iter().any(|slot| slot.player.is_dead() && slot.left_ship > 0) {
+                    params.star_manager.set_stop(true);
+                    self.enemy_manager.pause_attack(true);
+                    self.state = GameState::PlayerDead;
+                    self.count = 0;
+                } else if self.enemy_manager.all_destroyed() {
+                    self.state = if self.is_challenging_stage() {
+                        GameState::ChallengeResult
+                    } else {
+                        GameState::StageClear
+                    };
                     self.count = 0;
                 }
             }
@@ -161,7 +319,9 @@ impl GameManager {
             GameState::WaitReady2 => {
                 self.count += 1;
                 if self.count >= 60 {
-                    self.player.set_shot_enable(true);
+                    for slot in self.players.iter_mut() {
+                        slot.player.set_shot_enable(true);
+                    }
                     self.enemy_manager.pause_attack(false);
                     params.star_manager.set_stop(false);
                     self.state = GameState::Playing;
@@ -173,14 +333,38 @@ impl GameManager {
                 if self.count >= 60 {
                     self.stage = self.stage.saturating_add(1);
                     self.stage_indicator.set_stage(std::cmp::min(self.stage, 255) + 1);
+                    self.stats.stage_reached = self.stage.saturating_add(1);
 
                     self.state = GameState::StartStage;
                     self.count = 0;
                 }
             }
+            GameState::ChallengeResult => {
+                self.count += 1;
+                if self.count == 90 {
+                    let bonus = self.challenge_bonus();
+                    for player_no in 0..self.players.len() {
+                        if self.players[player_no].left_ship > 0 {
+                            self.add_score(player_no, bonus, params.score_holder, system);
+                        }
+                    }
+                }
+                if self.count >= 240 {
+                    self.state = GameState::StageClear;
+                    self.count = 0;
+                }
+            }
+            GameState::TurnEnd => {}
             GameState::GameOver => {
                 self.count += 1;
                 if self.count >= 35 * 60 / 10 {
+                    self.state = GameState::Result;
+                    self.count = 0;
+                }
+            }
+            GameState::Result => {
+                self.count += 1;
+                if self.count >= 10 * 60 || (self.count >= 60 && params.pad.is_trigger(PadBit::A)) {
                     self.state = GameState::Finished;
                 }
             }
@@ -191,25 +375,57 @@ impl GameManager {
         }
     }
 
+    fn is_challenging_stage(&self) -> bool {
+        self.stage_pack.is_challenging_stage(self.stage)
+    }
+
+    fn is_challenge_perfect(&self) -> bool {
+        let appeared = self.enemy_manager.appeared_count();
+        appeared > 0 && self.enemy_manager.hit_count() >= appeared
+    }
+
+    fn challenge_bonus(&self) -> u32 {
+        if self.is_challenge_perfect() {
+            CHALLENGING_PERFECT_BONUS
+        } else {
+            self.enemy_manager.hit_count() * CHALLENGING_HIT_POINT
+        }
+    }
+
     fn next_player(&mut self) {
-        self.left_ship -= 1;
-        if self.left_ship == 0 {
+        for slot in self.players.iter_mut().filter(|slot| slot.player.is_lost() && slot.left_ship > 0) {
+            slot.left_ship -= 1;
+        }
+        if self.players.iter().all(|slot| slot.left_ship == 0) {
             self.enemy_manager.pause_attack(true);
             self.state = GameState::GameOver;
             self.count = 0;
-        } else {
-            self.player.restart();
-            self.player.set_shot_enable(false);
-            self.state = GameState::WaitReady2;
+        } else if self.player_no.is_some() {
+            self.state = GameState::TurnEnd;
             self.count = 0;
+        } else {
+            self.restart_player();
+        }
+    }
+
+    fn restart_player(&mut self) {
+        for slot in self.players.iter_mut().filter(|slot| slot.player.is_lost() && slot.left_ship > 0) {
+            slot.player.restart();
+            slot.player.set_shot_enable(false);
         }
+        self.state = GameState::WaitReady2;
+        self.count = 0;
     }
 
     fn update_common<S: SystemTrait>(&mut self, params: &mut Params, system: &mut S) {
-        {
-            let accessor = unsafe { peep(self) };
-            self.player.update(params.pad, accessor, &mut self.event_queue);
-            for myshot_opt in self.myshots.iter_mut().filter(|x| x.is_some()) {
+        let pads = [params.pad, params.pad2];
+        let accessor = unsafe { peep(self) };
+        for (slot, pad) in self.players.iter_mut().zip(pads.iter()) {
+            if slot.left_ship == 0 {
+                continue;
+            }
+            slot.player.update(pad, accessor, &mut self.event_queue);
+            for myshot_opt in slot.myshots.iter_mut().filter(|x| x.is_some()) {
                 let myshot = myshot_opt.as_mut().unwrap();
                 if !myshot.update() {
                     *myshot_opt = None;
@@ -225,11 +441,12 @@ impl GameManager {
         // For MyShot.
         self.handle_event_queue(params, system);
 
-        //
-
-        self.check_collision();
-
-        self.handle_event_queue(params, system);
+        // Events are handled for each player, to know who gets the score.
+        for player_no in 0..self.players.len() {
+            self.hit_player = player_no;
+            self.check_collision(player_no);
+            self.handle_event_queue(params, system);
+        }
 
         for effect_opt in self.effects.iter_mut().filter(|x| x.is_some()) {
             let effect = effect_opt.as_mut().unwrap();
@@ -243,10 +460,14 @@ impl GameManager {
     where
         R: RendererTrait,
     {
-        self.player.draw(renderer);
+        for slot in self.players.iter().filter(|slot| slot.left_ship > 0) {
+            slot.player.draw(renderer);
+        }
         self.enemy_manager.draw(renderer);
-        for myshot in self.myshots.iter().flat_map(|x| x) {
-            myshot.draw(renderer);
+        for slot in self.players.iter() {
+            for myshot in slot.myshots.iter().flat_map(|x| x) {
+                myshot.draw(renderer);
+            }
         }
 
         for effect in self.effects.iter().flat_map(|x| x) {
@@ -254,20 +475,42 @@ impl GameManager {
         }
         self.stage_indicator.draw(renderer);
 
-        if self.left_ship > 0 {
-            let disp_count = std::cmp::min(self.left_ship - 1, 8);
-            for i in 0..disp_count {
-                renderer.draw_sprite("rustacean", &Vec2I::new(i as i32 * 16, HEIGHT - 16));
+        for (player_no, slot) in self.players.iter().enumerate() {
+            if slot.left_ship > 0 {
+                let disp_count = std::cmp::min(slot.left_ship - 1, 8);
+                for i in 0..disp_count {
+                    // Player 2 from the right.
+                    let x = if player_no == 0 { i as i32 * 16 } else { WIDTH - 16 - i as i32 * 16 };
+                    renderer.draw_sprite("rustacean", &Vec2I::new(x, HEIGHT - 16));
+                }
+            }
+        }
+
+        if let Some(player_no) = self.player_no {
+            match self.state {
+                GameState::StartStage | GameState::WaitReady2 | GameState::GameOver => {
+                    renderer.set_texture_color_mod("font", 0, 255, 255);
+                    renderer.draw_str("font", (28 - 8) / 2 * 8, 16 * 8, &format!("PLAYER {}", player_no + 1));
+                }
+                _ => {}
             }
         }
 
         match self.state {
             GameState::StartStage => {
                 renderer.set_texture_color_mod("font", 0, 255, 255);
-                renderer.draw_str("font", 10 * 8, 18 * 8, &format!("STAGE {}", self.stage + 1));
+                if self.is_challenging_stage() {
+                    renderer.draw_str("font", (28 - 17) / 2 * 8, 18 * 8, "CHALLENGING STAGE");
+                } else {
+                    renderer.draw_str("font", 10 * 8, 18 * 8, &format!("STAGE {}", self.stage + 1));
+                }
+            }
+            GameState::ChallengeResult => {
+                self.draw_challenge_result(renderer);
             }
             GameState::WaitReady | GameState::WaitReady2 => {
-                if self.left_ship > 1 || self.state == GameState::WaitReady2 {
+                let respawn = self.players.iter().any(|slot| slot.player.is_lost() && slot.left_ship > 1);
+                if (respawn && self.player_no.is_none()) || self.state == GameState::WaitReady2 {
                     renderer.set_texture_color_mod("font", 0, 255, 255);
                     renderer.draw_str("font", (28 - 6) / 2 * 8, 18 * 8, "READY");
                 }
@@ -282,16 +525,68 @@ impl GameManager {
                 renderer.set_texture_color_mod("font", 0, 255, 255);
                 renderer.draw_str("font", (28 - 8) / 2 * 8, 18 * 8, "GAME OVER");
             }
+            GameState::Result => {
+                self.draw_result(renderer);
+            }
             _ => {}
         }
     }
 
+    fn draw_result<R: RendererTrait>(&self, renderer: &mut R) {
+        let stats = &self.stats;
+        renderer.set_texture_color_mod("font", 255, 0, 0);
+        renderer.draw_str("font", 8 * 8, 10 * 8, "-RESULTS-");
+
+        renderer.set_texture_color_mod("font", 255, 255, 0);
+        renderer.draw_str("font", 3 * 8, 13 * 8, "SHOTS FIRED");
+        renderer.draw_str("font", 3 * 8, 15 * 8, "NUMBER OF HITS");
+        renderer.set_texture_color_mod("font", 255, 255, 255);
+        renderer.draw_str("font", 19 * 8, 13 * 8, &format!("{:>6}", stats.shots_fired));
+        renderer.draw_str("font", 19 * 8, 15 * 8, &format!("{:>6}", stats.hits));
+
+        let ratio = stats.hit_ratio();
+        renderer.set_texture_color_mod("font", 0, 255, 255);
+        renderer.draw_str("font", 3 * 8, 17 * 8, "HIT-MISS RATIO");
+        renderer.draw_str("font", 19 * 8, 17 * 8, &format!("{:>4}.{} %", ratio / 10, ratio % 10));
+
+        renderer.set_texture_color_mod("font", 255, 255, 255);
+        renderer.draw_str("font", 3 * 8, 20 * 8, &format!("STAGE {:>3}", stats.stage_reached));
+        renderer.draw_str("font", 3 * 8, 22 * 8,
+                          &format!("CAPTURED {:>2}  RESCUED {:>2}", stats.captures, stats.rescues));
+    }
+
+    fn draw_challenge_result<R: RendererTrait>(&self, renderer: &mut R) {
+        if self.count >= 30 {
+            renderer.set_texture_color_mod("font", 0, 255, 255);
+            renderer.draw_str("font", 4 * 8, 16 * 8, "NUMBER OF HITS");
+        }
+        if self.count >= 60 {
+            renderer.draw_str("font", 20 * 8, 16 * 8, &format!("{:>4}", self.enemy_manager.hit_count()));
+        }
+        if self.count >= 90 {
+            if self.is_challenge_perfect() {
+                renderer.set_texture_color_mod("font", 255, 0, 0);
+                renderer.draw_str("font", 4 * 8, 19 * 8, "PERFECT !");
+                renderer.set_texture_color_mod("font", 255, 255, 0);
+                renderer.draw_str("font", 2 * 8, 21 * 8,
+                                  &format!("SPECIAL BONUS {} PTS", CHALLENGING_PERFECT_BONUS));
+            } else {
+                renderer.set_texture_color_mod("font", 255, 255, 0);
+                renderer.draw_str("font", 4 * 8, 19 * 8, "BONUS");
+                renderer.draw_str("font", 14 * 8, 19 * 8, &format!("{:>10}", self.challenge_bonus()));
+            }
+        }
+    }
+
     fn handle_event_queue<S: SystemTrait>(&mut self, params: &mut Params, system: &mut S) {
         let mut i = 0;
         while i < self.event_queue.len() {
             match self.event_queue[i] {
-                EventType::MyShot(pos, dual, angle) => {
-                    if self.spawn_myshot(&pos, dual, angle) {
+                EventType::MyShot(pos, dual, angle, player_no) => {
+                    if self.spawn_myshot(player_no, &pos, dual, angle) {
+                        let count = if dual { 2 } else { 1 };
+                        self.stats.shots_fired += count;
+                        self.dynamic_rank.on_shot(count);
                         system.play_se(CH_SHOT, SE_MYSHOT);
                     }
                 }
@@ -299,13 +594,14 @@ impl GameManager {
                     self.spawn_ene_shot(&pos);
                 }
                 EventType::AddScore(add) => {
-                    self.add_score(params.score_holder.score, add, system);
-                    params.score_holder.add_score(add);
+                    self.event_tally.score += add;
+                    self.add_score(self.hit_player, add, params.score_holder, system);
                 }
                 EventType::EarnPointEffect(point_type, pos) => {
                     self.spawn_effect(Effect::create_earned_point(point_type, &pos));
                 }
                 EventType::EnemyExplosion(pos, angle, enemy_type) => {
+                    self.stats.add_kill(enemy_type);
                     self.spawn_effect(Effect::create_flash_enemy(&pos, angle, enemy_type));
                     self.spawn_effect(Effect::create_enemy_explosion(&pos));
                     system.play_se(CH_BOMB, SE_BOMB_ENEMY);
@@ -315,8 +611,13 @@ impl GameManager {
                     system.play_se(CH_BOMB, SE_BOMB_PLAYER);
                 }
                 EventType::DeadPlayer => {
+                    self.event_tally.dead += 1;
+                    self.dynamic_rank.on_dead();
                     params.star_manager.set_stop(true);
-                    if self.state != GameState::Recapturing {
+                    // Otherwise handled after the capture sequence.
+                    if self.state != GameState::Recapturing && self.state != GameState::Capturing &&
+                        self.state != GameState::Captured
+                    {
                         self.enemy_manager.pause_attack(true);
                         self.state = GameState::PlayerDead;
                         self.count = 0;
@@ -330,16 +631,18 @@ impl GameManager {
                     self.capture_state = CaptureState::NoCapture;
                     self.capture_enemy_fi = FormationIndex(0, 0);
                 }
-                EventType::CapturePlayer(capture_pos) => {
+                EventType::CapturePlayer(capture_pos, player_no) => {
                     params.star_manager.set_capturing(true);
                     self.enemy_manager.pause_attack(true);
-                    self.player.start_capture(&capture_pos);
+                    self.capture_player = player_no;
+                    self.players[player_no].player.start_capture(&capture_pos);
                     self.state = GameState::Capturing;
                     self.capture_state = CaptureState::Capturing;
                 }
                 EventType::CapturePlayerCompleted => {
                     params.star_manager.set_capturing(false);
-                    self.player.complete_capture();
+                    self.players[self.capture_player].player.complete_capture();
+                    self.stats.captures += 1;
                     self.capture_state = CaptureState::Captured;
                     self.state = GameState::Captured;
                     self.count = 0;
@@ -355,7 +658,9 @@ impl GameManager {
                         &captured_fighter_index)
                     {
                         let pos = captured_fighter.raw_pos();
-                        self.player.start_recapture_effect(&pos);
+                        self.capture_player = self.hit_player;
+                        self.players[self.capture_player].player.start_recapture_effect(&pos);
+                        self.stats.rescues += 1;
                         self.enemy_manager.remove_enemy(&captured_fighter_index);
                         self.enemy_manager.pause_attack(true);
                         self.state = GameState::Recapturing;
@@ -363,7 +668,7 @@ impl GameManager {
                     }
                 }
                 EventType::MovePlayerHomePos => {
-                    self.player.start_move_home_pos();
+                    self.players[self.capture_player].player.start_move_home_pos();
                 }
                 EventType::RecaptureEnded => {
                     self.enemy_manager.pause_attack(false);
@@ -376,7 +681,7 @@ impl GameManager {
                     self.capture_state = CaptureState::NoCapture;
                     self.capture_enemy_fi = FormationIndex(0, 0);
                     params.star_manager.set_capturing(false);
-                    self.player.escape_capturing();
+                    self.players[self.capture_player].player.escape_capturing();
                 }
                 EventType::EscapeEnded => {
                     self.enemy_manager.pause_attack(false);
@@ -395,24 +700,27 @@ impl GameManager {
         self.event_queue.clear();
     }
 
-    fn add_score<S: SystemTrait>(&mut self, before: u32, add: u32, system: &mut S) {
-        let ext = if before < EXTEND_FIRST_SCORE {
-            EXTEND_FIRST_SCORE
-        } else {
-            (before + EXTEND_AFTER_SCORE - 1) / EXTEND_AFTER_SCORE * EXTEND_AFTER_SCORE
-        };
-        if before + add >= ext {
-            self.extend_ship(system);
+    fn add_score<S: SystemTrait>(
+        &mut self, player_no: usize, add: u32, score_holder: &mut ScoreHolder, system: &mut S,
+    ) {
+        let before = score_holder.player_score(player_no);
+        score_holder.add_player_score(player_no, add);
+
+        if let Some(ext) = self.config.next_extend_score(before) {
+            if before + add >= ext {
+                self.extend_ship(player_no, system);
+            }
         }
     }
 
-    fn extend_ship<S: SystemTrait>(&mut self, system: &mut S) {
-        self.left_ship += 1;
+    fn extend_ship<S: SystemTrait>(&mut self, player_no: usize, system: &mut S) {
+        self.players[player_no].left_ship += 1;
         system.play_se(CH_JINGLE, SE_EXTEND_SHIP);
     }
 
-    fn spawn_myshot(&mut self, pos: &Vec2I, dual: bool, angle: i32) -> bool {
-        if let Some(myshot_opt) = self.myshots.iter_mut().find(|x| x.is_none()) {
+    fn spawn_myshot(&mut self, player_no: usize, pos: &Vec2I, dual: bool, angle: i32) -> bool {
+        let myshots = &mut self.players[player_no].myshots;
+        if let Some(myshot_opt) = myshots.iter_mut().find(|x| x.is_none()) {
             *myshot_opt = Some(MyShot::new(pos, dual, angle));
             true
         } else {
@@ -421,28 +729,28 @@ impl GameManager {
     }
 
     fn spawn_ene_shot(&mut self, pos: &Vec2I) {
-        let player_pos = [
-            Some(*self.player.raw_pos()),
-            self.player.dual_pos(),
-        ];
-        let speed = calc_ene_shot_speed(self.stage);
-        self.enemy_manager.spawn_shot(pos, &player_pos, speed);
+        let player_pos: Vec<Option<Vec2I>> = self.player_target_positions().into_iter().map(Some).collect();
+        if player_pos.is_empty() {
+            return;
+        }
+        let speed = self.config.ene_shot_speed(self.rank());
+        self.enemy_manager.spawn_shot(pos, &player_pos, speed, &mut self.rng);
     }
 
-    fn check_collision(&mut self) {
+    fn check_collision(&mut self, player_no: usize) {
         #[cfg(debug_assertions)]
         if self.state == GameState::EditTraj {
             return;
         }
 
-        self.check_collision_myshot_enemy();
-        self.check_collision_player_enemy();
+        self.check_collision_myshot_enemy(player_no);
+        self.check_collision_player_enemy(player_no);
     }
 
-    fn check_collision_myshot_enemy(&mut self) {
+    fn check_collision_myshot_enemy(&mut self, player_no: usize) {
         let power = 1;
         let accessor = unsafe { peep(self) };
-        for myshot_opt in self.myshots.iter_mut().filter(|x| x.is_some()) {
+        for myshot_opt in self.players[player_no].myshots.iter_mut().filter(|x| x.is_some()) {
             let myshot = myshot_opt.as_ref().unwrap();
             let colls: [Option<CollBox>; 2] = [
                 myshot.get_collbox(),
@@ -453,6 +761,8 @@ impl GameManager {
                 if let Some(fi) = self.enemy_manager.check_collision(collbox) {
                     self.enemy_manager.set_damage_to_enemy(
                         &fi, power, accessor, &mut self.event_queue);
+                    self.stats.hits += 1;
+                    self.dynamic_rank.on_hit();
                     hit = true;
                 }
             }
@@ -462,14 +772,15 @@ impl GameManager {
         }
     }
 
-    fn check_collision_player_enemy(&mut self) {
-        if !self.player.active() {
+    fn check_collision_player_enemy(&mut self, player_no: usize) {
+        let player = &self.players[player_no].player;
+        if !player.active() {
             return;
         }
 
         let collbox_opts: [Option<(CollBox, Vec2I)>; 2] = [
-            self.player.dual_collbox().map(|c| (c, self.player.dual_pos().unwrap())),
-            self.player.get_collbox().map(|c| (c, *self.player.raw_pos())),
+            player.dual_collbox().map(|c| (c, player.dual_pos().unwrap())),
+            player.get_collbox().map(|c| (c, *player.raw_pos())),
         ];
 
         for (collbox, player_pos) in collbox_opts.iter().flat_map(|x| x) {
@@ -480,7 +791,7 @@ impl GameManager {
                 self.enemy_manager.set_damage_to_enemy(&fi, power, accessor, &mut self.event_queue);
 
                 self.event_queue.push(EventType::PlayerExplosion(*player_pos));
-                if self.player.crash(&pos) {
+                if self.players[player_no].player.crash(&pos) {
                     self.event_queue.push(EventType::DeadPlayer);
                 }
                 continue;
@@ -488,7 +799,7 @@ impl GameManager {
 
             if let Some(pos) = self.enemy_manager.check_shot_collision(&collbox) {
                 self.event_queue.push(EventType::PlayerExplosion(*player_pos));
-                if self.player.crash(&pos) {
+                if self.players[player_no].player.crash(&pos) {
                     self.event_queue.push(EventType::DeadPlayer);
                 }
                 continue;
@@ -510,28 +821,35 @@ impl AccessorForPlayer for GameManager {
 }
 
 impl AccessorForEnemy for GameManager {
-    fn get_raw_player_pos(&self) -> &Vec2I {
-        self.player.raw_pos()
+    fn player_count(&self) -> usize {
+        self.players.len()
     }
 
-    fn get_dual_player_pos(&self) -> Option<Vec2I> {
-        self.player.dual_pos()
+    fn get_raw_player_pos(&self, player_no: usize) -> Option<&Vec2I> {
+        self.players.get(player_no)
+            .filter(|slot| slot.left_ship > 0)
+            .map(|slot| slot.player.raw_pos())
     }
 
-    fn is_player_dual(&self) -> bool {
-        self.player.is_dual()
+    fn get_dual_player_pos(&self, player_no: usize) -> Option<Vec2I> {
+        self.players.get(player_no).and_then(|slot| slot.player.dual_pos())
     }
 
-    fn can_player_capture(&self) -> bool {
+    fn is_player_dual(&self, player_no: usize) -> bool {
+        self.players.get(player_no).is_some_and(|slot| slot.player.is_dual())
+    }
+
+    fn can_player_capture(&self, player_no: usize) -> bool {
         #[cfg(debug_assertions)]
         if self.state == GameState::EditTraj {
             return false;
         }
-        self.state == GameState::Playing
+        self.state == GameState::Playing &&
+            self.players.get(player_no).is_some_and(|slot| slot.player.active())
     }
 
     fn is_player_capture_completed(&self) -> bool {
-        self.player.is_captured()
+        self.players[self.capture_player].player.is_captured()
     }
 
     fn capture_state(&self) -> CaptureState {
@@ -573,10 +891,167 @@ impl AccessorForEnemy for GameManager {
     fn get_stage_no(&self) -> u16 {
         self.stage
     }
+
+    fn rng(&mut self) -> &mut Xoshiro128Plus {
+        &mut self.rng
+    }
+
+    fn stage_pack(&self) -> &Rc<StagePack> {
+        &self.stage_pack
+    }
+
+    fn game_config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    fn rank(&self) -> i32 {
+        (self.config.rank(self.stage) + self.dynamic_rank.value()).clamp(0, ONE)
+    }
 }
 
-fn calc_ene_shot_speed(stage: u16) -> i32 {
-    const MAX_STAGE: i32 = 64;
-    let per = std::cmp::min(stage as i32, MAX_STAGE) * ONE / MAX_STAGE;
-    (ENE_SHOT_SPEED2 - ENE_SHOT_SPEED1) * per / ONE + ENE_SHOT_SPEED1
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::HeadlessSystem;
+
+    const CHALLENGING_STAGE: u16 = 2;  // 0 origin
+
+    struct Runner {
+        star_manager: StarManager,
+        pad: Pad,
+        score_holder: ScoreHolder,
+        system: HeadlessSystem,
+    }
+
+    impl Runner {
+        fn new() -> Self {
+            Self {
+                star_manager: StarManager::new(0),
+                pad: Pad::new(),
+                score_holder: ScoreHolder { score: 0, high_score: 0, score2: 0 },
+                system: HeadlessSystem::new(),
+            }
+        }
+
+        fn update(&mut self, game_manager: &mut GameManager) {
+            let pad2 = Pad::new();
+            let mut params = Params {
+                star_manager: &mut self.star_manager,
+                pad: &self.pad,
+                pad2: &pad2,
+                score_holder: &mut self.score_holder,
+            };
+            game_manager.update(&mut params, &mut self.system);
+        }
+    }
+
+    fn start_stage(stage: u16, config: GameConfig) -> GameManager {
+        let mut game_manager = GameManager::new(Rc::new(StagePack::default()), config);
+        game_manager.restart(1, 1);
+        game_manager.stage = stage;
+        game_manager
+    }
+
+    // Shoots down enemies as they appear, up to `max_hit` in total.
+    fn hit_enemies(game_manager: &mut GameManager, max_hit: u32) {
+        let fis: Vec<FormationIndex> = game_manager.enemy_manager.get_enemies().iter().flatten()
+            .map(|enemy| enemy.formation_index)
+            .collect();
+        for fi in fis {
+            if game_manager.enemy_manager.hit_count() >= max_hit {
+                break;
+            }
+            let accessor = unsafe { peep(game_manager) };
+            game_manager.enemy_manager.set_damage_to_enemy(&fi, 99, accessor, &mut game_manager.event_queue);
+        }
+    }
+
+    // Plays the challenging stage until the next stage starts,
+    // and returns the states passed and the score of the result screen.
+    fn play_challenging_stage(max_hit: u32) -> (Vec<GameState>, u32, u32) {
+        let mut game_manager = start_stage(CHALLENGING_STAGE, GameConfig::default());
+        let mut runner = Runner::new();
+        let mut states = vec![game_manager.state];
+        let mut score_before_result = 0;
+        for _ in 0..60 * 60 {
+            if game_manager.state == GameState::Playing {
+                hit_enemies(&mut game_manager, max_hit);
+            }
+            runner.update(&mut game_manager);
+            if game_manager.state != *states.last().unwrap() {
+                if game_manager.state == GameState::ChallengeResult {
+                    score_before_result = runner.score_holder.score;
+                }
+                states.push(game_manager.state);
+                if game_manager.state == GameState::StartStage {
+                    break;
+                }
+            }
+        }
+        assert_eq!(CHALLENGING_STAGE + 1, game_manager.stage);
+        let appeared = game_manager.enemy_manager.appeared_count();
+        let bonus = runner.score_holder.score - score_before_result;
+        (states, bonus, appeared)
+    }
+
+    // Plays the first stage until enemies start diving, and returns the wait till the next dive.
+    fn wait_first_attack(game_manager: &mut GameManager) -> u32 {
+        let mut runner = Runner::new();
+        for _ in 0..60 * 60 {
+            runner.update(game_manager);
+            let wait = game_manager.enemy_manager.attack_wait();
+            if wait > 0 {
+                return wait;
+            }
+        }
+        panic!("no dive");
+    }
+
+    // Speed of a bullet shot from right above the player.
+    fn ene_shot_speed(game_manager: &mut GameManager) -> i32 {
+        let target = AccessorForEnemy::player_target_positions(game_manager)[0];
+        let pos = Vec2I::new(target.x, target.y - 100 * ONE);
+        game_manager.spawn_ene_shot(&pos);
+        game_manager.enemy_manager.shots().find(|shot| shot.pos == pos).unwrap().vel.y
+    }
+
+    #[test]
+    fn test_rank() {
+        let mut fixed = start_stage(0, GameConfig { dynamic_rank: 0, ..GameConfig::default() });
+        let fixed_wait = wait_first_attack(&mut fixed);
+        assert_eq!((0, 0), fixed.rank_detail());
+
+        let mut played = start_stage(0, GameConfig::default());
+        assert_eq!(fixed_wait, wait_first_attack(&mut played));
+        assert!(played.rank_detail().1 > 0);
+
+        let mut raised = start_stage(0, GameConfig::default());
+        for _ in 0..ONE * 60 {
+            raised.dynamic_rank.update(true);
+        }
+        let raised_wait = wait_first_attack(&mut raised);
+        assert_eq!((ONE / 4, ONE / 4), raised.rank_detail());
+        assert!(raised_wait < fixed_wait);
+        assert!(ene_shot_speed(&mut raised) > ene_shot_speed(&mut fixed));
+    }
+
+    #[test]
+    fn test_challenge_perfect() {
+        let (states, bonus, appeared) = play_challenging_stage(u32::MAX);
+        assert_eq!(vec![GameState::StartStage, GameState::Playing, GameState::ChallengeResult,
+                        GameState::StageClear, GameState::StartStage],
+                   states);
+        assert!(appeared > 0);
+        assert_eq!(CHALLENGING_PERFECT_BONUS, bonus);
+    }
+
+    #[test]
+    fn test_challenge_hit_point() {
+        let (states, bonus, appeared) = play_challenging_stage(5);
+        assert_eq!(vec![GameState::StartStage, GameState::Playing, GameState::ChallengeResult,
+                        GameState::StageClear, GameState::StartStage],
+                   states);
+        assert!(appeared > 5);
+        assert_eq!(5 * CHALLENGING_HIT_POINT, bonus);
+    }
 }
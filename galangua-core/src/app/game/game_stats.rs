@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+use super::enemy::EnemyType;
+
+const ENEMY_TYPE_COUNT: usize = 4;
+
+// Statistics of one game.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct GameStats {
+    pub shots_fired: u32,
+    pub hits: u32,
+    kills: [u32; ENEMY_TYPE_COUNT],
+    pub captures: u32,
+    pub rescues: u32,
+    pub stage_reached: u16,  // 1 origin
+}
+
+impl GameStats {
+    pub fn kills(&self, enemy_type: EnemyType) -> u32 {
+        self.kills[enemy_type as usize]
+    }
+
+    pub fn total_kills(&self) -> u32 {
+        self.kills.iter().sum()
+    }
+
+    // Hits per shots, in permille.
+    pub fn hit_ratio(&self) -> u32 {
+        if self.shots_fired > 0 {
+            (self.hits as u64 * 1000 / self.shots_fired as u64) as u32
+        } else {
+            0
+        }
+    }
+
+    pub(super) fn add_kill(&mut self, enemy_type: EnemyType) {
+        self.kills[enemy_type as usize] += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hit_ratio() {
+        let mut stats = GameStats::default();
+        assert_eq!(0, stats.hit_ratio());
+
+        stats.shots_fired = 7;
+        stats.hits = 3;
+        assert_eq!(428, stats.hit_ratio());
+    }
+
+    #[test]
+    fn test_kills() {
+        let mut stats = GameStats::default();
+        stats.add_kill(EnemyType::Bee);
+        stats.add_kill(EnemyType::Bee);
+        stats.add_kill(EnemyType::Owl);
+        assert_eq!(2, stats.kills(EnemyType::Bee));
+        assert_eq!(0, stats.kills(EnemyType::Butterfly));
+        assert_eq!(3, stats.total_kills());
+    }
+}
@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+
+use crate::framework::SystemTrait;
+
+const KEY_HIGH_SCORE_TABLE: &str = "highScoreTable";
+pub const HIGH_SCORE_TABLE_SIZE: usize = 10;
+pub const NAME_LENGTH: usize = 3;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HighScoreEntry {
+    pub score: u32,
+    pub stage: u16,
+    pub name: String,
+}
+
+// Top scores in descending order, persisted through `SystemTrait` as JSON.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct HighScoreTable {
+    entries: Vec<HighScoreEntry>,
+}
+
+impl HighScoreTable {
+    pub fn load<S: SystemTrait>(system: &S) -> Self {
+        system.get_str(KEY_HIGH_SCORE_TABLE)
+            .and_then(|json| serde_json::from_str::<Self>(&json).ok())
+            .map(|mut table| {
+                table.entries.sort_by_key(|entry| std::cmp::Reverse(entry.score));
+                table.entries.truncate(HIGH_SCORE_TABLE_SIZE);
+                table
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn save<S: SystemTrait>(&self, system: &mut S) {
+        system.set_str(KEY_HIGH_SCORE_TABLE, &serde_json::to_string(self).unwrap());
+    }
+
+    pub fn entries(&self) -> &[HighScoreEntry] {
+        &self.entries
+    }
+
+    pub fn top_score(&self) -> Option<u32> {
+        self.entries.first().map(|entry| entry.score)
+    }
+
+    // Rank (0 origin) which the score would get, or `None` if it is out of the table.
+    // Later one is placed below on a tie.
+    pub fn rank_of(&self, score: u32) -> Option<usize> {
+        if score == 0 {
+            return None;
+        }
+        let rank = self.entries.iter().position(|entry| score > entry.score)
+            .unwrap_or(self.entries.len());
+        if rank < HIGH_SCORE_TABLE_SIZE { Some(rank) } else { None }
+    }
+
+    pub fn insert(&mut self, entry: HighScoreEntry) -> Option<usize> {
+        let rank = self.rank_of(entry.score)?;
+        self.entries.insert(rank, entry);
+        self.entries.truncate(HIGH_SCORE_TABLE_SIZE);
+        Some(rank)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::HeadlessSystem;
+
+    fn entry(score: u32, name: &str) -> HighScoreEntry {
+        HighScoreEntry { score, stage: 1, name: String::from(name) }
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut table = HighScoreTable::default();
+        assert_eq!(None, table.rank_of(0));
+        assert_eq!(Some(0), table.insert(entry(100, "AAA")));
+        assert_eq!(Some(0), table.insert(entry(300, "BBB")));
+        assert_eq!(Some(2), table.insert(entry(100, "CCC")));
+        let names: Vec<&str> = table.entries().iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(vec!["BBB", "AAA", "CCC"], names);
+
+        for i in 0..HIGH_SCORE_TABLE_SIZE {
+            table.insert(entry(1000 + i as u32, "DDD"));
+        }
+        assert_eq!(HIGH_SCORE_TABLE_SIZE, table.entries().len());
+        assert_eq!(None, table.rank_of(1000));
+        assert_eq!(Some(HIGH_SCORE_TABLE_SIZE - 1), table.rank_of(1001));
+        assert_eq!(Some(1009), table.top_score());
+    }
+
+    #[test]
+    fn test_persist() {
+        let mut system = HeadlessSystem::new();
+        assert_eq!(HighScoreTable::default(), HighScoreTable::load(&system));
+
+        let mut table = HighScoreTable::default();
+        table.insert(entry(500, "ABC"));
+        table.save(&mut system);
+        assert_eq!(table, HighScoreTable::load(&system));
+
+        system.set_str(KEY_HIGH_SCORE_TABLE, "broken");
+        assert_eq!(HighScoreTable::default(), HighScoreTable::load(&system));
+    }
+}
@@ -1,13 +1,22 @@
+use serde::{Deserialize, Serialize};
+
 pub mod effect;
 pub mod enemy;
+mod dynamic_rank;
 mod event_queue;
+pub mod game_config;
 pub mod game_manager;
+mod game_stats;
+pub mod high_score_table;
+pub mod name_entry;
 mod player;
 pub mod score_holder;
 
 pub use self::event_queue::{EventQueue, EventType};
+pub use self::game_config::{Difficulty, GameConfig};
+pub use self::game_stats::GameStats;
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 pub enum CaptureState {
     NoCapture,
     CaptureAttacking,
@@ -0,0 +1,130 @@
+use super::high_score_table::{HighScoreEntry, NAME_LENGTH};
+
+use crate::framework::RendererTrait;
+use crate::util::pad::{Pad, PadBit};
+
+const LETTERS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ. ";
+const TIME_LIMIT: u32 = 30 * 60;
+
+// Initials entry for a high score: Up/down to change the letter, fire to confirm.
+pub struct NameEntry {
+    rank: usize,
+    score: u32,
+    stage: u16,
+    letters: [usize; NAME_LENGTH],
+    cursor: usize,
+    count: u32,
+}
+
+impl NameEntry {
+    pub fn new(rank: usize, score: u32, stage: u16) -> Self {
+        Self {
+            rank,
+            score,
+            stage,
+            letters: [0; NAME_LENGTH],
+            cursor: 0,
+            count: 0,
+        }
+    }
+
+    // Returns true when done.
+    pub fn update(&mut self, pad: &Pad) -> bool {
+        self.count += 1;
+        if self.cursor >= NAME_LENGTH || self.count >= TIME_LIMIT {
+            return true;
+        }
+
+        let letter = &mut self.letters[self.cursor];
+        if pad.is_trigger(PadBit::U) {
+            *letter = (*letter + 1) % LETTERS.len();
+        }
+        if pad.is_trigger(PadBit::D) {
+            *letter = (*letter + LETTERS.len() - 1) % LETTERS.len();
+        }
+        if pad.is_trigger(PadBit::A) {
+            self.cursor += 1;
+            if self.cursor >= NAME_LENGTH {
+                return true;
+            }
+            self.letters[self.cursor] = self.letters[self.cursor - 1];
+        }
+        false
+    }
+
+    pub fn entry(&self) -> HighScoreEntry {
+        HighScoreEntry {
+            score: self.score,
+            stage: self.stage,
+            name: self.letters.iter().map(|&i| LETTERS[i] as char).collect(),
+        }
+    }
+
+    pub fn draw<R: RendererTrait>(&self, renderer: &mut R) {
+        renderer.set_texture_color_mod("font", 255, 0, 0);
+        renderer.draw_str("font", 4 * 8, 8 * 8, "ENTER YOUR INITIALS !");
+
+        renderer.set_texture_color_mod("font", 0, 255, 255);
+        renderer.draw_str("font", 5 * 8, 11 * 8, "SCORE       NAME");
+        renderer.set_texture_color_mod("font", 255, 255, 255);
+        renderer.draw_str("font", 3 * 8, 13 * 8, &format!("{:>7}", self.score));
+        for i in 0..NAME_LENGTH {
+            if i > self.cursor || (i == self.cursor && self.count & 16 != 0) {
+                continue;
+            }
+            let c = LETTERS[self.letters[i]] as char;
+            renderer.draw_str("font", (18 + i as i32) * 8, 13 * 8, &c.to_string());
+        }
+
+        renderer.set_texture_color_mod("font", 255, 255, 0);
+        renderer.draw_str("font", 6 * 8, 17 * 8, &format!("{} PLACE", ordinal(self.rank)));
+    }
+}
+
+pub fn ordinal(rank: usize) -> String {
+    let n = rank + 1;
+    let suffix = match (n % 10, n % 100) {
+        (1, r) if r != 11 => "ST",
+        (2, r) if r != 12 => "ND",
+        (3, r) if r != 13 => "RD",
+        _ => "TH",
+    };
+    format!("{}{}", n, suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framework::VKey;
+
+    fn press(pad: &mut Pad, name_entry: &mut NameEntry, vkey: VKey) -> bool {
+        pad.on_key(vkey, true);
+        pad.update();
+        let done = name_entry.update(pad);
+        pad.on_key(vkey, false);
+        pad.update();
+        name_entry.update(pad);
+        done
+    }
+
+    #[test]
+    fn test_name_entry() {
+        let mut pad = Pad::new();
+        let mut name_entry = NameEntry::new(0, 1234, 5);
+        assert!(!press(&mut pad, &mut name_entry, VKey::Down));  // 'A' -> ' '
+        assert!(!press(&mut pad, &mut name_entry, VKey::Down));  // ' ' -> '.'
+        assert!(!press(&mut pad, &mut name_entry, VKey::Space));
+        assert!(!press(&mut pad, &mut name_entry, VKey::Up));  // '.' -> ' '
+        assert!(!press(&mut pad, &mut name_entry, VKey::Up));  // ' ' -> 'A'
+        assert!(!press(&mut pad, &mut name_entry, VKey::Up));  // 'A' -> 'B'
+        assert!(!press(&mut pad, &mut name_entry, VKey::Space));
+        assert!(press(&mut pad, &mut name_entry, VKey::Space));
+        assert_eq!(HighScoreEntry { score: 1234, stage: 5, name: String::from(".BB") }, name_entry.entry());
+    }
+
+    #[test]
+    fn test_ordinal() {
+        let ordinals: Vec<String> = [0, 1, 2, 3, 9, 10, 11, 12, 20].iter().map(|&r| ordinal(r)).collect();
+        assert_eq!(vec!["1ST", "2ND", "3RD", "4TH", "10TH", "11TH", "12TH", "13TH", "21ST"], ordinals);
+    }
+}
@@ -1,9 +1,12 @@
+use serde::{Deserialize, Serialize};
+
 use crate::app::consts::*;
 use crate::app::util::{CollBox, Collidable};
 use crate::framework::types::Vec2I;
 use crate::framework::RendererTrait;
 use crate::util::math::{calc_velocity, quantize_angle, round_up, ONE};
 
+#[derive(Serialize, Deserialize)]
 pub struct MyShot {
     pos: Vec2I,
     dual: bool,
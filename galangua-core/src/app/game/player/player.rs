@@ -1,17 +1,19 @@
+use serde::{Deserialize, Serialize};
+
 use crate::app::consts::*;
 use crate::app::game::{EventQueue, EventType};
 use crate::app::util::{CollBox, Collidable};
 use crate::framework::types::{Vec2I, ZERO_VEC};
 use crate::framework::RendererTrait;
 use crate::util::math::{clamp, quantize_angle, round_up, ANGLE, ONE};
-use crate::util::pad::{Pad, PadBit};
+use crate::util::pad::{Pad, PadBit, ANALOG_STEPS};
 
 use super::recaptured_fighter::RecapturedFighter;
 use super::Accessor;
 
 const Y_POSITION: i32 = HEIGHT - 16 - 8;
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Serialize, Deserialize)]
 enum State {
     Normal,
     Dead,
@@ -22,6 +24,7 @@ enum State {
     MoveHomePos,
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct Player {
     pos: Vec2I,
     state: State,
@@ -30,24 +33,29 @@ pub struct Player {
     capture_pos: Vec2I,
     recaptured_fighter: Option<RecapturedFighter>,
     shot_enable: bool,
+    player_no: usize,
+    home_x: i32,
 }
 
 impl Player {
-    pub fn new() -> Self {
+    // `home_x` is in dots, where the fighter appears.
+    pub fn new(player_no: usize, home_x: i32) -> Self {
         Self {
-            pos: &Vec2I::new(WIDTH / 2, Y_POSITION) * ONE,
+            pos: &Vec2I::new(home_x, Y_POSITION) * ONE,
             state: State::Normal,
             dual: false,
             angle: 0,
             capture_pos: ZERO_VEC,
             recaptured_fighter: None,
             shot_enable: true,
+            player_no,
+            home_x,
         }
     }
 
     pub fn restart(&mut self) {
         self.state = State::Normal;
-        self.pos = &Vec2I::new(WIDTH / 2, HEIGHT - 16 - 8) * ONE;
+        self.pos = &Vec2I::new(self.home_x, HEIGHT - 16 - 8) * ONE;
     }
 
     pub fn set_shot_enable(&mut self, value: bool) {
@@ -99,15 +107,16 @@ impl Player {
     }
 
     pub fn update_normal(&mut self, pad: &Pad, event_queue: &mut EventQueue) {
+        let speed = PLAYER_SPEED * pad.speed() / ANALOG_STEPS;
         if pad.is_pressed(PadBit::L) {
-            self.pos.x -= PLAYER_SPEED;
+            self.pos.x -= speed;
             let left = 8 * ONE;
             if self.pos.x < left {
                 self.pos.x = left;
             }
         }
         if pad.is_pressed(PadBit::R) {
-            self.pos.x += PLAYER_SPEED;
+            self.pos.x += speed;
             let right = if self.dual { (WIDTH - 8 - 16) * ONE } else { (WIDTH - 8) * ONE };
             if self.pos.x > right {
                 self.pos.x = right;
@@ -134,7 +143,7 @@ impl Player {
     fn fire_bullet(&mut self, pad: &Pad, event_queue: &mut EventQueue) {
         if self.shot_enable && pad.is_trigger(PadBit::A) {
             let pos = &self.pos + &Vec2I::new(0, -4 * ONE);
-            event_queue.push(EventType::MyShot(pos, self.dual, self.angle));
+            event_queue.push(EventType::MyShot(pos, self.dual, self.angle, self.player_no));
         }
     }
 
@@ -172,6 +181,15 @@ impl Player {
         self.state == State::Normal
     }
 
+    pub fn is_dead(&self) -> bool {
+        self.state == State::Dead
+    }
+
+    // Dead, or taken by a tractor beam.
+    pub fn is_lost(&self) -> bool {
+        self.state == State::Dead || self.state == State::CaptureCompleted
+    }
+
     fn pos(&self) -> Vec2I {
         round_up(&self.pos)
     }
@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 use crate::app::consts::*;
 use crate::app::game::{EventQueue, EventType};
 use crate::framework::types::Vec2I;
@@ -6,7 +8,7 @@ use crate::util::math::{clamp, quantize_angle, round_up, ANGLE, ONE};
 
 use super::Accessor;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 enum State {
     Rotate,
     SlideHorz,
@@ -14,6 +16,7 @@ enum State {
     Done,
 }
 
+#[derive(Serialize, Deserialize)]
 pub(super) struct RecapturedFighter {
     pos: Vec2I,
     state: State,
@@ -1,18 +1,27 @@
-#[derive(Clone, Debug)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ScoreHolder {
     pub score: u32,
     pub high_score: u32,
+    pub score2: u32,  // Player 2
 }
 
 impl ScoreHolder {
     pub fn reset_score(&mut self) {
         self.score = 0;
+        self.score2 = 0;
+    }
+
+    pub fn player_score(&self, player_no: usize) -> u32 {
+        if player_no == 0 { self.score } else { self.score2 }
     }
 
-    pub fn add_score(&mut self, add: u32) {
-        self.score = self.score.saturating_add(add);
-        if self.score > self.high_score {
-            self.high_score = self.score;
+    pub fn add_player_score(&mut self, player_no: usize, add: u32) {
+        let score = if player_no == 0 { &mut self.score } else { &mut self.score2 };
+        *score = score.saturating_add(add);
+        if *score > self.high_score {
+            self.high_score = *score;
         }
     }
 }
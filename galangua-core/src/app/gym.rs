@@ -0,0 +1,310 @@
+use serde::Serialize;
+use std::rc::Rc;
+
+use super::consts::*;
+use super::game::effect::StarManager;
+use super::game::enemy::{EnemyState, EnemyType, StagePack, MAX_ENEMY_COUNT, MAX_SHOT_COUNT};
+use super::game::game_manager::GameManager;
+use super::game::game_manager::Params as GameManagerParams;
+use super::game::score_holder::ScoreHolder;
+use super::game::GameConfig;
+use super::pilot::Observation;
+
+use crate::framework::RendererTrait;
+use crate::headless::{HeadlessSystem, SilhouetteRenderer};
+use crate::util::math::ONE;
+use crate::util::pad::{Pad, PadBit};
+
+const BEAM_SLOTS: usize = 2;
+const HEADER_LEN: usize = 6;
+const ENEMY_LEN: usize = 7;
+const SHOT_LEN: usize = 5;
+const BEAM_LEN: usize = 3;
+
+// Length of the vector observation.
+pub const VECTOR_LEN: usize = HEADER_LEN + MAX_ENEMY_COUNT * ENEMY_LEN + MAX_SHOT_COUNT * SHOT_LEN + BEAM_SLOTS * BEAM_LEN;
+
+// Discrete actions: none, left, right, fire, left + fire, right + fire.
+pub const ACTION_COUNT: usize = 6;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ObservationType {
+    Vector,       // `VECTOR_LEN` values, roughly in -1.0..=1.0.
+    Framebuffer,  // Gray scale silhouettes, (WIDTH / scale) x (HEIGHT / scale).
+}
+
+pub struct GymConfig {
+    pub observation_type: ObservationType,
+    pub frame_skip: u32,  // Frames to repeat an action in a step.
+    pub max_frames: u32,  // An episode is cut off after this.
+    pub death_penalty: f32,
+    pub framebuffer_scale: i32,
+    pub assets_dir: String,  // For the sprite sheet of the framebuffer.
+    pub stage_pack: StagePack,
+    pub game_config: GameConfig,
+}
+
+impl Default for GymConfig {
+    fn default() -> Self {
+        Self {
+            observation_type: ObservationType::Vector,
+            frame_skip: 4,
+            max_frames: 60 * 60 * 10,
+            death_penalty: 1000.0,
+            framebuffer_scale: 4,
+            assets_dir: String::from("assets"),
+            stage_pack: StagePack::default(),
+            game_config: GameConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum GymObservation {
+    Vector(Vec<f32>),
+    Framebuffer(Vec<u8>),
+}
+
+#[derive(Debug, Serialize)]
+pub struct StepResult {
+    pub observation: GymObservation,
+    pub reward: f32,  // Score earned, minus the penalty for each death.
+    pub done: bool,
+}
+
+// Reinforcement learning environment of 1 player game,
+// without the title or other screens, running as fast as possible.
+pub struct GymEnv {
+    config: GymConfig,
+    stage_pack: Rc<StagePack>,
+    game_manager: GameManager,
+    star_manager: StarManager,
+    score_holder: ScoreHolder,
+    pad: Pad,
+    pad2: Pad,
+    system: HeadlessSystem,
+    renderer: Option<SilhouetteRenderer>,
+    frame: u32,
+}
+
+impl GymEnv {
+    pub fn new(mut config: GymConfig) -> Self {
+        // A step runs a frame at least, to go on.
+        config.frame_skip = config.frame_skip.max(1);
+        let renderer = if config.observation_type == ObservationType::Framebuffer {
+            let mut renderer = SilhouetteRenderer::new(WIDTH, HEIGHT);
+            renderer.load_sprite_sheet(&format!("{}/chr.json", config.assets_dir));
+            Some(renderer)
+        } else {
+            None
+        };
+        let stage_pack = Rc::new(config.stage_pack.clone());
+
+        let mut env = Self {
+            game_manager: GameManager::new(stage_pack.clone(), config.game_config.clone()),
+            stage_pack,
+            config,
+            star_manager: StarManager::new(0),
+            score_holder: ScoreHolder { score: 0, high_score: 0, score2: 0 },
+            pad: Pad::new(),
+            pad2: Pad::new(),
+            system: HeadlessSystem::new(),
+            renderer,
+            frame: 0,
+        };
+        env.reset(0);
+        env
+    }
+
+    pub fn observation_shape(&self) -> Vec<usize> {
+        match self.config.observation_type {
+            ObservationType::Vector => vec![VECTOR_LEN],
+            ObservationType::Framebuffer => {
+                let scale = self.config.framebuffer_scale;
+                vec![(HEIGHT / scale) as usize, (WIDTH / scale) as usize]
+            }
+        }
+    }
+
+    pub fn reset(&mut self, seed: u64) -> GymObservation {
+        self.game_manager = GameManager::new(self.stage_pack.clone(), self.config.game_config.clone());
+        self.game_manager.restart(seed, 1);
+        self.star_manager = StarManager::new(seed);
+        self.score_holder = ScoreHolder { score: 0, high_score: 0, score2: 0 };
+        self.pad = Pad::new();
+        self.frame = 0;
+        self.observe()
+    }
+
+    // `action` is in 0..ACTION_COUNT.
+    pub fn step(&mut self, action: usize) -> StepResult {
+        let bits = action_bits(action);
+        let mut done = false;
+        for _ in 0..self.config.frame_skip {
+            self.pad.update_with(bits);
+            let mut params = GameManagerParams {
+                star_manager: &mut self.star_manager,
+                pad: &self.pad,
+                pad2: &self.pad2,
+                score_holder: &mut self.score_holder,
+            };
+            self.game_manager.update(&mut params, &mut self.system);
+            self.frame += 1;
+
+            done = self.game_manager.is_game_over() || self.frame >= self.config.max_frames;
+            if done {
+                break;
+            }
+        }
+        self.system.clear_played_se();
+
+        let tally = self.game_manager.take_event_tally();
+        StepResult {
+            observation: self.observe(),
+            reward: tally.score as f32 - tally.dead as f32 * self.config.death_penalty,
+            done,
+        }
+    }
+
+    pub fn score(&self) -> u32 {
+        self.score_holder.score
+    }
+
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    fn observe(&mut self) -> GymObservation {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_draw_color(0, 0, 0);
+            renderer.clear();
+            self.game_manager.draw(renderer);
+            GymObservation::Framebuffer(renderer.downscaled(self.config.framebuffer_scale))
+        } else {
+            GymObservation::Vector(to_vector(&self.game_manager.observe()))
+        }
+    }
+}
+
+pub fn action_bits(action: usize) -> PadBit {
+    const TABLE: [PadBit; ACTION_COUNT] = [
+        PadBit::empty(),
+        PadBit::L,
+        PadBit::R,
+        PadBit::A,
+        PadBit::from_bits_truncate(PadBit::L.bits() | PadBit::A.bits()),
+        PadBit::from_bits_truncate(PadBit::R.bits() | PadBit::A.bits()),
+    ];
+    TABLE[action % ACTION_COUNT]
+}
+
+// Fixed layout: header, then slots of enemies, enemy shots and tractor beams.
+// Each slot starts with 1.0 if present, and is filled with 0.0 otherwise.
+pub fn to_vector(observation: &Observation) -> Vec<f32> {
+    let w = WIDTH as f32;
+    let h = HEIGHT as f32;
+    let mut v = Vec::with_capacity(VECTOR_LEN);
+
+    match &observation.player_pos {
+        Some(pos) => v.extend_from_slice(&[1.0, pos.x as f32 / w, pos.y as f32 / h]),
+        None => v.extend_from_slice(&[0.0, 0.0, 0.0]),
+    }
+    v.push(if observation.dual { 1.0 } else { 0.0 });
+    v.push(observation.stage as f32 / 256.0);
+    v.push(observation.left_ship as f32 / 10.0);
+
+    for i in 0..MAX_ENEMY_COUNT {
+        if let Some(enemy) = observation.enemies.get(i) {
+            let angle = enemy.angle as f32 * (2.0 * std::f32::consts::PI / 256.0);
+            v.extend_from_slice(&[
+                1.0,
+                enemy_type_value(enemy.enemy_type),
+                enemy_state_value(enemy.state),
+                enemy.pos.x as f32 / w,
+                enemy.pos.y as f32 / h,
+                angle.sin(),
+                -angle.cos(),
+            ]);
+        } else {
+            v.extend_from_slice(&[0.0; ENEMY_LEN]);
+        }
+    }
+
+    for i in 0..MAX_SHOT_COUNT {
+        if let Some(shot) = observation.ene_shots.get(i) {
+            v.extend_from_slice(&[
+                1.0,
+                shot.pos.x as f32 / w,
+                shot.pos.y as f32 / h,
+                shot.vel.x as f32 / (4 * ONE) as f32,
+                shot.vel.y as f32 / (4 * ONE) as f32,
+            ]);
+        } else {
+            v.extend_from_slice(&[0.0; SHOT_LEN]);
+        }
+    }
+
+    for i in 0..BEAM_SLOTS {
+        if let Some(beam) = observation.tractor_beams.get(i) {
+            v.extend_from_slice(&[1.0, beam.pos.x as f32 / w, if beam.full { 1.0 } else { 0.0 }]);
+        } else {
+            v.extend_from_slice(&[0.0; BEAM_LEN]);
+        }
+    }
+    v
+}
+
+fn enemy_type_value(enemy_type: EnemyType) -> f32 {
+    (enemy_type as usize + 1) as f32 / 4.0
+}
+
+fn enemy_state_value(state: EnemyState) -> f32 {
+    match state {
+        EnemyState::Formation => 0.0,
+        EnemyState::Appearance | EnemyState::MoveToFormation => 0.5,
+        _ => 1.0,  // Attacking.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_episode() {
+        let mut env = GymEnv::new(GymConfig { max_frames: 60 * 60, ..Default::default() });
+        match env.reset(1) {
+            GymObservation::Vector(v) => assert_eq!(VECTOR_LEN, v.len()),
+            _ => panic!("vector expected"),
+        }
+
+        let mut total = 0.0;
+        let mut steps = 0;
+        loop {
+            let result = env.step(steps % ACTION_COUNT);
+            total += result.reward;
+            steps += 1;
+            if result.done {
+                break;
+            }
+        }
+        assert!(steps <= 60 * 60 / 4);
+        assert!(total != 0.0);
+
+        // Same seed and actions reproduce the same episode.
+        let score = env.score();
+        env.reset(1);
+        for i in 0..steps {
+            env.step(i % ACTION_COUNT);
+        }
+        assert_eq!(score, env.score());
+    }
+
+    #[test]
+    fn test_zero_frame_skip() {
+        let mut env = GymEnv::new(GymConfig { frame_skip: 0, max_frames: 10, ..Default::default() });
+        env.reset(1);
+        assert!((0..10).any(|_| env.step(0).done));
+    }
+}
@@ -0,0 +1,308 @@
+use crate::framework::{RendererTrait, VKey};
+use crate::util::bindings::{Bindings, Button, JoyInput, JoyState};
+use crate::util::pad::{Pad, PadBit};
+
+const DEAD_ZONE_STEP: i16 = 1000;
+const MIN_DEAD_ZONE: i16 = 1000;
+const MAX_DEAD_ZONE: i16 = 30000;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Row {
+    Bind(bool, Button),  // Player 2 or not, and the button.
+    DeadZone,
+    Analog,
+    Default,
+    Save,
+}
+
+const ROWS: [Row; 13] = [
+    Row::Bind(false, Button::Left),
+    Row::Bind(false, Button::Right),
+    Row::Bind(false, Button::Up),
+    Row::Bind(false, Button::Down),
+    Row::Bind(false, Button::Fire),
+    Row::Bind(false, Button::AutoFire),
+    Row::Bind(true, Button::Left),
+    Row::Bind(true, Button::Right),
+    Row::Bind(true, Button::Fire),
+    Row::DeadZone,
+    Row::Analog,
+    Row::Default,
+    Row::Save,
+];
+
+// Line of each row, binding rows of each player and the others are separated.
+const ROW_Y: [i32; 13] = [10, 12, 14, 16, 18, 20, 24, 26, 28, 30, 31, 33, 34];
+
+#[derive(PartialEq)]
+enum State {
+    Select,
+    Waiting,  // For a new key or joystick input of the selected row.
+    Bound,    // Got one in this frame, the pad trigger is ignored.
+}
+
+// Remap screen: up/down to select a row, fire to wait for a new key or joystick input,
+// left/right to change the dead zone or analog movement. Escape leaves it without saving.
+pub struct KeyConfig {
+    bindings: Bindings,
+    cursor: usize,
+    state: State,
+    held_keys: Vec<VKey>,
+    joy_state: JoyState,
+    count: u32,
+}
+
+impl KeyConfig {
+    pub fn new(bindings: &Bindings) -> Self {
+        Self {
+            bindings: bindings.clone(),
+            cursor: 0,
+            state: State::Select,
+            held_keys: Vec::new(),
+            joy_state: JoyState::default(),
+            count: 0,
+        }
+    }
+
+    pub fn bindings(&self) -> &Bindings {
+        &self.bindings
+    }
+
+    // Returns true when saved.
+    pub fn update(&mut self, pad: &Pad) -> bool {
+        self.count = self.count.wrapping_add(1);
+        match self.state {
+            State::Waiting => return false,
+            State::Bound => {
+                self.state = State::Select;
+                return false;
+            }
+            State::Select => {}
+        }
+
+        if pad.is_trigger(PadBit::U) {
+            self.cursor = (self.cursor + ROWS.len() - 1) % ROWS.len();
+        }
+        if pad.is_trigger(PadBit::D) {
+            self.cursor = (self.cursor + 1) % ROWS.len();
+        }
+        match ROWS[self.cursor] {
+            Row::Bind(..) => {
+                if pad.is_trigger(PadBit::A) {
+                    self.state = State::Waiting;
+                }
+            }
+            Row::DeadZone => {
+                let dead_zone = &mut self.bindings.dead_zone;
+                if pad.is_trigger(PadBit::L) {
+                    *dead_zone = (*dead_zone - DEAD_ZONE_STEP).max(MIN_DEAD_ZONE);
+                }
+                if pad.is_trigger(PadBit::R) {
+                    *dead_zone = (*dead_zone + DEAD_ZONE_STEP).min(MAX_DEAD_ZONE);
+                }
+            }
+            Row::Analog => {
+                if pad.is_trigger(PadBit::L) || pad.is_trigger(PadBit::R) || pad.is_trigger(PadBit::A) {
+                    self.bindings.analog = !self.bindings.analog;
+                }
+            }
+            Row::Default => {
+                if pad.is_trigger(PadBit::A) {
+                    self.bindings = Bindings::default();
+                }
+            }
+            Row::Save => {
+                return pad.is_trigger(PadBit::A);
+            }
+        }
+        false
+    }
+
+    // Escape is left to cancel.
+    pub fn on_key(&mut self, vkey: VKey, down: bool) {
+        let pressed = down && !self.held_keys.contains(&vkey);
+        self.held_keys.retain(|&key| key != vkey);
+        if down {
+            self.held_keys.push(vkey);
+        }
+
+        if pressed && vkey != VKey::Escape {
+            if let Some((player2, button)) = self.waiting_row() {
+                self.bindings.bind_key(player2, button, vkey);
+                self.state = State::Bound;
+            }
+        }
+    }
+
+    pub fn on_joystick_axis(&mut self, axis_index: u8, value: i16) {
+        let dead_zone = self.bindings.dead_zone;
+        let pressed = self.joy_state.axis(axis_index).abs() <= dead_zone && value.abs() > dead_zone;
+        self.joy_state.set_axis(axis_index, value);
+        if pressed {
+            self.bind_joy(JoyInput::Axis(axis_index, value.signum() as i8));
+        }
+    }
+
+    pub fn on_joystick_hat(&mut self, hat_index: u8, x: i8, y: i8) {
+        let pressed = self.joy_state.hat(hat_index) == (0, 0) && (x, y) != (0, 0);
+        self.joy_state.set_hat(hat_index, x, y);
+        if pressed {
+            self.bind_joy(if x != 0 { JoyInput::Hat(hat_index, x, 0) } else { JoyInput::Hat(hat_index, 0, y) });
+        }
+    }
+
+    pub fn on_joystick_button(&mut self, button_index: u8, down: bool) {
+        let pressed = down && !self.joy_state.is_button_down(button_index);
+        self.joy_state.set_button(button_index, down);
+        if pressed {
+            self.bind_joy(JoyInput::Button(button_index));
+        }
+    }
+
+    fn waiting_row(&self) -> Option<(bool, Button)> {
+        match (&self.state, ROWS[self.cursor]) {
+            (State::Waiting, Row::Bind(player2, button)) => Some((player2, button)),
+            _ => None,
+        }
+    }
+
+    // The joystick is shared by both players.
+    fn bind_joy(&mut self, input: JoyInput) {
+        if let Some((_, button)) = self.waiting_row() {
+            self.bindings.bind_joy(button, input);
+            self.state = State::Bound;
+        }
+    }
+
+    pub fn draw<R: RendererTrait>(&self, renderer: &mut R) {
+        renderer.set_texture_color_mod("font", 255, 0, 0);
+        renderer.draw_str("font", 9 * 8, 2 * 8, "KEY CONFIG");
+        if self.state == State::Waiting && self.count & 16 == 0 {
+            renderer.set_texture_color_mod("font", 255, 255, 255);
+            renderer.draw_str("font", 4 * 8, 4 * 8, "PRESS KEY OR BUTTON");
+        }
+
+        renderer.set_texture_color_mod("font", 0, 255, 255);
+        renderer.draw_str("font", 9 * 8, 6 * 8, "KEY    JOYSTICK");
+        renderer.set_texture_color_mod("font", 255, 255, 0);
+        renderer.draw_str("font", 2 * 8, 8 * 8, "PLAYER 1");
+        renderer.draw_str("font", 2 * 8, 22 * 8, "PLAYER 2 (CO-OP)");
+
+        for (i, row) in ROWS.iter().enumerate() {
+            let y = ROW_Y[i] * 8;
+            renderer.set_texture_color_mod("font", 255, 255, 255);
+            if i == self.cursor {
+                renderer.draw_str("font", 0, y, ">");
+            }
+            match *row {
+                Row::Bind(player2, button) => {
+                    renderer.draw_str("font", 2 * 8, y, button_label(button));
+                    if i == self.cursor && self.state == State::Waiting {
+                        continue;
+                    }
+                    if let Some(key) = self.bindings.keys_of(player2, button).next() {
+                        renderer.draw_str("font", 9 * 8, y, &key_label(key));
+                    }
+                    let joy: Vec<String> = self.bindings.joy_of(button).map(joy_label).collect();
+                    renderer.draw_str("font", 16 * 8, y, &joy.join(" "));
+                }
+                Row::DeadZone => {
+                    let percent = self.bindings.dead_zone as i32 * 100 / 32768;
+                    renderer.draw_str("font", 2 * 8, y, &format!("DEAD ZONE  {:>3}%", percent));
+                }
+                Row::Analog => {
+                    let analog = if self.bindings.analog { "ON" } else { "OFF" };
+                    renderer.draw_str("font", 2 * 8, y, &format!("ANALOG     {:>3}", analog));
+                }
+                Row::Default => renderer.draw_str("font", 2 * 8, y, "DEFAULT"),
+                Row::Save => renderer.draw_str("font", 2 * 8, y, "SAVE"),
+            }
+        }
+    }
+}
+
+fn button_label(button: Button) -> &'static str {
+    match button {
+        Button::Left => "LEFT",
+        Button::Right => "RIGHT",
+        Button::Up => "UP",
+        Button::Down => "DOWN",
+        Button::Fire => "FIRE",
+        Button::AutoFire => "TURBO",
+    }
+}
+
+fn key_label(key: VKey) -> String {
+    format!("{:?}", key).to_uppercase()
+}
+
+// Short to fit in the column: `B0` for a button, `A1-` for an axis, `H0L` for a hat.
+fn joy_label(input: JoyInput) -> String {
+    match input {
+        JoyInput::Button(index) => format!("B{}", index),
+        JoyInput::Axis(index, dir) => format!("A{}{}", index, if dir < 0 { '-' } else { '+' }),
+        JoyInput::Hat(index, x, y) => {
+            let dir = match (x, y) {
+                (-1, _) => 'L',
+                (1, _) => 'R',
+                (_, -1) => 'U',
+                _ => 'D',
+            };
+            format!("H{}{}", index, dir)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(pad: &mut Pad, key_config: &mut KeyConfig, vkey: VKey) -> bool {
+        pad.on_key(vkey, true);
+        key_config.on_key(vkey, true);
+        pad.update();
+        let done = key_config.update(pad);
+        pad.on_key(vkey, false);
+        key_config.on_key(vkey, false);
+        pad.update();
+        key_config.update(pad);
+        done
+    }
+
+    #[test]
+    fn test_remap() {
+        let mut pad = Pad::new();
+        let mut key_config = KeyConfig::new(&Bindings::default());
+
+        // Player 1 fire to Z key and joystick button 5.
+        for _ in 0..4 {
+            press(&mut pad, &mut key_config, VKey::Down);
+        }
+        press(&mut pad, &mut key_config, VKey::Space);
+        press(&mut pad, &mut key_config, VKey::Z);
+        press(&mut pad, &mut key_config, VKey::Space);
+        key_config.on_joystick_button(5, true);
+        key_config.update(&pad);
+
+        // Dead zone.
+        for _ in 0..5 {
+            press(&mut pad, &mut key_config, VKey::Down);
+        }
+        press(&mut pad, &mut key_config, VKey::Right);
+
+        // Analog off.
+        press(&mut pad, &mut key_config, VKey::Down);
+        press(&mut pad, &mut key_config, VKey::Left);
+
+        press(&mut pad, &mut key_config, VKey::Down);
+        press(&mut pad, &mut key_config, VKey::Down);
+        assert!(press(&mut pad, &mut key_config, VKey::Space));
+
+        let bindings = key_config.bindings();
+        assert_eq!(PadBit::A, bindings.key_bits(false, VKey::Z));
+        assert_eq!(PadBit::empty(), bindings.key_bits(false, VKey::Space));
+        assert_eq!(vec![JoyInput::Button(5)], bindings.joy_of(Button::Fire).collect::<Vec<_>>());
+        assert_eq!(11_000, bindings.dead_zone);
+        assert_eq!(false, bindings.analog);
+    }
+}
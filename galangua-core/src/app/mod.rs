@@ -1,9 +1,18 @@
 pub mod consts;
+mod demo;
 mod galangua_app;
 mod game;
+pub mod gym;
+mod key_config;
+mod pause_menu;
+pub mod pilot;
+pub mod settings;
 mod util;
 
-pub use self::galangua_app::GalanguaApp;
+pub use self::galangua_app::{GalanguaApp, PlayMode};
+pub use self::game::enemy::{EnemyState, EnemyType, StagePack};
+pub use self::game::high_score_table::{HighScoreEntry, HighScoreTable};
+pub use self::game::{Difficulty, GameConfig, GameStats};
 
 #[cfg(debug_assertions)]
 mod debug;
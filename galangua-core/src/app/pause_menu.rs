@@ -0,0 +1,138 @@
+use crate::app::consts::MAX_VOLUME;
+use crate::app::settings::{Settings, MAX_SCALE};
+use crate::framework::RendererTrait;
+use crate::framework::types::Vec2I;
+use crate::util::pad::{Pad, PadBit};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PauseAction {
+    Resume,
+    Restart,
+    Controls,
+    QuitToTitle,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Item {
+    Resume,
+    Restart,
+    Options,
+    Quit,
+    Volume,
+    Scale,
+    Fullscreen,
+    Controls,
+    Back,
+}
+
+const MAIN_ITEMS: [Item; 4] = [Item::Resume, Item::Restart, Item::Options, Item::Quit];
+const OPTION_ITEMS: [Item; 5] = [Item::Volume, Item::Scale, Item::Fullscreen, Item::Controls, Item::Back];
+
+// Overlay menu on the paused game: up/down to select, fire to decide,
+// left/right to change an option.
+pub struct PauseMenu {
+    options_page: bool,
+    cursor: usize,
+    count: u32,
+}
+
+impl PauseMenu {
+    pub fn new() -> Self {
+        Self {
+            options_page: false,
+            cursor: 0,
+            count: 0,
+        }
+    }
+
+    // `settings` are changed in place.
+    pub fn update(&mut self, pad: &Pad, settings: &mut Settings) -> Option<PauseAction> {
+        self.count = self.count.wrapping_add(1);
+        let items = self.items();
+        if pad.is_trigger(PadBit::U) {
+            self.cursor = (self.cursor + items.len() - 1) % items.len();
+        }
+        if pad.is_trigger(PadBit::D) {
+            self.cursor = (self.cursor + 1) % items.len();
+        }
+
+        let fire = pad.is_trigger(PadBit::A);
+        match items[self.cursor] {
+            Item::Resume if fire => return Some(PauseAction::Resume),
+            Item::Restart if fire => return Some(PauseAction::Restart),
+            Item::Options if fire => self.open_page(true),
+            Item::Quit if fire => return Some(PauseAction::QuitToTitle),
+            Item::Volume => settings.volume = step(settings.volume, pad, 0, MAX_VOLUME),
+            Item::Scale => settings.scale = step(settings.scale, pad, 1, MAX_SCALE),
+            Item::Fullscreen if fire || pad.is_trigger(PadBit::L) || pad.is_trigger(PadBit::R) => {
+                settings.fullscreen = !settings.fullscreen;
+            }
+            Item::Controls if fire => return Some(PauseAction::Controls),
+            Item::Back if fire => self.open_page(false),
+            _ => {}
+        }
+        None
+    }
+
+    // Escape key: back to the main page, or true to resume.
+    pub fn back(&mut self) -> bool {
+        if self.options_page {
+            self.open_page(false);
+            false
+        } else {
+            true
+        }
+    }
+
+    pub fn draw<R: RendererTrait>(&self, renderer: &mut R, settings: &Settings) {
+        renderer.set_draw_color(0, 0, 0);
+        renderer.fill_rect(Some([&Vec2I::new(4 * 8, 11 * 8), &Vec2I::new(20 * 8, 12 * 8)]));
+
+        renderer.set_texture_color_mod("font", 255, 0, 0);
+        if self.options_page {
+            renderer.draw_str("font", 10 * 8, 12 * 8, "OPTIONS");
+        } else if self.count & 32 == 0 {
+            renderer.draw_str("font", 11 * 8, 12 * 8, "PAUSE");
+        }
+
+        renderer.set_texture_color_mod("font", 255, 255, 255);
+        for (i, item) in self.items().iter().enumerate() {
+            let y = (15 + i as i32 * 2) * 8;
+            if i == self.cursor {
+                renderer.draw_str("font", 6 * 8, y, ">");
+            }
+            let label = match item {
+                Item::Resume => String::from("RESUME"),
+                Item::Restart => String::from("RESTART"),
+                Item::Options => String::from("OPTIONS"),
+                Item::Quit => String::from("QUIT TO TITLE"),
+                Item::Volume => format!("VOLUME     {:>3}", settings.volume),
+                Item::Scale => format!("SCALE      {:>3}", settings.scale),
+                Item::Fullscreen => format!("FULLSCREEN {:>3}", if settings.fullscreen { "ON" } else { "OFF" }),
+                Item::Controls => String::from("CONTROLS"),
+                Item::Back => String::from("BACK"),
+            };
+            renderer.draw_str("font", 8 * 8, y, &label);
+        }
+    }
+
+    fn items(&self) -> &'static [Item] {
+        if self.options_page { &OPTION_ITEMS } else { &MAIN_ITEMS }
+    }
+
+    fn open_page(&mut self, options_page: bool) {
+        // Cursor on the item which opened the options page.
+        self.cursor = if options_page { 0 } else { 2 };
+        self.options_page = options_page;
+    }
+}
+
+fn step(value: u32, pad: &Pad, min: u32, max: u32) -> u32 {
+    if pad.is_trigger(PadBit::L) && value > min {
+        value - 1
+    } else if pad.is_trigger(PadBit::R) && value < max {
+        value + 1
+    } else {
+        value
+    }
+}
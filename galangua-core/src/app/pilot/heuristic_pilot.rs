@@ -0,0 +1,154 @@
+use super::{Observation, PilotTrait};
+
+use crate::app::consts::*;
+use crate::framework::types::Vec2I;
+use crate::util::math::ONE;
+use crate::util::pad::PadBit;
+
+const LOOKAHEAD: i32 = 40;  // Frames to predict shots.
+const SHOT_HALF_WIDTH: i32 = 8;
+const SHOT_HALF_HEIGHT: i32 = 10;
+const ATTACKER_RANGE_Y: i32 = 64;
+const ATTACKER_HALF_WIDTH: i32 = 14;
+const BEAM_HALF_WIDTH: i32 = 28;
+const AIM_RANGE: i32 = 6;
+const MARGIN_X: i32 = 16;
+
+// Baseline bot: dodges enemy shots and attackers, and shoots at the nearest column.
+pub struct HeuristicPilot {
+    count: u32,
+}
+
+impl HeuristicPilot {
+    pub fn new() -> Self {
+        Self {
+            count: 0,
+        }
+    }
+
+    fn update_in_game(&self, observation: &Observation) -> PadBit {
+        let player_pos = match &observation.player_pos {
+            Some(pos) => *pos,
+            None => return PadBit::empty(),
+        };
+
+        let target_x = observation.enemies.iter()
+            .map(|enemy| enemy.pos.x)
+            .min_by_key(|x| (x - player_pos.x).abs());
+
+        // Choose the safest move, and prefer heading to the target among equally safe ones.
+        let candidates = [(PadBit::L, -1), (PadBit::empty(), 0), (PadBit::R, 1)];
+        let (dir, _) = candidates.iter()
+            .map(|&(bits, dx)| {
+                let danger = calc_danger(observation, &player_pos, dx);
+                let dist = target_x.map_or(0, |x| (x - (player_pos.x + dx * 8)).abs());
+                (bits, (danger, dist))
+            })
+            .min_by_key(|(_, score)| *score)
+            .unwrap();
+
+        let aimed = target_x.is_some_and(|x| (x - player_pos.x).abs() <= AIM_RANGE);
+        if aimed && self.count & 1 == 0 { dir | PadBit::A } else { dir }
+    }
+}
+
+impl Default for HeuristicPilot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PilotTrait for HeuristicPilot {
+    fn update(&mut self, observation: &Observation) -> PadBit {
+        self.count = self.count.wrapping_add(1);
+        if observation.in_game {
+            self.update_in_game(observation)
+        } else if self.count & 15 == 0 {
+            // Starts a game, and skips the other screens.
+            PadBit::A
+        } else {
+            PadBit::empty()
+        }
+    }
+}
+
+// Higher is worse, moving `dx` direction (-1, 0, 1) for the next frames.
+fn calc_danger(observation: &Observation, player_pos: &Vec2I, dx: i32) -> i32 {
+    let x_at = |t: i32| {
+        let x = player_pos.x + dx * t * PLAYER_SPEED / ONE;
+        x.clamp(MARGIN_X, WIDTH - MARGIN_X)
+    };
+    let mut danger = 0;
+    if dx != 0 && x_at(1) == player_pos.x {
+        danger += 1;  // Against the wall, no use.
+    }
+
+    for shot in observation.ene_shots.iter() {
+        for t in 0..LOOKAHEAD {
+            let sx = shot.pos.x + shot.vel.x * t / ONE;
+            let sy = shot.pos.y + shot.vel.y * t / ONE;
+            if (sy - player_pos.y).abs() <= SHOT_HALF_HEIGHT && (sx - x_at(t)).abs() <= SHOT_HALF_WIDTH {
+                danger += (LOOKAHEAD - t) * 16;
+                break;
+            }
+        }
+    }
+
+    let x = x_at(8);
+    for enemy in observation.enemies.iter() {
+        let dy = player_pos.y - enemy.pos.y;
+        if (-16..=ATTACKER_RANGE_Y).contains(&dy) && (enemy.pos.x - x).abs() <= ATTACKER_HALF_WIDTH {
+            danger += (ATTACKER_RANGE_Y - dy.max(0)) * 8;
+        }
+    }
+
+    for beam in observation.tractor_beams.iter() {
+        if (beam.pos.x - x).abs() <= BEAM_HALF_WIDTH {
+            danger += if beam.full { 1000 } else { 200 };
+        }
+    }
+    danger
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::pilot::{EnemyObservation, ShotObservation};
+    use crate::app::{EnemyState, EnemyType};
+
+    fn observation(player_x: i32) -> Observation {
+        Observation {
+            in_game: true,
+            player_pos: Some(Vec2I::new(player_x, HEIGHT - 32)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_dodge_shot() {
+        let mut observation = observation(WIDTH / 2);
+        observation.ene_shots.push(ShotObservation {
+            pos: Vec2I::new(WIDTH / 2 + 4, HEIGHT - 80),
+            vel: Vec2I::new(0, 2 * ONE),
+        });
+
+        let mut pilot = HeuristicPilot::new();
+        assert_eq!(PadBit::L, pilot.update(&observation) & (PadBit::L | PadBit::R));
+    }
+
+    #[test]
+    fn test_shoot_enemy_above() {
+        let mut observation = observation(WIDTH / 2);
+        observation.enemies.push(EnemyObservation {
+            enemy_type: EnemyType::Bee,
+            state: EnemyState::Formation,
+            pos: Vec2I::new(WIDTH / 2, 80),
+            angle: 0,
+        });
+
+        let mut pilot = HeuristicPilot::new();
+        let bits = [pilot.update(&observation), pilot.update(&observation)];
+        assert!(bits.iter().any(|bits| bits.contains(PadBit::A)));
+        assert!(bits.iter().all(|bits| !bits.intersects(PadBit::L | PadBit::R)));
+    }
+}
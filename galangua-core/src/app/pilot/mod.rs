@@ -0,0 +1,47 @@
+mod heuristic_pilot;
+
+pub use self::heuristic_pilot::HeuristicPilot;
+
+use super::game::enemy::{EnemyState, EnemyType};
+
+use crate::framework::types::Vec2I;
+use crate::util::pad::PadBit;
+
+// Drives player 1 in place of the keyboard/joystick, e.g. for bots or training.
+pub trait PilotTrait {
+    // Called once per frame, returns the pad state for the frame.
+    fn update(&mut self, observation: &Observation) -> PadBit;
+}
+
+// Read-only view of the game for a pilot, positions are in dots.
+#[derive(Clone, Debug, Default)]
+pub struct Observation {
+    pub in_game: bool,  // false on the title and other screens.
+    pub player_pos: Option<Vec2I>,  // None while the fighter can't be controlled.
+    pub dual: bool,
+    pub enemies: Vec<EnemyObservation>,
+    pub ene_shots: Vec<ShotObservation>,
+    pub tractor_beams: Vec<TractorBeamObservation>,
+    pub stage: u16,  // 1 origin.
+    pub left_ship: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct EnemyObservation {
+    pub enemy_type: EnemyType,
+    pub state: EnemyState,
+    pub pos: Vec2I,
+    pub angle: i32,  // 0..256, 0 is upward and clockwise.
+}
+
+#[derive(Clone, Debug)]
+pub struct ShotObservation {
+    pub pos: Vec2I,
+    pub vel: Vec2I,  // Fixed point, ONE = 1 dot per frame.
+}
+
+#[derive(Clone, Debug)]
+pub struct TractorBeamObservation {
+    pub pos: Vec2I,  // Top center.
+    pub full: bool,  // Captures a fighter within the width.
+}
@@ -0,0 +1,174 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::app::consts::MAX_VOLUME;
+use crate::app::game::game_config::{DEFAULT_STARTING_SHIPS, MAX_STARTING_SHIPS};
+use crate::app::game::{Difficulty, GameConfig};
+use crate::framework::SystemTrait;
+use crate::util::bindings::Bindings;
+
+const KEY_SETTINGS: &str = "settings";
+const SETTINGS_VERSION: u32 = 1;
+
+// Separate entries before the settings store, migrated as version 0.
+const LEGACY_KEY_VOLUME: &str = "volume";
+const LEGACY_KEY_SCALE: &str = "scale";
+const LEGACY_KEY_BINDINGS: &str = "bindings";
+
+pub const DEFAULT_SCALE: u32 = 3;
+pub const MAX_SCALE: u32 = 6;
+
+// User settings, persisted through `SystemTrait` as a JSON entry with a schema version.
+// Missing fields take the default, and older versions are migrated on load.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub version: u32,
+    pub volume: u32,  // 0..=MAX_VOLUME
+    pub scale: u32,   // Window scale, 1..=MAX_SCALE
+    pub fullscreen: bool,
+    pub bindings: Bindings,
+    pub difficulty: Difficulty,
+    pub starting_lives: u32,  // 1..=MAX_STARTING_SHIPS
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            volume: MAX_VOLUME,
+            scale: DEFAULT_SCALE,
+            fullscreen: false,
+            bindings: Bindings::default(),
+            difficulty: Difficulty::Normal,
+            starting_lives: DEFAULT_STARTING_SHIPS,
+        }
+    }
+}
+
+impl Settings {
+    // Falls back to the default if broken, or saved by a newer version.
+    pub fn load<S: SystemTrait>(system: &S) -> Self {
+        let value = match system.get_str(KEY_SETTINGS) {
+            Some(json) => serde_json::from_str(&json).map_err(|e| e.to_string()),
+            None => Ok(load_legacy(system)),
+        };
+        value.and_then(Self::from_value).unwrap_or_default()
+    }
+
+    pub fn save<S: SystemTrait>(&self, system: &mut S) {
+        system.set_str(KEY_SETTINGS, &serde_json::to_string(self).unwrap());
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        Self::from_value(serde_json::from_str(json).map_err(|e| e.to_string())?)
+    }
+
+    // Preset of the difficulty, with the starting ships.
+    pub fn game_config(&self) -> GameConfig {
+        GameConfig {
+            starting_ships: self.starting_lives,
+            ..GameConfig::preset(self.difficulty)
+        }
+    }
+
+    // Tells the platform the volume and the window.
+    pub fn apply<S: SystemTrait>(&self, system: &mut S) {
+        system.set_volume(self.volume);
+        system.set_window(self.scale, self.fullscreen);
+    }
+
+    fn from_value(mut value: Value) -> Result<Self, String> {
+        let version = value.get("version").and_then(Value::as_u64).unwrap_or(0) as u32;
+        if version > SETTINGS_VERSION {
+            return Err(format!("unsupported settings version: {}", version));
+        }
+        for from in version..SETTINGS_VERSION {
+            migrate(&mut value, from)?;
+        }
+
+        let mut settings: Self = serde_json::from_value(value).map_err(|e| e.to_string())?;
+        settings.version = SETTINGS_VERSION;
+        settings.volume = settings.volume.min(MAX_VOLUME);
+        settings.scale = settings.scale.clamp(1, MAX_SCALE);
+        settings.starting_lives = settings.starting_lives.clamp(1, MAX_STARTING_SHIPS);
+        Ok(settings)
+    }
+}
+
+// Collects the separate entries into a version 0 object.
+fn load_legacy<S: SystemTrait>(system: &S) -> Value {
+    let mut map = Map::new();
+    map.insert(String::from("version"), Value::from(0));
+    if let Some(volume) = system.get_u32(LEGACY_KEY_VOLUME) {
+        map.insert(String::from("volume"), Value::from(volume));
+    }
+    if let Some(scale) = system.get_u32(LEGACY_KEY_SCALE) {
+        map.insert(String::from("scale"), Value::from(scale));
+    }
+    if let Some(json) = system.get_str(LEGACY_KEY_BINDINGS) {
+        map.insert(String::from("bindings"), Value::from(json));
+    }
+    Value::Object(map)
+}
+
+// Upgrades the object from the version to the next one.
+fn migrate(value: &mut Value, from: u32) -> Result<(), String> {
+    let map = value.as_object_mut().ok_or_else(|| String::from("settings must be an object"))?;
+    match from {
+        0 => {
+            // Bindings were a JSON string, dropped if broken.
+            if let Some(Value::String(json)) = map.remove("bindings") {
+                if let Ok(bindings) = Bindings::from_json(&json) {
+                    map.insert(String::from("bindings"), serde_json::to_value(bindings).unwrap());
+                }
+            }
+        }
+        _ => return Err(format!("no migration from settings version {}", from)),
+    }
+    map.insert(String::from("version"), Value::from(from + 1));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framework::VKey;
+    use crate::headless::HeadlessSystem;
+    use crate::util::bindings::Button;
+
+    #[test]
+    fn test_save_and_load() {
+        let mut system = HeadlessSystem::new();
+        assert_eq!(Settings::default(), Settings::load(&system));
+
+        let mut settings = Settings::default();
+        settings.volume = 4;
+        settings.fullscreen = true;
+        settings.difficulty = Difficulty::Hard;
+        settings.bindings.bind_key(false, Button::Fire, VKey::Z);
+        settings.save(&mut system);
+        assert_eq!(settings, Settings::load(&system));
+
+        // Newer version is not read.
+        system.set_str(KEY_SETTINGS, r#"{"version": 99, "volume": 1}"#);
+        assert_eq!(Settings::default(), Settings::load(&system));
+    }
+
+    #[test]
+    fn test_migrate_legacy_entries() {
+        let mut bindings = Bindings::default();
+        bindings.bind_key(false, Button::Fire, VKey::Z);
+        let mut system = HeadlessSystem::new();
+        system.set_u32(LEGACY_KEY_VOLUME, 7);
+        system.set_u32(LEGACY_KEY_SCALE, 99);
+        system.set_str(LEGACY_KEY_BINDINGS, &serde_json::to_string(&bindings).unwrap());
+
+        let settings = Settings::load(&system);
+        assert_eq!(SETTINGS_VERSION, settings.version);
+        assert_eq!(7, settings.volume);
+        assert_eq!(MAX_SCALE, settings.scale);
+        assert_eq!(bindings, settings.bindings);
+        assert_eq!(DEFAULT_STARTING_SHIPS, settings.starting_lives);
+    }
+}
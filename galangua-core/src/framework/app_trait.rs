@@ -6,6 +6,7 @@ pub trait AppTrait<R: RendererTrait> {
     fn draw(&mut self, renderer: &mut R);
 
     fn on_key(&mut self, keycode: VKey, down: bool);
-    fn on_joystick_axis(&mut self, axis_index: u8, dir: i8);
+    fn on_joystick_axis(&mut self, axis_index: u8, value: i16);
+    fn on_joystick_hat(&mut self, hat_index: u8, x: i8, y: i8);
     fn on_joystick_button(&mut self, button_index: u8, down: bool);
 }
@@ -1,6 +1,14 @@
 pub trait SystemTrait {
     fn get_u32(&self, key: &str) -> Option<u32>;
     fn set_u32(&mut self, key: &str, value: u32);
+    fn get_str(&self, key: &str) -> Option<String>;
+    fn set_str(&mut self, key: &str, value: &str);
 
     fn play_se(&mut self, channel: u32, filename: &str);
+
+    // Settings for the platform: `volume` is in 0..=MAX_VOLUME, `scale` is of the window.
+    fn set_volume(&mut self, volume: u32);
+    fn set_window(&mut self, scale: u32, fullscreen: bool);
+    // Stops sound effects playing while paused.
+    fn pause_audio(&mut self, paused: bool);
 }
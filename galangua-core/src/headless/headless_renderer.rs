@@ -0,0 +1,115 @@
+use crate::framework::types::Vec2I;
+use crate::framework::RendererTrait;
+
+// Draw call issued to the renderer, kept when recording.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DrawCommand {
+    Clear,
+    SetTextureColorMod(String, u8, u8, u8),
+    DrawStr(String, i32, i32, String),
+    DrawSprite(String, Vec2I),
+    DrawSpriteRot(String, Vec2I, u8, Option<Vec2I>),
+    SetDrawColor(u8, u8, u8),
+    FillRect(Option<[Vec2I; 2]>),
+}
+
+// Renderer which draws nothing.
+// In recording mode, draw calls since the last `clear` are kept.
+pub struct HeadlessRenderer {
+    recording: bool,
+    commands: Vec<DrawCommand>,
+}
+
+impl HeadlessRenderer {
+    pub fn new() -> Self {
+        Self {
+            recording: false,
+            commands: Vec::new(),
+        }
+    }
+
+    pub fn new_recording() -> Self {
+        Self {
+            recording: true,
+            commands: Vec::new(),
+        }
+    }
+
+    pub fn commands(&self) -> &[DrawCommand] {
+        &self.commands
+    }
+
+    fn record(&mut self, command: DrawCommand) {
+        if self.recording {
+            self.commands.push(command);
+        }
+    }
+}
+
+impl Default for HeadlessRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RendererTrait for HeadlessRenderer {
+    fn load_textures(&mut self, _base_path: &str, _filenames: &[&str]) {}
+
+    fn load_sprite_sheet(&mut self, _filename: &str) {}
+
+    fn clear(&mut self) {
+        self.commands.clear();
+        self.record(DrawCommand::Clear);
+    }
+
+    fn set_texture_color_mod(&mut self, tex_name: &str, r: u8, g: u8, b: u8) {
+        self.record(DrawCommand::SetTextureColorMod(String::from(tex_name), r, g, b));
+    }
+
+    fn draw_str(&mut self, tex_name: &str, x: i32, y: i32, text: &str) {
+        self.record(DrawCommand::DrawStr(String::from(tex_name), x, y, String::from(text)));
+    }
+
+    fn draw_sprite(&mut self, sprite_name: &str, pos: &Vec2I) {
+        self.record(DrawCommand::DrawSprite(String::from(sprite_name), *pos));
+    }
+
+    fn draw_sprite_rot(&mut self, sprite_name: &str, pos: &Vec2I, angle: u8,
+                       center: Option<&Vec2I>) {
+        self.record(DrawCommand::DrawSpriteRot(String::from(sprite_name), *pos, angle,
+                                               center.copied()));
+    }
+
+    fn set_draw_color(&mut self, r: u8, g: u8, b: u8) {
+        self.record(DrawCommand::SetDrawColor(r, g, b));
+    }
+
+    fn fill_rect(&mut self, dst: Option<[&Vec2I; 2]>) {
+        self.record(DrawCommand::FillRect(dst.map(|rect| [*rect[0], *rect[1]])));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recording() {
+        let mut renderer = HeadlessRenderer::new_recording();
+        renderer.draw_sprite("foo", &Vec2I::new(1, 2));
+        renderer.clear();
+        renderer.draw_str("font", 3, 4, "HELLO");
+
+        assert_eq!(&[DrawCommand::Clear,
+                     DrawCommand::DrawStr(String::from("font"), 3, 4, String::from("HELLO"))],
+                   renderer.commands());
+    }
+
+    #[test]
+    fn test_null() {
+        let mut renderer = HeadlessRenderer::new();
+        renderer.clear();
+        renderer.draw_sprite("foo", &Vec2I::new(1, 2));
+        assert!(renderer.commands().is_empty());
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+
+use crate::framework::SystemTrait;
+
+// System which keeps stored values in memory, and plays no sound.
+pub struct HeadlessSystem {
+    map: HashMap<String, u32>,
+    str_map: HashMap<String, String>,
+    played_se: Vec<(u32, String)>,
+}
+
+impl HeadlessSystem {
+    pub fn new() -> Self {
+        Self {
+            map: HashMap::new(),
+            str_map: HashMap::new(),
+            played_se: Vec::new(),
+        }
+    }
+
+    // Sound effects requested so far, as (channel, filename).
+    pub fn played_se(&self) -> &[(u32, String)] {
+        &self.played_se
+    }
+
+    pub fn clear_played_se(&mut self) {
+        self.played_se.clear();
+    }
+}
+
+impl Default for HeadlessSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SystemTrait for HeadlessSystem {
+    fn get_u32(&self, key: &str) -> Option<u32> {
+        self.map.get(key).copied()
+    }
+
+    fn set_u32(&mut self, key: &str, value: u32) {
+        self.map.insert(String::from(key), value);
+    }
+
+    fn get_str(&self, key: &str) -> Option<String> {
+        self.str_map.get(key).cloned()
+    }
+
+    fn set_str(&mut self, key: &str, value: &str) {
+        self.str_map.insert(String::from(key), String::from(value));
+    }
+
+    fn play_se(&mut self, channel: u32, filename: &str) {
+        self.played_se.push((channel, String::from(filename)));
+    }
+
+    fn set_volume(&mut self, _volume: u32) {}
+    fn set_window(&mut self, _scale: u32, _fullscreen: bool) {}
+    fn pause_audio(&mut self, _paused: bool) {}
+}
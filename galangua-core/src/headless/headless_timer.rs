@@ -0,0 +1,39 @@
+use crate::util::fps_calc::TimerTrait;
+
+const FPS: u32 = 60;
+
+// Timer driven by frame count instead of wall clock:
+// one second passes on every 60 calls.
+pub struct HeadlessTimer {
+    count: u32,
+}
+
+impl HeadlessTimer {
+    pub fn new() -> Self {
+        Self {
+            count: 0,
+        }
+    }
+}
+
+impl Default for HeadlessTimer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimerTrait for HeadlessTimer {
+    fn passed_one_second(&mut self) -> bool {
+        self.count += 1;
+        if self.count < FPS {
+            return false;
+        }
+
+        self.count = 0;
+        true
+    }
+
+    fn reset(&mut self) {
+        self.count = 0;
+    }
+}
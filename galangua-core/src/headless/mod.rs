@@ -0,0 +1,13 @@
+mod headless_renderer;
+mod headless_system;
+mod headless_timer;
+mod silhouette_renderer;
+#[cfg(feature = "software-renderer")]
+mod software_renderer;
+
+pub use self::headless_renderer::{DrawCommand, HeadlessRenderer};
+pub use self::headless_system::HeadlessSystem;
+pub use self::headless_timer::HeadlessTimer;
+pub use self::silhouette_renderer::SilhouetteRenderer;
+#[cfg(feature = "software-renderer")]
+pub use self::software_renderer::{load_png, save_png, SoftwareRenderer};
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+
+use crate::framework::sprite_sheet::{load_sprite_sheet, SpriteSheet};
+use crate::framework::types::Vec2I;
+use crate::framework::RendererTrait;
+
+const DEFAULT_SPRITE_SIZE: i32 = 16;
+const CHAR_SIZE: i32 = 8;
+
+// Renderer which fills the bounds of sprites and characters in gray scale, without textures.
+// Rough but fast, e.g. for observations in learning.
+pub struct SilhouetteRenderer {
+    width: i32,
+    height: i32,
+    pixels: Vec<u8>,
+    sprite_sheet: HashMap<String, SpriteSheet>,
+    color_mods: HashMap<String, u8>,
+    draw_color: u8,
+}
+
+impl SilhouetteRenderer {
+    pub fn new(width: i32, height: i32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; (width * height) as usize],
+            sprite_sheet: HashMap::new(),
+            color_mods: HashMap::new(),
+            draw_color: 0,
+        }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    // Averages each `scale` x `scale` block.
+    pub fn downscaled(&self, scale: i32) -> Vec<u8> {
+        let w = self.width / scale;
+        let h = self.height / scale;
+        let mut result = Vec::with_capacity((w * h) as usize);
+        for y in 0..h {
+            for x in 0..w {
+                let mut sum = 0;
+                for dy in 0..scale {
+                    let row = ((y * scale + dy) * self.width + x * scale) as usize;
+                    sum += self.pixels[row..row + scale as usize].iter().map(|&p| p as u32).sum::<u32>();
+                }
+                result.push((sum / (scale * scale) as u32) as u8);
+            }
+        }
+        result
+    }
+
+    fn texture_value(&self, tex_name: &str) -> u8 {
+        *self.color_mods.get(tex_name).unwrap_or(&255)
+    }
+
+    fn sprite_rect(&self, sprite_name: &str, pos: &Vec2I) -> (u8, [i32; 4]) {
+        if let Some(sheet) = self.sprite_sheet.get(sprite_name) {
+            let mut pos = *pos;
+            if let Some(trimmed) = &sheet.trimmed {
+                pos.x += trimmed.sprite_source_size.x;
+                pos.y += trimmed.sprite_source_size.y;
+            }
+            (self.texture_value(&sheet.texture), [pos.x, pos.y, sheet.frame.w as i32, sheet.frame.h as i32])
+        } else {
+            (255, [pos.x, pos.y, DEFAULT_SPRITE_SIZE, DEFAULT_SPRITE_SIZE])
+        }
+    }
+
+    fn fill(&mut self, value: u8, [x, y, w, h]: [i32; 4]) {
+        let x0 = x.clamp(0, self.width);
+        let x1 = (x + w).clamp(0, self.width);
+        for yy in y.clamp(0, self.height)..(y + h).clamp(0, self.height) {
+            let row = (yy * self.width) as usize;
+            self.pixels[row + x0 as usize..row + x1 as usize].iter_mut().for_each(|p| *p = value);
+        }
+    }
+}
+
+fn luminance(r: u8, g: u8, b: u8) -> u8 {
+    ((r as u32 * 77 + g as u32 * 150 + b as u32 * 29) >> 8) as u8
+}
+
+impl RendererTrait for SilhouetteRenderer {
+    fn load_textures(&mut self, _base_path: &str, _filenames: &[&str]) {}
+
+    fn load_sprite_sheet(&mut self, filename: &str) {
+        let text = std::fs::read_to_string(filename)
+            .expect("load_sprite_sheet failed");
+        self.sprite_sheet = load_sprite_sheet(&text);
+    }
+
+    fn clear(&mut self) {
+        let value = self.draw_color;
+        self.pixels.iter_mut().for_each(|p| *p = value);
+    }
+
+    fn set_texture_color_mod(&mut self, tex_name: &str, r: u8, g: u8, b: u8) {
+        self.color_mods.insert(String::from(tex_name), luminance(r, g, b));
+    }
+
+    fn draw_str(&mut self, tex_name: &str, x: i32, y: i32, text: &str) {
+        let value = self.texture_value(tex_name);
+        for (i, c) in text.chars().enumerate() {
+            if c != ' ' {
+                self.fill(value, [x + i as i32 * CHAR_SIZE, y, CHAR_SIZE, CHAR_SIZE]);
+            }
+        }
+    }
+
+    fn draw_sprite(&mut self, sprite_name: &str, pos: &Vec2I) {
+        let (value, rect) = self.sprite_rect(sprite_name, pos);
+        self.fill(value, rect);
+    }
+
+    // Rotation is ignored, the bounds are nearly the same for square sprites.
+    fn draw_sprite_rot(&mut self, sprite_name: &str, pos: &Vec2I, _angle: u8,
+                       _center: Option<&Vec2I>) {
+        self.draw_sprite(sprite_name, pos);
+    }
+
+    fn set_draw_color(&mut self, r: u8, g: u8, b: u8) {
+        self.draw_color = luminance(r, g, b);
+    }
+
+    fn fill_rect(&mut self, dst: Option<[&Vec2I; 2]>) {
+        let rect = match dst {
+            Some([pos, size]) => [pos.x, pos.y, size.x, size.y],
+            None => [0, 0, self.width, self.height],
+        };
+        self.fill(self.draw_color, rect);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_and_downscale() {
+        let mut renderer = SilhouetteRenderer::new(8, 4);
+        renderer.set_draw_color(255, 255, 255);
+        renderer.fill_rect(Some([&Vec2I::new(-1, 0), &Vec2I::new(2, 2)]));
+        renderer.draw_sprite("unknown", &Vec2I::new(6, 2));
+
+        assert_eq!(&[255, 0, 0, 0, 0, 0, 0, 0], &renderer.pixels()[0..8]);
+        assert_eq!(&[0, 0, 0, 0, 0, 0, 255, 255], &renderer.pixels()[24..32]);
+        assert_eq!(vec![127, 0, 0, 0, 0, 0, 0, 255], renderer.downscaled(2));
+    }
+}
@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+
+use crate::framework::sprite_sheet::{load_sprite_sheet, SpriteSheet};
+use crate::framework::types::Vec2I;
+use crate::framework::RendererTrait;
+
+const CHAR_SIZE: i32 = 8;
+const FONT_COLUMNS: i32 = 16;
+
+struct Texture {
+    width: i32,
+    height: i32,
+    pixels: Vec<u8>,  // RGBA
+    color_mod: [u8; 3],
+}
+
+impl Texture {
+    fn load(filename: &str) -> Result<Self, String> {
+        let (width, height, pixels) = load_png(filename)?;
+        Ok(Self {
+            width: width as i32,
+            height: height as i32,
+            pixels,
+            color_mod: [255, 255, 255],
+        })
+    }
+
+    fn texel(&self, x: i32, y: i32) -> [u8; 4] {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return [0, 0, 0, 0];
+        }
+        let i = ((y * self.width + x) * 4) as usize;
+        let p = &self.pixels[i..i + 4];
+        let m = &self.color_mod;
+        [mul(p[0], m[0]), mul(p[1], m[1]), mul(p[2], m[2]), p[3]]
+    }
+}
+
+// Renderer on CPU into an RGBA buffer, no display needed.
+// Textures are alpha blended like the SDL version.
+pub struct SoftwareRenderer {
+    width: i32,
+    height: i32,
+    pixels: Vec<u8>,  // RGBA
+    textures: HashMap<String, Texture>,
+    sprite_sheet: HashMap<String, SpriteSheet>,
+    draw_color: [u8; 3],
+}
+
+impl SoftwareRenderer {
+    pub fn new(width: i32, height: i32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; (width * height * 4) as usize],
+            textures: HashMap::new(),
+            sprite_sheet: HashMap::new(),
+            draw_color: [0, 0, 0],
+        }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn save_png(&self, filename: &str) -> Result<(), String> {
+        save_png(filename, self.width as u32, self.height as u32, &self.pixels)
+    }
+
+    fn blend(&mut self, x: i32, y: i32, src: [u8; 4]) {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height || src[3] == 0 {
+            return;
+        }
+        let i = ((y * self.width + x) * 4) as usize;
+        let a = src[3] as u32;
+        for (d, &s) in self.pixels[i..i + 3].iter_mut().zip(src.iter()) {
+            *d = ((s as u32 * a + *d as u32 * (255 - a)) / 255) as u8;
+        }
+        self.pixels[i + 3] = 255;
+    }
+
+    // Copies the frame of a sprite, rotated clockwise by `angle` around `center`
+    // (relative to the destination, the middle of the sprite if `None`) like SDL.
+    fn copy_sprite(&mut self, sheet_name: &str, pos: &Vec2I, angle: u8, center: Option<&Vec2I>) {
+        let sheet = self.sprite_sheet.get(sheet_name).expect("No sprite");
+        let mut pos = *pos;
+        if let Some(trimmed) = &sheet.trimmed {
+            pos.x += trimmed.sprite_source_size.x;
+            pos.y += trimmed.sprite_source_size.y;
+        }
+        let frame = sheet.frame;
+        let rotated = sheet.rotated;
+        let texture = self.textures.get(&sheet.texture).expect("No texture");
+        let (w, h) = (frame.w as i32, frame.h as i32);
+
+        // Texel of the sprite at (sx, sy), TexturePacker stores rotated ones 90 degrees clockwise.
+        let sample = |sx: i32, sy: i32| -> [u8; 4] {
+            if sx < 0 || sy < 0 || sx >= w || sy >= h {
+                [0, 0, 0, 0]
+            } else if rotated {
+                texture.texel(frame.x + h - 1 - sy, frame.y + sx)
+            } else {
+                texture.texel(frame.x + sx, frame.y + sy)
+            }
+        };
+
+        let mut texels = Vec::new();
+        if angle == 0 {
+            for sy in 0..h {
+                for sx in 0..w {
+                    texels.push((pos.x + sx, pos.y + sy, sample(sx, sy)));
+                }
+            }
+        } else {
+            // Rotates clockwise around the center, in doubled coordinates for pixel centers.
+            let (cx2, cy2) = match center {
+                Some(c) => (c.x * 2, c.y * 2),
+                None => (w, h),
+            };
+            let rad = angle as f64 * (2.0 * std::f64::consts::PI / 256.0);
+            let (sn, cs) = rad.sin_cos();
+            let r = (((w * w + h * h) as f64).sqrt() + 2.0) as i32;
+            let (cx, cy) = (cx2 / 2, cy2 / 2);
+            for dy in cy - r..=cy + r {
+                for dx in cx - r..=cx + r {
+                    let px = (dx * 2 + 1 - cx2) as f64;
+                    let py = (dy * 2 + 1 - cy2) as f64;
+                    let sx = px * cs + py * sn + cx2 as f64;
+                    let sy = -px * sn + py * cs + cy2 as f64;
+                    let texel = sample((sx / 2.0).floor() as i32, (sy / 2.0).floor() as i32);
+                    texels.push((pos.x + dx, pos.y + dy, texel));
+                }
+            }
+        }
+
+        for (x, y, texel) in texels {
+            self.blend(x, y, texel);
+        }
+    }
+}
+
+// Returns width, height and RGBA pixels.
+pub fn load_png(filename: &str) -> Result<(u32, u32, Vec<u8>), String> {
+    let file = File::open(filename).map_err(|e| format!("{}: {}", filename, e))?;
+    let mut decoder = png::Decoder::new(file);
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let (info, mut reader) = decoder.read_info().map_err(|e| format!("{}: {}", filename, e))?;
+    let mut buf = vec![0; info.buffer_size()];
+    reader.next_frame(&mut buf).map_err(|e| format!("{}: {}", filename, e))?;
+
+    let pixels = match info.color_type {
+        png::ColorType::RGBA => buf,
+        png::ColorType::RGB => buf.chunks(3).flat_map(|c| vec![c[0], c[1], c[2], 255]).collect(),
+        png::ColorType::GrayscaleAlpha => buf.chunks(2).flat_map(|c| vec![c[0], c[0], c[0], c[1]]).collect(),
+        png::ColorType::Grayscale => buf.iter().flat_map(|&c| vec![c, c, c, 255]).collect(),
+        png::ColorType::Indexed => return Err(format!("{}: unexpanded palette", filename)),
+    };
+    Ok((info.width, info.height, pixels))
+}
+
+pub fn save_png(filename: &str, width: u32, height: u32, rgba: &[u8]) -> Result<(), String> {
+    let file = File::create(Path::new(filename)).map_err(|e| format!("{}: {}", filename, e))?;
+    let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::RGBA);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| format!("{}: {}", filename, e))?;
+    writer.write_image_data(rgba).map_err(|e| format!("{}: {}", filename, e))
+}
+
+fn mul(a: u8, b: u8) -> u8 {
+    ((a as u32 * b as u32) / 255) as u8
+}
+
+fn get_mainname(filename: &str) -> &str {
+    filename.rsplit_once('.').map_or(filename, |(name, _)| name)
+}
+
+impl RendererTrait for SoftwareRenderer {
+    fn load_textures(&mut self, base_path: &str, filenames: &[&str]) {
+        for filename in filenames.iter() {
+            let texture = Texture::load(&format!("{}/{}", base_path, filename))
+                .expect("load_textures failed");
+            self.textures.insert(String::from(get_mainname(filename)), texture);
+        }
+    }
+
+    fn load_sprite_sheet(&mut self, filename: &str) {
+        let text = std::fs::read_to_string(filename)
+            .expect("load_sprite_sheet failed");
+        self.sprite_sheet = load_sprite_sheet(&text);
+    }
+
+    fn clear(&mut self) {
+        let [r, g, b] = self.draw_color;
+        for p in self.pixels.chunks_mut(4) {
+            p.copy_from_slice(&[r, g, b, 255]);
+        }
+    }
+
+    fn set_texture_color_mod(&mut self, tex_name: &str, r: u8, g: u8, b: u8) {
+        if let Some(texture) = self.textures.get_mut(tex_name) {
+            texture.color_mod = [r, g, b];
+        }
+    }
+
+    fn draw_str(&mut self, tex_name: &str, x: i32, y: i32, text: &str) {
+        let mut texels = Vec::new();
+        {
+            let texture = self.textures.get(tex_name).expect("No texture");
+            for (i, c) in text.chars().enumerate() {
+                let code = (c as i32) - (' ' as i32);
+                let u = code % FONT_COLUMNS * CHAR_SIZE;
+                let v = code / FONT_COLUMNS * CHAR_SIZE;
+                for dy in 0..CHAR_SIZE {
+                    for dx in 0..CHAR_SIZE {
+                        texels.push((x + i as i32 * CHAR_SIZE + dx, y + dy, texture.texel(u + dx, v + dy)));
+                    }
+                }
+            }
+        }
+        for (x, y, texel) in texels {
+            self.blend(x, y, texel);
+        }
+    }
+
+    fn draw_sprite(&mut self, sprite_name: &str, pos: &Vec2I) {
+        self.copy_sprite(sprite_name, pos, 0, None);
+    }
+
+    fn draw_sprite_rot(&mut self, sprite_name: &str, pos: &Vec2I, angle: u8,
+                       center: Option<&Vec2I>) {
+        self.copy_sprite(sprite_name, pos, angle, center);
+    }
+
+    fn set_draw_color(&mut self, r: u8, g: u8, b: u8) {
+        self.draw_color = [r, g, b];
+    }
+
+    fn fill_rect(&mut self, dst: Option<[&Vec2I; 2]>) {
+        let [x, y, w, h] = match dst {
+            Some([pos, size]) => [pos.x, pos.y, size.x, size.y],
+            None => [0, 0, self.width, self.height],
+        };
+        let [r, g, b] = self.draw_color;
+        for yy in y.max(0)..(y + h).min(self.height) {
+            for xx in x.max(0)..(x + w).min(self.width) {
+                let i = ((yy * self.width + xx) * 4) as usize;
+                self.pixels[i..i + 4].copy_from_slice(&[r, g, b, 255]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framework::sprite_sheet::Rect;
+
+    // 2x3 sprite, stored rotated in a 3x2 texture.
+    fn renderer_with_sprite(rotated: bool) -> SoftwareRenderer {
+        let mut renderer = SoftwareRenderer::new(4, 4);
+        let (tw, th) = if rotated { (3, 2) } else { (2, 3) };
+        let mut pixels = Vec::new();
+        for i in 0..tw * th {
+            pixels.extend_from_slice(&[i as u8 * 10, 0, 0, 255]);
+        }
+        renderer.textures.insert(String::from("chr"), Texture {
+            width: tw, height: th, pixels, color_mod: [255, 255, 255],
+        });
+        renderer.sprite_sheet.insert(String::from("spr"), SpriteSheet {
+            texture: String::from("chr"),
+            frame: Rect { x: 0, y: 0, w: 2, h: 3 },
+            rotated,
+            trimmed: None,
+        });
+        renderer
+    }
+
+    fn red_at(renderer: &SoftwareRenderer, x: i32, y: i32) -> u8 {
+        renderer.pixels()[((y * renderer.width() + x) * 4) as usize]
+    }
+
+    #[test]
+    fn test_draw_sprite() {
+        let mut renderer = renderer_with_sprite(false);
+        renderer.draw_sprite("spr", &Vec2I::new(1, 0));
+        assert_eq!([0, 10, 20, 30, 40, 50],
+                   [red_at(&renderer, 1, 0), red_at(&renderer, 2, 0), red_at(&renderer, 1, 1),
+                    red_at(&renderer, 2, 1), red_at(&renderer, 1, 2), red_at(&renderer, 2, 2)]);
+        assert_eq!(0, red_at(&renderer, 3, 0));
+    }
+
+    #[test]
+    fn test_draw_rotated_sprite() {
+        // Sprite (sx, sy) is at texture (2 - sy, sx).
+        let mut renderer = renderer_with_sprite(true);
+        renderer.draw_sprite("spr", &Vec2I::new(0, 0));
+        assert_eq!([20, 50, 10, 40, 0, 30],
+                   [red_at(&renderer, 0, 0), red_at(&renderer, 1, 0), red_at(&renderer, 0, 1),
+                    red_at(&renderer, 1, 1), red_at(&renderer, 0, 2), red_at(&renderer, 1, 2)]);
+    }
+
+    #[test]
+    fn test_draw_sprite_rot_and_color_mod() {
+        let mut renderer = renderer_with_sprite(false);
+        renderer.set_texture_color_mod("chr", 128, 255, 255);
+        // Half turn around the center of the sprite.
+        renderer.draw_sprite_rot("spr", &Vec2I::new(0, 0), 128, None);
+        assert_eq!([25, 20, 0], [red_at(&renderer, 0, 0), red_at(&renderer, 1, 0), red_at(&renderer, 1, 2)]);
+    }
+}
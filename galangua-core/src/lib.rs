@@ -1,3 +1,4 @@
 pub mod app;
 pub mod framework;
+pub mod headless;
 pub mod util;
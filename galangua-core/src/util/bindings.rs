@@ -0,0 +1,269 @@
+use serde::{Deserialize, Serialize};
+
+use crate::framework::VKey;
+use crate::util::pad::{PadBit, ANALOG_STEPS};
+
+const BINDINGS_VERSION: u32 = 1;
+pub const DEFAULT_DEAD_ZONE: i16 = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Button {
+    Left,
+    Right,
+    Up,
+    Down,
+    Fire,
+    AutoFire,  // Fires repeatedly while held.
+}
+
+impl Button {
+    // Auto-fire has no bit, `Pad` makes fire bits for it.
+    pub fn bit(self) -> PadBit {
+        match self {
+            Button::Left => PadBit::L,
+            Button::Right => PadBit::R,
+            Button::Up => PadBit::U,
+            Button::Down => PadBit::D,
+            Button::Fire => PadBit::A,
+            Button::AutoFire => PadBit::empty(),
+        }
+    }
+}
+
+// Joystick input, directions are -1 (left or up) or 1 (right or down).
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum JoyInput {
+    Button(u8),
+    Axis(u8, i8),     // Axis index and direction, pressed beyond the dead zone.
+    Hat(u8, i8, i8),  // Hat index and direction (x, y), one of them is 0.
+}
+
+// Key and joystick assignments to pad buttons, persisted in `Settings`.
+// A button can have more than one input.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bindings {
+    pub version: u32,
+    pub keys: Vec<(Button, VKey)>,   // Player 1, or both in 1 player and alternating play.
+    pub keys2: Vec<(Button, VKey)>,  // Player 2 in co-op.
+    pub joy: Vec<(Button, JoyInput)>,
+    pub dead_zone: i16,  // Axis value (0..32767) to be pressed.
+    pub analog: bool,    // Left/right speed follows the deflection of axes.
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self {
+            version: BINDINGS_VERSION,
+            keys: vec![
+                (Button::Left, VKey::Left),
+                (Button::Right, VKey::Right),
+                (Button::Up, VKey::Up),
+                (Button::Down, VKey::Down),
+                (Button::Fire, VKey::Space),
+            ],
+            keys2: vec![
+                (Button::Left, VKey::A),
+                (Button::Right, VKey::D),
+                (Button::Fire, VKey::W),
+            ],
+            joy: vec![
+                (Button::Left, JoyInput::Axis(0, -1)),
+                (Button::Right, JoyInput::Axis(0, 1)),
+                (Button::Up, JoyInput::Axis(1, -1)),
+                (Button::Down, JoyInput::Axis(1, 1)),
+                (Button::Left, JoyInput::Hat(0, -1, 0)),
+                (Button::Right, JoyInput::Hat(0, 1, 0)),
+                (Button::Up, JoyInput::Hat(0, 0, -1)),
+                (Button::Down, JoyInput::Hat(0, 0, 1)),
+                (Button::Fire, JoyInput::Button(0)),
+                (Button::Fire, JoyInput::Button(1)),
+                (Button::Fire, JoyInput::Button(2)),
+                (Button::Fire, JoyInput::Button(3)),
+            ],
+            dead_zone: DEFAULT_DEAD_ZONE,
+            analog: true,
+        }
+    }
+}
+
+impl Bindings {
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let bindings: Self = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        if bindings.version != BINDINGS_VERSION {
+            return Err(format!("unsupported bindings version: {}", bindings.version));
+        }
+        Ok(bindings)
+    }
+
+    pub fn key_bits(&self, player2: bool, key: VKey) -> PadBit {
+        let keys = if player2 { &self.keys2 } else { &self.keys };
+        keys.iter()
+            .filter(|(_, k)| *k == key)
+            .fold(PadBit::empty(), |bits, (button, _)| bits | button.bit())
+    }
+
+    pub fn is_auto_fire_key(&self, player2: bool, key: VKey) -> bool {
+        self.keys_of(player2, Button::AutoFire).any(|k| k == key)
+    }
+
+    // Replaces the key of the button, and unbinds the key from the others.
+    pub fn bind_key(&mut self, player2: bool, button: Button, key: VKey) {
+        let keys = if player2 { &mut self.keys2 } else { &mut self.keys };
+        keys.retain(|&(b, k)| b != button && k != key);
+        keys.push((button, key));
+    }
+
+    // Replaces the joystick input of the same kind for the button.
+    pub fn bind_joy(&mut self, button: Button, input: JoyInput) {
+        let same_kind = |a: &JoyInput, b: &JoyInput| std::mem::discriminant(a) == std::mem::discriminant(b);
+        self.joy.retain(|(b, i)| !((*b == button && same_kind(i, &input)) || *i == input));
+        self.joy.push((button, input));
+    }
+
+    pub fn keys_of(&self, player2: bool, button: Button) -> impl Iterator<Item = VKey> + '_ {
+        let keys = if player2 { &self.keys2 } else { &self.keys };
+        keys.iter().filter(move |(b, _)| *b == button).map(|(_, k)| *k)
+    }
+
+    pub fn joy_of(&self, button: Button) -> impl Iterator<Item = JoyInput> + '_ {
+        self.joy.iter().filter(move |(b, _)| *b == button).map(|(_, i)| *i)
+    }
+}
+
+// Raw joystick state, to resolve overlapping inputs (e.g. an axis and a hat for the same button).
+#[derive(Clone, Debug, Default)]
+pub struct JoyState {
+    axes: Vec<i16>,
+    hats: Vec<(i8, i8)>,
+    buttons: u64,
+}
+
+impl JoyState {
+    pub fn set_axis(&mut self, index: u8, value: i16) {
+        set_at(&mut self.axes, index, value);
+    }
+
+    pub fn set_hat(&mut self, index: u8, x: i8, y: i8) {
+        set_at(&mut self.hats, index, (x, y));
+    }
+
+    pub fn set_button(&mut self, index: u8, down: bool) {
+        if index < 64 {
+            let bit = 1 << index;
+            if down { self.buttons |= bit } else { self.buttons &= !bit }
+        }
+    }
+
+    pub fn axis(&self, index: u8) -> i16 {
+        self.axes.get(index as usize).copied().unwrap_or(0)
+    }
+
+    pub fn hat(&self, index: u8) -> (i8, i8) {
+        self.hats.get(index as usize).copied().unwrap_or((0, 0))
+    }
+
+    pub fn is_button_down(&self, index: u8) -> bool {
+        index < 64 && self.buttons & (1 << index) != 0
+    }
+
+    pub fn is_on(&self, input: &JoyInput, dead_zone: i16) -> bool {
+        match *input {
+            JoyInput::Button(index) => self.is_button_down(index),
+            JoyInput::Axis(index, dir) => self.axis(index) as i32 * dir as i32 > dead_zone as i32,
+            JoyInput::Hat(index, x, y) => {
+                let (hx, hy) = self.hat(index);
+                (x == 0 || x == hx) && (y == 0 || y == hy) && (x, y) != (0, 0)
+            }
+        }
+    }
+
+    pub fn bits(&self, bindings: &Bindings) -> PadBit {
+        self.bits_by(bindings, |_| true)
+    }
+
+    // Bits by buttons and hats, without axes.
+    pub fn digital_bits(&self, bindings: &Bindings) -> PadBit {
+        self.bits_by(bindings, |input| !matches!(input, JoyInput::Axis(..)))
+    }
+
+    // Speed of left/right by the largest deflection of axes beyond the dead zone,
+    // full speed (empty) if not analog.
+    pub fn speed_bits(&self, bindings: &Bindings) -> PadBit {
+        if !bindings.analog {
+            return PadBit::empty();
+        }
+        let dead_zone = bindings.dead_zone as i32;
+        let range = (i16::MAX as i32 - dead_zone).max(1);
+        let speed = bindings.joy.iter()
+            .filter_map(|(button, input)| match *input {
+                JoyInput::Axis(index, dir) if *button == Button::Left || *button == Button::Right => {
+                    let value = self.axis(index) as i32 * dir as i32;
+                    if value > dead_zone { Some((value - dead_zone) * ANALOG_STEPS) } else { None }
+                }
+                _ => None,
+            })
+            .max();
+        match speed {
+            Some(speed) => PadBit::from_speed((speed + range - 1) / range),
+            None => PadBit::empty(),
+        }
+    }
+
+    pub fn is_auto_fire(&self, bindings: &Bindings) -> bool {
+        bindings.joy_of(Button::AutoFire).any(|input| self.is_on(&input, bindings.dead_zone))
+    }
+
+    fn bits_by(&self, bindings: &Bindings, filter: impl Fn(&JoyInput) -> bool) -> PadBit {
+        bindings.joy.iter()
+            .filter(|(_, input)| filter(input) && self.is_on(input, bindings.dead_zone))
+            .fold(PadBit::empty(), |bits, (button, _)| bits | button.bit())
+    }
+}
+
+fn set_at<T: Clone + Default>(values: &mut Vec<T>, index: u8, value: T) {
+    let index = index as usize;
+    if values.len() <= index {
+        values.resize(index + 1, T::default());
+    }
+    values[index] = value;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bind_and_json() {
+        let mut bindings = Bindings::default();
+        bindings.bind_key(false, Button::Fire, VKey::Z);
+        bindings.bind_key(false, Button::Left, VKey::Space);
+        assert_eq!(PadBit::A, bindings.key_bits(false, VKey::Z));
+        assert_eq!(PadBit::L, bindings.key_bits(false, VKey::Space));
+        assert_eq!(PadBit::empty(), bindings.key_bits(false, VKey::Left));
+        assert_eq!(PadBit::empty(), bindings.key_bits(true, VKey::Z));
+
+        bindings.bind_joy(Button::Fire, JoyInput::Button(5));
+        assert_eq!(vec![JoyInput::Button(5)], bindings.joy_of(Button::Fire).collect::<Vec<_>>());
+
+        let json = serde_json::to_string(&bindings).unwrap();
+        assert_eq!(bindings, Bindings::from_json(&json).unwrap());
+    }
+
+    #[test]
+    fn test_joy_state() {
+        let mut bindings = Bindings::default();
+        bindings.dead_zone = 20_000;
+        let mut state = JoyState::default();
+        state.set_axis(0, -15_000);
+        assert_eq!(PadBit::empty(), state.bits(&bindings));
+        state.set_axis(0, -25_000);
+        assert_eq!(PadBit::L, state.bits(&bindings));
+        state.set_hat(0, 1, -1);
+        assert_eq!(PadBit::L | PadBit::R | PadBit::U, state.bits(&bindings));
+        state.set_axis(0, 0);
+        state.set_hat(0, 0, 0);
+        state.set_button(3, true);
+        assert_eq!(PadBit::A, state.bits(&bindings));
+    }
+}
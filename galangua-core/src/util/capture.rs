@@ -0,0 +1,185 @@
+use gif::{Encoder, Frame, Repeat};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::headless::save_png;
+
+const FPS: u32 = 60;
+
+// GIF delays are in 1/100 seconds, and viewers slow down delays shorter than this.
+const MIN_GIF_DELAY: u32 = 2;
+const GIF_QUANTIZE_SPEED: i32 = 10;
+
+// (frame, channel, filename)
+type SeEvent = (u32, u32, String);
+
+enum Writer {
+    Gif {
+        encoder: Encoder<BufWriter<File>>,
+        pending: Option<(Vec<u8>, u32)>,  // Not written yet, as (RGBA, start time in 1/100 sec).
+    },
+    PngSequence {
+        base: String,
+        ext: String,
+    },
+}
+
+// Writes frames at 60 fps into an animated GIF (`*.gif`),
+// or a PNG sequence (`clip.png` is written as `clip_000000.png`, `clip_000001.png`, ...).
+// Optionally keeps a timeline of sound effects played, and writes it as text on `finish`.
+pub struct Capture {
+    width: u32,
+    height: u32,
+    writer: Writer,
+    frame: u32,
+    se_timeline: Option<(String, Vec<SeEvent>)>,
+}
+
+impl Capture {
+    pub fn create(filename: &str, width: u32, height: u32) -> Result<Self, String> {
+        let writer = if filename.to_lowercase().ends_with(".gif") {
+            let file = File::create(filename).map_err(|e| format!("{}: {}", filename, e))?;
+            let mut encoder = Encoder::new(BufWriter::new(file), width as u16, height as u16, &[])
+                .map_err(|e| format!("{}: {}", filename, e))?;
+            encoder.set_repeat(Repeat::Infinite).map_err(|e| format!("{}: {}", filename, e))?;
+            Writer::Gif { encoder, pending: None }
+        } else {
+            let (base, ext) = match filename.rfind('.') {
+                Some(i) if !filename[i..].contains('/') => (&filename[..i], &filename[i..]),
+                _ => (filename, ".png"),
+            };
+            Writer::PngSequence { base: String::from(base), ext: String::from(ext) }
+        };
+
+        Ok(Self {
+            width,
+            height,
+            writer,
+            frame: 0,
+            se_timeline: None,
+        })
+    }
+
+    // Sound effects are written into `filename` as `frame seconds channel filename` per line.
+    pub fn set_se_timeline(&mut self, filename: &str) {
+        self.se_timeline = Some((String::from(filename), Vec::new()));
+    }
+
+    // Number of frames captured so far.
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    // Records a sound effect played at the next frame.
+    pub fn add_se(&mut self, channel: u32, filename: &str) {
+        if let Some((_, timeline)) = &mut self.se_timeline {
+            timeline.push((self.frame, channel, String::from(filename)));
+        }
+    }
+
+    // Adds a frame shown for `count` frames, more than 1 when frames are skipped.
+    pub fn add_frame(&mut self, rgba: &[u8], count: u32) -> Result<(), String> {
+        let (width, height) = (self.width, self.height);
+        match &mut self.writer {
+            Writer::Gif { encoder, pending } => {
+                let now = self.frame * 100 / FPS;
+                match pending {
+                    Some((pixels, _)) if pixels.as_slice() == rgba => {}
+                    Some((pixels, start)) if now - *start < MIN_GIF_DELAY => {
+                        // Too short to show, replaced by this frame.
+                        pixels.copy_from_slice(rgba);
+                    }
+                    _ => {
+                        if let Some((mut pixels, start)) = pending.take() {
+                            write_gif_frame(encoder, width, height, &mut pixels, now - start)?;
+                        }
+                        *pending = Some((rgba.to_vec(), now));
+                    }
+                }
+            }
+            Writer::PngSequence { base, ext } => {
+                for i in 0..count {
+                    let filename = format!("{}_{:06}{}", base, self.frame + i, ext);
+                    save_png(&filename, width, height, rgba)?;
+                }
+            }
+        }
+        self.frame += count;
+        Ok(())
+    }
+
+    // Writes the rest and closes the files.
+    pub fn finish(self) -> Result<(), String> {
+        let (width, height) = (self.width, self.height);
+        if let Writer::Gif { mut encoder, pending } = self.writer {
+            if let Some((mut pixels, start)) = pending {
+                let delay = (self.frame * 100 / FPS).saturating_sub(start).max(MIN_GIF_DELAY);
+                write_gif_frame(&mut encoder, width, height, &mut pixels, delay)?;
+            }
+            let mut writer = encoder.into_inner().map_err(|e| e.to_string())?;
+            writer.flush().map_err(|e| e.to_string())?;
+        }
+
+        if let Some((filename, timeline)) = &self.se_timeline {
+            let text: String = timeline.iter()
+                .map(|(frame, channel, se)| format!("{} {:.3} {} {}\n", frame, *frame as f64 / FPS as f64, channel, se))
+                .collect();
+            std::fs::write(filename, text).map_err(|e| format!("{}: {}", filename, e))?;
+        }
+        Ok(())
+    }
+}
+
+fn write_gif_frame(encoder: &mut Encoder<BufWriter<File>>, width: u32, height: u32,
+                   rgba: &mut [u8], delay: u32) -> Result<(), String> {
+    let mut frame = Frame::from_rgba_speed(width as u16, height as u16, rgba, GIF_QUANTIZE_SPEED);
+    frame.delay = delay as u16;
+    encoder.write_frame(&frame).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> String {
+        let dir = std::env::temp_dir().join("galangua-capture-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join(name).to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_gif_timing() {
+        let filename = temp_path("test.gif");
+        let mut capture = Capture::create(&filename, 2, 2).unwrap();
+        let black = [0, 0, 0, 255].repeat(4);
+        let white = [255, 255, 255, 255].repeat(4);
+        for i in 0..60 {
+            capture.add_frame(if (i / 6) & 1 == 0 { &black } else { &white }, 1).unwrap();
+        }
+        capture.finish().unwrap();
+
+        let mut decoder = gif::DecodeOptions::new().read_info(File::open(&filename).unwrap()).unwrap();
+        let mut delays = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            delays.push(frame.delay);
+        }
+        assert_eq!(10, delays.len());
+        assert_eq!(100, delays.iter().sum::<u16>());
+    }
+
+    #[test]
+    fn test_png_sequence_and_se_timeline() {
+        let filename = temp_path("seq.png");
+        let timeline = temp_path("seq.txt");
+        let mut capture = Capture::create(&filename, 1, 1).unwrap();
+        capture.set_se_timeline(&timeline);
+        capture.add_frame(&[0, 0, 0, 255], 1).unwrap();
+        capture.add_se(2, "se.ogg");
+        capture.add_frame(&[0, 0, 0, 255], 2).unwrap();
+        assert_eq!(3, capture.frame());
+        capture.finish().unwrap();
+
+        assert!(std::path::Path::new(&temp_path("seq_000002.png")).exists());
+        assert_eq!("1 0.017 2 se.ogg\n", std::fs::read_to_string(&timeline).unwrap());
+    }
+}
@@ -1,11 +1,14 @@
 pub trait TimerTrait {
     fn passed_one_second(&mut self) -> bool;
+    // Starts counting a second from now.
+    fn reset(&mut self);
 }
 
 pub struct FpsCalc<T: TimerTrait> {
     fps: i32,
     timer: T,
     ndraw: i32,
+    paused: bool,
 }
 
 impl<T: TimerTrait> FpsCalc<T> {
@@ -14,10 +17,24 @@ impl<T: TimerTrait> FpsCalc<T> {
             fps: 0,
             timer,
             ndraw: 0,
+            paused: false,
         }
     }
 
+    // Frames drawn while paused are not counted, and the timer restarts on resume.
+    pub fn set_paused(&mut self, paused: bool) {
+        if self.paused && !paused {
+            self.timer.reset();
+            self.ndraw = 0;
+        }
+        self.paused = paused;
+    }
+
     pub fn update(&mut self) -> bool {
+        if self.paused {
+            return false;
+        }
+
         self.ndraw += 1;
         if !self.timer.passed_one_second() {
             return false;
@@ -1,3 +1,8 @@
+pub mod bindings;
+#[cfg(feature = "capture")]
+pub mod capture;
 pub mod fps_calc;
 pub mod math;
 pub mod pad;
+pub mod replay;
+pub mod serde_array;
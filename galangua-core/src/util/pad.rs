@@ -1,6 +1,8 @@
 use bitflags::bitflags;
 
 use crate::framework::VKey;
+use crate::util::bindings::{Bindings, JoyState};
+use crate::util::replay::Replay;
 
 bitflags! {
     pub struct PadBit: u32 {
@@ -9,33 +11,155 @@ bitflags! {
         const U      = 0b00000100;
         const D      = 0b00001000;
         const A      = 0b00010000;
+        const SPEED  = 0b11100000;  // Analog speed of left/right, see `speed`.
     }
 }
 
+// Steps of analog speed.
+pub const ANALOG_STEPS: i32 = 8;
+const SPEED_SHIFT: u32 = 5;
+const AUTO_FIRE_INTERVAL: u32 = 8;  // Frames between shots by auto-fire.
+
+impl PadBit {
+    // Analog speed in 1/ANALOG_STEPS, `ANALOG_STEPS` for digital input (0 in the bits).
+    pub fn speed(self) -> i32 {
+        match (self & PadBit::SPEED).bits() >> SPEED_SHIFT {
+            0 => ANALOG_STEPS,
+            speed => speed as i32,
+        }
+    }
+
+    // `speed` is in 1..=ANALOG_STEPS.
+    pub fn from_speed(speed: i32) -> PadBit {
+        if speed >= ANALOG_STEPS {
+            PadBit::empty()
+        } else {
+            PadBit::from_bits_truncate((speed.max(1) as u32) << SPEED_SHIFT)
+        }
+    }
+}
+
+// Inputs which a pad reads, split for simultaneous 2 players.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PadSource {
+    All,      // Player 1's keys and joystick.
+    Player1,  // Player 1's keys (arrow keys and space key by default).
+    Player2,  // Player 2's keys (A/D/W keys by default) and joystick.
+}
+
 pub struct Pad {
+    source: PadSource,
     pad: PadBit,
     trg: PadBit,
     last_pad: PadBit,
-    key: PadBit,
-    joy: PadBit,
+    bindings: Bindings,
+    held_keys: Vec<VKey>,
+    joy_state: JoyState,
+    auto_fire_count: u32,
+    recording: Option<Replay>,
+    playback: Option<(Replay, usize)>,
 }
 
 impl Pad {
     pub fn new() -> Self {
         let empty = PadBit::empty();
         Self {
+            source: PadSource::All,
             pad: empty,
             trg: empty,
             last_pad: empty,
-            key: empty,
-            joy: empty,
+            bindings: Bindings::default(),
+            held_keys: Vec::new(),
+            joy_state: JoyState::default(),
+            auto_fire_count: 0,
+            recording: None,
+            playback: None,
         }
     }
 
+    // Keeps the current key state, joystick state is cleared when it becomes unread.
+    pub fn set_source(&mut self, source: PadSource) {
+        if source == PadSource::Player1 {
+            self.joy_state = JoyState::default();
+        }
+        self.source = source;
+    }
+
+    pub fn set_bindings(&mut self, bindings: &Bindings) {
+        self.bindings = bindings.clone();
+    }
+
     pub fn update(&mut self) {
-        self.pad = self.key | self.joy;
-        self.trg = self.pad & !self.last_pad;
-        self.last_pad = self.pad;
+        self.update_pads(None, None);
+    }
+
+    // Sets the state directly instead of keyboard/joystick, e.g. for a pilot.
+    // Recorded as well as live input.
+    pub fn update_with(&mut self, bits: PadBit) {
+        self.update_pads(Some(bits), None);
+    }
+
+    // Updates together with player 2's pad, whose input goes into the same replay.
+    // `bits` sets the state of this pad directly, like `update_with`.
+    pub fn update_pads(&mut self, bits: Option<PadBit>, mut pad2: Option<&mut Pad>) {
+        let mut bits = [
+            bits.unwrap_or_else(|| self.live_bits()),
+            pad2.as_mut().map_or(PadBit::empty(), |pad2| pad2.live_bits()),
+        ];
+        if let Some((replay, frame)) = &mut self.playback {
+            if let Some(recorded) = replay.get(*frame) {
+                bits = recorded;
+                *frame += 1;
+            } else {
+                // Replay ended: back to live input.
+                self.playback = None;
+            }
+        }
+        if let Some(replay) = &mut self.recording {
+            replay.push(bits);
+        }
+
+        self.set_bits(bits[0]);
+        if let Some(pad2) = pad2 {
+            pad2.set_bits(bits[1]);
+        }
+    }
+
+    // Reads live input without recording or playback, e.g. for a pause menu.
+    // Triggers of the next `update` are against the last recorded state, to keep replays in sync.
+    pub fn update_live(&mut self) {
+        let bits = self.live_bits();
+        self.trg = bits & !self.pad;
+        self.pad = bits;
+    }
+
+    // Records into an empty `replay`.
+    pub fn start_recording(&mut self, replay: Replay) {
+        self.recording = Some(replay);
+        self.reset_trigger();
+    }
+
+    pub fn recording_seed(&self) -> Option<u64> {
+        self.recording.as_ref().map(|replay| replay.seed)
+    }
+
+    pub fn take_recording(&mut self) -> Option<Replay> {
+        self.recording.take()
+    }
+
+    // Feed recorded bits in place of live keyboard/joystick input.
+    pub fn start_playback(&mut self, replay: Replay) {
+        self.playback = Some((replay, 0));
+        self.reset_trigger();
+    }
+
+    pub fn is_playing_back(&self) -> bool {
+        self.playback.is_some()
+    }
+
+    // Forgets the last state, for triggers to be the same as in a replay from here.
+    pub fn reset_trigger(&mut self) {
+        self.last_pad = PadBit::empty();
     }
 
     pub fn is_pressed(&self, btn: PadBit) -> bool {
@@ -46,57 +170,79 @@ impl Pad {
         self.trg.contains(btn)
     }
 
+    pub fn is_any_trigger(&self) -> bool {
+        !(self.trg - PadBit::SPEED).is_empty()
+    }
+
+    // Analog speed of left/right in 1/ANALOG_STEPS.
+    pub fn speed(&self) -> i32 {
+        self.pad.speed()
+    }
+
     pub fn on_key(&mut self, keycode: VKey, down: bool) {
-        let bit = get_key_bit(keycode);
+        self.held_keys.retain(|&key| key != keycode);
         if down {
-            self.key |= bit;
-        } else {
-            self.key &= !bit;
+            self.held_keys.push(keycode);
         }
     }
 
-    pub fn on_joystick_axis(&mut self, axis_index: u8, dir: i8) {
-        match axis_index {
-            0 => {
-                let lr = if dir < 0 { PadBit::L }
-                    else if dir > 0 { PadBit::R }
-                    else { PadBit::empty() };
-                self.joy = (self.joy & !(PadBit::L | PadBit::R)) | lr;
-            }
-            1 => {
-                let ud = if dir < 0 { PadBit::U }
-                    else if dir > 0 { PadBit::D }
-                    else { PadBit::empty() };
-                self.joy = (self.joy & !(PadBit::U | PadBit::D)) | ud;
-            }
-            _ => {}
+    // `value` is a raw axis value, pressed beyond the dead zone of the bindings.
+    pub fn on_joystick_axis(&mut self, axis_index: u8, value: i16) {
+        if self.source != PadSource::Player1 {
+            self.joy_state.set_axis(axis_index, value);
         }
     }
 
-    pub fn on_joystick_button(&mut self, _button_index: u8, down: bool) {
-        let bit = PadBit::A;
-        if down {
-            self.joy |= bit;
-        } else {
-            self.joy &= !bit;
+    // `x` and `y` are -1 (left or up), 0 or 1 (right or down).
+    pub fn on_joystick_hat(&mut self, hat_index: u8, x: i8, y: i8) {
+        if self.source != PadSource::Player1 {
+            self.joy_state.set_hat(hat_index, x, y);
         }
     }
-}
 
-fn get_key_bit(key: VKey) -> PadBit {
-    match key {
-        VKey::Left => PadBit::L,
-        VKey::Right => PadBit::R,
-        VKey::Up => PadBit::U,
-        VKey::Down => PadBit::D,
-        VKey::Space => PadBit::A,
-        _ => PadBit::empty(),
+    pub fn on_joystick_button(&mut self, button_index: u8, down: bool) {
+        if self.source != PadSource::Player1 {
+            self.joy_state.set_button(button_index, down);
+        }
+    }
+
+    fn set_bits(&mut self, bits: PadBit) {
+        self.pad = bits;
+        self.trg = self.pad & !self.last_pad;
+        self.last_pad = self.pad;
+    }
+
+    // Keyboard and joystick, with analog speed and auto-fire.
+    fn live_bits(&mut self) -> PadBit {
+        let player2 = self.source == PadSource::Player2;
+        let key_bits = self.held_keys.iter()
+            .fold(PadBit::empty(), |bits, &key| bits | self.bindings.key_bits(player2, key));
+        let mut bits = key_bits | self.joy_state.bits(&self.bindings);
+
+        // Digital input takes priority.
+        let digital = key_bits | self.joy_state.digital_bits(&self.bindings);
+        if !digital.intersects(PadBit::L | PadBit::R) {
+            bits |= self.joy_state.speed_bits(&self.bindings);
+        }
+
+        let auto_fire = self.held_keys.iter().any(|&key| self.bindings.is_auto_fire_key(player2, key)) ||
+            self.joy_state.is_auto_fire(&self.bindings);
+        if auto_fire {
+            if self.auto_fire_count % AUTO_FIRE_INTERVAL < AUTO_FIRE_INTERVAL / 2 {
+                bits |= PadBit::A;
+            }
+            self.auto_fire_count = self.auto_fire_count.wrapping_add(1);
+        } else {
+            self.auto_fire_count = 0;
+        }
+        bits
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::util::bindings::Button;
 
     #[test]
     fn test_trigger() {
@@ -111,4 +257,89 @@ mod tests {
         assert_eq!(true, pad.is_pressed(PadBit::A));
         assert_eq!(false, pad.is_trigger(PadBit::A));
     }
+
+    #[test]
+    fn test_record_and_playback() {
+        let mut pad = Pad::new();
+        let mut pad2 = Pad::new();
+        pad2.set_source(PadSource::Player2);
+        pad.start_recording(Replay::new(123, None));
+        pad.on_key(VKey::Left, true);
+        pad2.on_key(VKey::D, true);
+        pad.update_pads(None, Some(&mut pad2));
+        pad.on_key(VKey::Left, false);
+        pad.on_key(VKey::Space, true);
+        pad2.on_key(VKey::D, false);
+        pad2.on_key(VKey::W, true);
+        pad.update_pads(None, Some(&mut pad2));
+        let replay = pad.take_recording().unwrap();
+        assert_eq!(123, replay.seed);
+        assert_eq!(2, replay.len());
+
+        let mut pad = Pad::new();
+        let mut pad2 = Pad::new();
+        pad2.set_source(PadSource::Player2);
+        pad.on_key(VKey::Right, true);  // Ignored while playing back.
+        pad2.on_key(VKey::A, true);
+        pad.start_playback(replay);
+        pad.update_pads(None, Some(&mut pad2));
+        assert_eq!(true, pad.is_pressed(PadBit::L));
+        assert_eq!(false, pad.is_pressed(PadBit::R));
+        assert_eq!(true, pad2.is_pressed(PadBit::R));
+        assert_eq!(false, pad2.is_pressed(PadBit::L));
+        pad.update_pads(None, Some(&mut pad2));
+        assert_eq!(true, pad.is_trigger(PadBit::A));
+        assert_eq!(true, pad2.is_trigger(PadBit::A));
+
+        pad.update_pads(None, Some(&mut pad2));
+        assert_eq!(false, pad.is_playing_back());
+        assert_eq!(true, pad.is_pressed(PadBit::R));
+        assert_eq!(true, pad2.is_pressed(PadBit::L));
+    }
+
+    #[test]
+    fn test_analog_and_auto_fire() {
+        let mut bindings = Bindings::default();
+        bindings.bind_key(false, Button::AutoFire, VKey::Z);
+        let mut pad = Pad::new();
+        pad.set_bindings(&bindings);
+
+        pad.on_joystick_axis(0, -(bindings.dead_zone + (32767 - bindings.dead_zone) / 2));
+        pad.on_key(VKey::Z, true);
+        let mut fires = 0;
+        for _ in 0..AUTO_FIRE_INTERVAL * 2 {
+            pad.update();
+            assert_eq!(true, pad.is_pressed(PadBit::L));
+            assert_eq!(ANALOG_STEPS / 2, pad.speed());
+            if pad.is_trigger(PadBit::A) {
+                fires += 1;
+            }
+        }
+        assert_eq!(2, fires);
+
+        pad.on_key(VKey::Left, true);
+        pad.update();
+        assert_eq!(ANALOG_STEPS, pad.speed());
+    }
+
+    #[test]
+    fn test_source() {
+        let mut pad1 = Pad::new();
+        let mut pad2 = Pad::new();
+        pad1.set_source(PadSource::Player1);
+        pad2.set_source(PadSource::Player2);
+        for pad in [&mut pad1, &mut pad2].iter_mut() {
+            pad.on_key(VKey::Left, true);
+            pad.on_key(VKey::W, true);
+            pad.on_joystick_axis(0, 20_000);
+            pad.update();
+        }
+
+        assert_eq!(true, pad1.is_pressed(PadBit::L));
+        assert_eq!(false, pad1.is_pressed(PadBit::R));
+        assert_eq!(false, pad1.is_pressed(PadBit::A));
+        assert_eq!(false, pad2.is_pressed(PadBit::L));
+        assert_eq!(true, pad2.is_pressed(PadBit::R));
+        assert_eq!(true, pad2.is_pressed(PadBit::A));
+    }
 }
@@ -0,0 +1,221 @@
+use crate::app::GameConfig;
+use crate::util::pad::PadBit;
+
+// Replay file layout (little endian):
+//   "GLRP", version: u8, seed: u64, frame count: u32,
+//   game config: (length: u16, JSON), 0 length for none,
+//   then runs of (player 1's pad bits: u8, player 2's pad bits: u8, run length: LEB128 varint).
+// Version 2 has no game config, and version 1 has no player 2's bits either, read as no input.
+const MAGIC: &[u8; 4] = b"GLRP";
+const VERSION: u8 = 3;
+const HEADER_SIZE: usize = 4 + 1 + 8 + 4;
+const MAX_FRAMES: usize = 60 * 60 * 60 * 24;  // A day at 60 fps, to reject a broken count.
+
+// Per-frame pad state of both players, together with the random seed
+// and the rules of games to reproduce a run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    pub config: Option<GameConfig>,  // `None` in old files: the current one is used.
+    frames: Vec<[PadBit; 2]>,
+}
+
+impl Replay {
+    pub fn new(seed: u64, config: Option<GameConfig>) -> Self {
+        Self {
+            seed,
+            config,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn push(&mut self, bits: [PadBit; 2]) {
+        self.frames.push(bits);
+    }
+
+    pub fn get(&self, frame: usize) -> Option<[PadBit; 2]> {
+        self.frames.get(frame).copied()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + 16);
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&(self.frames.len() as u32).to_le_bytes());
+
+        let config = self.config.as_ref()
+            .map_or_else(Vec::new, |config| serde_json::to_vec(config).unwrap());
+        bytes.extend_from_slice(&(config.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(&config);
+
+        let mut i = 0;
+        while i < self.frames.len() {
+            let bits = self.frames[i];
+            let run = self.frames[i..].iter().take_while(|&&x| x == bits).count();
+            bytes.push(bits[0].bits() as u8);
+            bytes.push(bits[1].bits() as u8);
+            write_varint(&mut bytes, run as u32);
+            i += run;
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() < HEADER_SIZE || &bytes[0..4] != MAGIC {
+            return Err(String::from("not a replay file"));
+        }
+        let version = bytes[4];
+        if version == 0 || version > VERSION {
+            return Err(format!("unsupported replay version: {}", version));
+        }
+        let pad_count = if version == 1 { 1 } else { 2 };
+        let mut seed_bytes = [0; 8];
+        seed_bytes.copy_from_slice(&bytes[5..13]);
+        let mut count_bytes = [0; 4];
+        count_bytes.copy_from_slice(&bytes[13..17]);
+        let count = u32::from_le_bytes(count_bytes) as usize;
+        if count > MAX_FRAMES {
+            return Err(format!("too many frames: {}", count));
+        }
+
+        let mut p = HEADER_SIZE;
+        let mut config = None;
+        if version >= 3 {
+            let len = bytes.get(p..p + 2).ok_or("broken game config")?;
+            let len = u16::from_le_bytes([len[0], len[1]]) as usize;
+            p += 2;
+            if len > 0 {
+                let json = bytes.get(p..p + len).ok_or("broken game config")?;
+                config = Some(serde_json::from_slice::<GameConfig>(json)
+                    .map_err(|e| format!("broken game config: {}", e))?);
+                p += len;
+            }
+        }
+
+        // Grown by decoded runs, not to trust the count in a broken file.
+        let mut frames = Vec::new();
+        while p < bytes.len() {
+            let mut bits = [PadBit::empty(); 2];
+            for (i, bits) in bits.iter_mut().take(pad_count).enumerate() {
+                let b = *bytes.get(p + i).ok_or_else(|| format!("broken run at {}", p))?;
+                *bits = PadBit::from_bits(b as u32)
+                    .ok_or_else(|| format!("illegal pad bits at {}", p + i))?;
+            }
+            p += pad_count;
+            let (run, next) = read_varint(bytes, p)
+                .ok_or_else(|| format!("broken run length at {}", p))?;
+            if frames.len() + run as usize > count {
+                return Err(String::from("too many frames"));
+            }
+            frames.resize(frames.len() + run as usize, bits);
+            p = next;
+        }
+        if frames.len() != count {
+            return Err(format!("frame count mismatch: {} != {}", frames.len(), count));
+        }
+
+        Ok(Self {
+            seed: u64::from_le_bytes(seed_bytes),
+            config,
+            frames,
+        })
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn read_varint(bytes: &[u8], mut p: usize) -> Option<(u32, usize)> {
+    let mut value: u32 = 0;
+    let mut shift = 0;
+    loop {
+        let b = *bytes.get(p)?;
+        p += 1;
+        if shift > 28 {
+            return None;
+        }
+        value |= ((b & 0x7f) as u32) << shift;
+        if b & 0x80 == 0 {
+            return Some((value, p));
+        }
+        shift += 7;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::Difficulty;
+
+    #[test]
+    fn test_bytes_roundtrip() {
+        let mut replay = Replay::new(0x1234_5678_9abc_def0, None);
+        for _ in 0..300 {
+            replay.push([PadBit::empty(), PadBit::empty()]);
+        }
+        replay.push([PadBit::A | PadBit::L, PadBit::empty()]);
+        replay.push([PadBit::L, PadBit::empty()]);
+        replay.push([PadBit::L, PadBit::R]);
+
+        let bytes = replay.to_bytes();
+        assert_eq!(HEADER_SIZE + 2 + 4 + 3 + 3 + 3, bytes.len());
+        assert_eq!(Ok(replay.clone()), Replay::from_bytes(&bytes));
+
+        let config = GameConfig { starting_ships: 5, ..GameConfig::preset(Difficulty::Hard) };
+        replay.config = Some(config);
+        assert_eq!(Ok(replay.clone()), Replay::from_bytes(&replay.to_bytes()));
+    }
+
+    #[test]
+    fn test_from_bytes_version1() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(1);
+        bytes.extend_from_slice(&7u64.to_le_bytes());
+        bytes.extend_from_slice(&3u32.to_le_bytes());
+        bytes.extend_from_slice(&[PadBit::L.bits() as u8, 2, PadBit::A.bits() as u8, 1]);
+
+        let replay = Replay::from_bytes(&bytes).unwrap();
+        assert_eq!(7, replay.seed);
+        assert_eq!(3, replay.len());
+        assert_eq!(Some([PadBit::L, PadBit::empty()]), replay.get(1));
+        assert_eq!(Some([PadBit::A, PadBit::empty()]), replay.get(2));
+    }
+
+    #[test]
+    fn test_from_bytes_error() {
+        assert!(Replay::from_bytes(b"GLRQ").is_err());
+
+        let mut replay = Replay::new(1, None);
+        replay.push([PadBit::R, PadBit::empty()]);
+        let mut bytes = replay.to_bytes();
+        bytes.pop();
+        assert!(Replay::from_bytes(&bytes).is_err());
+
+        // Truncated file with a huge frame count.
+        let mut bytes = replay.to_bytes();
+        bytes[13..17].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(Replay::from_bytes(&bytes[..HEADER_SIZE + 1]).is_err());
+        bytes[13..17].copy_from_slice(&(MAX_FRAMES as u32).to_le_bytes());
+        assert!(Replay::from_bytes(&bytes[..HEADER_SIZE + 1]).is_err());
+
+        // Game config beyond the end.
+        let mut bytes = replay.to_bytes();
+        bytes[HEADER_SIZE..HEADER_SIZE + 2].copy_from_slice(&100u16.to_le_bytes());
+        assert!(Replay::from_bytes(&bytes).is_err());
+    }
+}
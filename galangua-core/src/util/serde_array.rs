@@ -0,0 +1,25 @@
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::convert::TryFrom;
+
+// Serde supports arrays only up to 32 elements,
+// use with `#[serde(with = "crate::util::serde_array")]` for larger ones.
+
+pub fn serialize<S, T, const N: usize>(array: &[T; N], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Serialize,
+{
+    serializer.collect_seq(array.iter())
+}
+
+pub fn deserialize<'de, D, T, const N: usize>(deserializer: D) -> Result<[T; N], D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    let vec = Vec::<T>::deserialize(deserializer)?;
+    let len = vec.len();
+    <[T; N]>::try_from(vec)
+        .map_err(|_| D::Error::invalid_length(len, &format!("an array of length {}", N).as_str()))
+}
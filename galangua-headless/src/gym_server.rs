@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Write};
+
+use galangua_core::app::gym::{GymEnv, ACTION_COUNT};
+
+// Gym environment over stdin/stdout, one JSON per line:
+//
+//   -> {"cmd": "info"}
+//   <- {"action_count": 6, "observation_shape": [562]}
+//   -> {"cmd": "reset", "seed": 1}
+//   <- {"observation": [...]}
+//   -> {"cmd": "step", "action": 3}
+//   <- {"observation": [...], "reward": 10.0, "done": false}
+//
+// Runs until `{"cmd": "close"}` or the end of input. An illegal request gets `{"error": "..."}`.
+
+#[derive(Deserialize)]
+#[serde(tag = "cmd", rename_all = "lowercase")]
+enum Request {
+    Info,
+    Reset {
+        #[serde(default)]
+        seed: u64,
+    },
+    Step {
+        action: usize,
+    },
+    Close,
+}
+
+#[derive(Serialize)]
+struct Info {
+    action_count: usize,
+    observation_shape: Vec<usize>,
+}
+
+#[derive(Serialize)]
+struct Error {
+    error: String,
+}
+
+pub fn serve(env: &mut GymEnv) -> Result<(), String> {
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    let mut out = std::io::BufWriter::new(stdout.lock());
+
+    for line in stdin.lock().lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(Request::Info) => {
+                let info = Info {
+                    action_count: ACTION_COUNT,
+                    observation_shape: env.observation_shape(),
+                };
+                serde_json::to_string(&info)
+            }
+            Ok(Request::Reset { seed }) => {
+                let observation = env.reset(seed);
+                serde_json::to_string(&serde_json::json!({ "observation": observation }))
+            }
+            Ok(Request::Step { action }) if action < ACTION_COUNT => {
+                serde_json::to_string(&env.step(action))
+            }
+            Ok(Request::Step { action }) => {
+                serde_json::to_string(&Error { error: format!("illegal action: {}", action) })
+            }
+            Ok(Request::Close) => break,
+            Err(e) => serde_json::to_string(&Error { error: e.to_string() }),
+        }.map_err(|e| e.to_string())?;
+
+        writeln!(out, "{}", response).map_err(|e| e.to_string())?;
+        out.flush().map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use counted_array::counted_array;
+
+use galangua_core::framework::VKey;
+
+// Scripted key input, one event per line:
+//
+//   # frame  key    down|up
+//   60       Space  down
+//   62       Space  up
+//
+// Lines starting with `#` are comments. Events must be sorted by frame.
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct InputEvent {
+    pub frame: u32,
+    pub vkey: VKey,
+    pub down: bool,
+}
+
+pub struct InputScript {
+    events: Vec<InputEvent>,
+    index: usize,
+}
+
+impl InputScript {
+    pub fn new(events: Vec<InputEvent>) -> Self {
+        Self {
+            events,
+            index: 0,
+        }
+    }
+
+    pub fn load(filename: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(filename)
+            .map_err(|e| format!("{}: {}", filename, e))?;
+        let events = parse_input_script(&text)
+            .map_err(|e| format!("{}: {}", filename, e))?;
+        Ok(Self::new(events))
+    }
+
+    // Returns events which should be fed at the given frame.
+    pub fn events_at(&mut self, frame: u32) -> &[InputEvent] {
+        let start = self.index;
+        while self.index < self.events.len() && self.events[self.index].frame <= frame {
+            self.index += 1;
+        }
+        &self.events[start..self.index]
+    }
+}
+
+pub fn parse_input_script(text: &str) -> Result<Vec<InputEvent>, String> {
+    let mut events: Vec<InputEvent> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let lineno = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.len() != 3 {
+            return Err(format!("Line {}: `frame key down|up` expected", lineno));
+        }
+        let frame = words[0].parse::<u32>()
+            .map_err(|_| format!("Line {}: frame number expected", lineno))?;
+        let vkey = find_vkey(words[1])
+            .ok_or_else(|| format!("Line {}: unknown key `{}`", lineno, words[1]))?;
+        let down = match words[2] {
+            "down" => true,
+            "up" => false,
+            _ => return Err(format!("Line {}: `down` or `up` expected", lineno)),
+        };
+        if let Some(last) = events.last() {
+            if frame < last.frame {
+                return Err(format!("Line {}: frame must not decrease", lineno));
+            }
+        }
+        events.push(InputEvent { frame, vkey, down });
+    }
+    Ok(events)
+}
+
+fn find_vkey(name: &str) -> Option<VKey> {
+    KEY_NAME_TABLE.iter().find(|(key_name, _)| *key_name == name).map(|(_, vkey)| *vkey)
+}
+
+counted_array!(const KEY_NAME_TABLE: [(&str, VKey); _] = [
+    ("Space",  VKey::Space),
+    ("Return", VKey::Return),
+    ("Escape", VKey::Escape),
+    ("Left",   VKey::Left),
+    ("Right",  VKey::Right),
+    ("Up",     VKey::Up),
+    ("Down",   VKey::Down),
+
+    ("A", VKey::A), ("B", VKey::B), ("C", VKey::C), ("D", VKey::D),
+    ("E", VKey::E), ("F", VKey::F), ("G", VKey::G), ("H", VKey::H),
+    ("I", VKey::I), ("J", VKey::J), ("K", VKey::K), ("L", VKey::L),
+    ("M", VKey::M), ("N", VKey::N), ("O", VKey::O), ("P", VKey::P),
+    ("Q", VKey::Q), ("R", VKey::R), ("S", VKey::S), ("T", VKey::T),
+    ("U", VKey::U), ("V", VKey::V), ("W", VKey::W), ("X", VKey::X),
+    ("Y", VKey::Y), ("Z", VKey::Z),
+
+    ("Num0", VKey::Num0), ("Num1", VKey::Num1), ("Num2", VKey::Num2),
+    ("Num3", VKey::Num3), ("Num4", VKey::Num4), ("Num5", VKey::Num5),
+    ("Num6", VKey::Num6), ("Num7", VKey::Num7), ("Num8", VKey::Num8),
+    ("Num9", VKey::Num9),
+
+    ("F1", VKey::F1), ("F2", VKey::F2), ("F3", VKey::F3), ("F4", VKey::F4),
+    ("F5", VKey::F5), ("F6", VKey::F6), ("F7", VKey::F7), ("F8", VKey::F8),
+    ("F9", VKey::F9), ("F10", VKey::F10), ("F11", VKey::F11), ("F12", VKey::F12),
+]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_input_script() {
+        let events = parse_input_script("# comment\n\n10 Space down\n12 Space up\n").unwrap();
+        assert_eq!(vec![InputEvent { frame: 10, vkey: VKey::Space, down: true },
+                        InputEvent { frame: 12, vkey: VKey::Space, down: false }],
+                   events);
+
+        assert_eq!(Err(String::from("Line 1: unknown key `Foo`")),
+                   parse_input_script("1 Foo down"));
+        assert_eq!(Err(String::from("Line 2: frame must not decrease")),
+                   parse_input_script("5 Left down\n4 Left up"));
+    }
+
+    #[test]
+    fn test_events_at() {
+        let mut script = InputScript::new(parse_input_script("1 Left down\n1 Space down\n3 Left up").unwrap());
+        assert_eq!(0, script.events_at(0).len());
+        assert_eq!(2, script.events_at(1).len());
+        assert_eq!(0, script.events_at(2).len());
+        assert_eq!(1, script.events_at(3).len());
+    }
+}
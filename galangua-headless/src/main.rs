@@ -0,0 +1,299 @@
+mod gym_server;
+mod input_script;
+
+use galangua_core::app::gym::{GymConfig, GymEnv, ObservationType};
+use galangua_core::app::consts::{HEIGHT, WIDTH};
+use galangua_core::app::pilot::HeuristicPilot;
+use galangua_core::app::{Difficulty, EnemyType, GalanguaApp, GameConfig, StagePack};
+use galangua_core::framework::AppTrait;
+use galangua_core::headless::{HeadlessRenderer, HeadlessSystem, HeadlessTimer, SoftwareRenderer};
+use galangua_core::util::capture::Capture;
+use galangua_core::util::replay::Replay;
+
+use crate::input_script::InputScript;
+
+type App = GalanguaApp<HeadlessTimer, HeadlessSystem>;
+
+const APP_NAME: &str = "galangua-headless";
+const DEFAULT_FRAMES: u32 = 60 * 60;
+
+pub fn main() -> Result<(), String> {
+    let matches = clap::App::new(APP_NAME)
+        .version("0.1.0")
+        .about("Run Galangua without display and audio.")
+        .arg(clap::Arg::with_name("frames")
+             .help("Number of frames to run (default: 3600)")
+             .short("n")
+             .long("frames")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("input")
+             .help("Scripted input file, `frame key down|up` per line")
+             .short("i")
+             .long("input")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("seed")
+             .help("Specify random seed (default: random)")
+             .long("seed")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("stage-pack")
+             .help("Load stage definitions from a JSON file")
+             .long("stage-pack")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("difficulty")
+             .help("Game difficulty: easy, normal, hard or arcade (default: the settings)")
+             .long("difficulty")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("ships")
+             .help("Number of fighters to start with, 1-5 (default: the settings)")
+             .long("ships")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("dump-stage-pack")
+             .help("Print the built-in stage pack, as a base for a new one")
+             .long("dump-stage-pack"))
+        .arg(clap::Arg::with_name("dump-traj")
+             .help("Print a trajectory of the stage pack in text format")
+             .long("dump-traj")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("record")
+             .help("Record input into a replay file")
+             .long("record")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("replay")
+             .help("Play back a replay file")
+             .long("replay")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("load-state")
+             .help("Start from a snapshot file")
+             .long("load-state")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("save-state")
+             .help("Save a snapshot file at the end")
+             .long("save-state")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("bot")
+             .help("Play by the built-in heuristic bot instead of input")
+             .long("bot"))
+        .arg(clap::Arg::with_name("gym")
+             .help("Serve a gym environment over stdin/stdout in JSON lines, `-n` limits frames of an episode")
+             .long("gym"))
+        .arg(clap::Arg::with_name("observation")
+             .help("Observation of the gym environment: vector (default) or framebuffer")
+             .long("observation")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("frame-skip")
+             .help("Frames per step of the gym environment, 1 or more (default: 4)")
+             .long("frame-skip")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("png")
+             .help("Render the last frame into a PNG file")
+             .long("png")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("capture")
+             .help("Capture frames into an animated GIF (*.gif) or a PNG sequence (*.png)")
+             .long("capture")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("capture-se")
+             .help("Write a timeline of sound effects in the capture, `frame seconds channel file` per line")
+             .long("capture-se")
+             .takes_value(true)
+             .requires("capture"))
+        .arg(clap::Arg::with_name("dump")
+             .help("Dump draw commands of the last frame")
+             .short("d")
+             .long("dump")
+             .conflicts_with("capture"))
+        .get_matches();
+
+    if matches.is_present("dump-stage-pack") {
+        println!("{}", StagePack::default().to_json());
+        return Ok(());
+    }
+
+    if let Some(name) = matches.value_of("dump-traj") {
+        let stage_pack = if let Some(filename) = matches.value_of("stage-pack") {
+            load_stage_pack(filename)?
+        } else {
+            StagePack::default()
+        };
+        let text = stage_pack.traj_text(name).ok_or_else(|| format!("unknown trajectory: {}", name))?;
+        print!("{}", text);
+        return Ok(());
+    }
+
+    let frames = if let Some(frames) = matches.value_of("frames") {
+        frames.parse().map_err(|_| format!("illegal frame count: {}", frames))?
+    } else {
+        DEFAULT_FRAMES
+    };
+
+    if matches.is_present("gym") {
+        let mut config = GymConfig::default();
+        if matches.is_present("frames") {
+            config.max_frames = frames;
+        }
+        config.observation_type = match matches.value_of("observation") {
+            None | Some("vector") => ObservationType::Vector,
+            Some("framebuffer") => ObservationType::Framebuffer,
+            Some(other) => return Err(format!("illegal observation: {}", other)),
+        };
+        if let Some(frame_skip) = matches.value_of("frame-skip") {
+            config.frame_skip = frame_skip.parse().ok().filter(|&n| n >= 1)
+                .ok_or_else(|| format!("illegal frame skip: {}", frame_skip))?;
+        }
+        if let Some(filename) = matches.value_of("stage-pack") {
+            config.stage_pack = load_stage_pack(filename)?;
+        }
+        if let Some(name) = matches.value_of("difficulty") {
+            config.game_config = GameConfig::preset(parse_difficulty(name)?);
+        }
+        if let Some(ships) = matches.value_of("ships") {
+            config.game_config.starting_ships = parse_ships(ships)?;
+        }
+        return gym_server::serve(&mut GymEnv::new(config));
+    }
+    let mut script = if let Some(filename) = matches.value_of("input") {
+        InputScript::load(filename)?
+    } else {
+        InputScript::new(Vec::new())
+    };
+    let dump = matches.is_present("dump");
+
+    let mut renderer = if dump { HeadlessRenderer::new_recording() } else { HeadlessRenderer::new() };
+    let mut app = GalanguaApp::new(HeadlessTimer::new(), HeadlessSystem::new());
+    if let Some(seed) = matches.value_of("seed") {
+        app.set_seed(seed.parse().map_err(|_| format!("illegal seed: {}", seed))?);
+    }
+    if let Some(filename) = matches.value_of("stage-pack") {
+        app.set_stage_pack(load_stage_pack(filename)?);
+    }
+    if let Some(name) = matches.value_of("difficulty") {
+        app.set_difficulty(parse_difficulty(name)?);
+    }
+    if let Some(ships) = matches.value_of("ships") {
+        app.set_starting_ships(parse_ships(ships)?);
+    }
+    if let Some(filename) = matches.value_of("replay") {
+        app.start_replay(load_replay(filename)?);
+    }
+    if matches.is_present("bot") {
+        app.set_pilot(Some(Box::new(HeuristicPilot::new())));
+    }
+    let record = matches.value_of("record");
+    if record.is_some() {
+        app.start_recording();
+    }
+    if let Some(filename) = matches.value_of("load-state") {
+        let json = std::fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+        app.load_snapshot(&json).map_err(|e| format!("{}: {}", filename, e))?;
+    }
+
+    let mut capturer = if let Some(filename) = matches.value_of("capture") {
+        let mut capture = Capture::create(filename, WIDTH as u32, HEIGHT as u32)?;
+        if let Some(filename) = matches.value_of("capture-se") {
+            capture.set_se_timeline(filename);
+        }
+        Some(Capturer { renderer: SoftwareRenderer::new(WIDTH, HEIGHT), capture })
+    } else {
+        None
+    };
+
+    let count = run(&mut app, &mut renderer, &mut script, frames, capturer.as_mut())?;
+
+    if let Some(capturer) = capturer {
+        capturer.capture.finish()?;
+    }
+
+    if let Some(filename) = matches.value_of("save-state") {
+        let json = app.save_snapshot().ok_or_else(|| String::from("not in game, no state to save"))?;
+        std::fs::write(filename, json).map_err(|e| format!("{}: {}", filename, e))?;
+    }
+
+    if let Some(filename) = record {
+        if let Some(replay) = app.take_replay() {
+            save_replay(filename, &replay)?;
+        }
+    }
+
+    if let Some(filename) = matches.value_of("png") {
+        let mut renderer = SoftwareRenderer::new(WIDTH, HEIGHT);
+        AppTrait::<SoftwareRenderer>::init(&mut app, &mut renderer);
+        AppTrait::<SoftwareRenderer>::draw(&mut app, &mut renderer);
+        renderer.save_png(filename)?;
+    }
+
+    if dump {
+        for command in renderer.commands() {
+            println!("{:?}", command);
+        }
+    }
+    println!("seed: {}, frames: {}, score: {}, high score: {}",
+             app.seed(), count, app.score(), app.high_score());
+    if let Some(stats) = app.game_stats() {
+        println!("stage: {}, shots: {}, hits: {}, kills: {} (bee {}, butterfly {}, owl {}, fighter {}), captures: {}, rescues: {}",
+                 stats.stage_reached, stats.shots_fired, stats.hits, stats.total_kills(),
+                 stats.kills(EnemyType::Bee), stats.kills(EnemyType::Butterfly),
+                 stats.kills(EnemyType::Owl), stats.kills(EnemyType::CapturedFighter),
+                 stats.captures, stats.rescues);
+    }
+    Ok(())
+}
+
+// Draws frames by software instead, to capture them.
+struct Capturer {
+    renderer: SoftwareRenderer,
+    capture: Capture,
+}
+
+fn run(
+    app: &mut App, renderer: &mut HeadlessRenderer, script: &mut InputScript, frames: u32,
+    mut capturer: Option<&mut Capturer>,
+) -> Result<u32, String> {
+    AppTrait::<HeadlessRenderer>::init(app, renderer);
+    if let Some(capturer) = &mut capturer {
+        AppTrait::<SoftwareRenderer>::init(app, &mut capturer.renderer);
+    }
+
+    for frame in 0..frames {
+        for event in script.events_at(frame) {
+            AppTrait::<HeadlessRenderer>::on_key(app, event.vkey, event.down);
+        }
+        if !AppTrait::<HeadlessRenderer>::update(app) {
+            return Ok(frame);
+        }
+        if let Some(capturer) = &mut capturer {
+            let system = app.system_mut();
+            for (channel, filename) in system.played_se() {
+                capturer.capture.add_se(*channel, filename);
+            }
+            system.clear_played_se();
+
+            AppTrait::<SoftwareRenderer>::draw(app, &mut capturer.renderer);
+            capturer.capture.add_frame(capturer.renderer.pixels(), 1)?;
+        } else {
+            AppTrait::<HeadlessRenderer>::draw(app, renderer);
+        }
+    }
+    Ok(frames)
+}
+
+fn load_stage_pack(filename: &str) -> Result<StagePack, String> {
+    let text = std::fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+    StagePack::from_json(&text).map_err(|e| format!("{}: {}", filename, e))
+}
+
+fn parse_difficulty(name: &str) -> Result<Difficulty, String> {
+    Difficulty::from_name(name).ok_or_else(|| format!("illegal difficulty: {}", name))
+}
+
+fn parse_ships(ships: &str) -> Result<u32, String> {
+    ships.parse().map_err(|_| format!("illegal ships: {}", ships))
+}
+
+fn load_replay(filename: &str) -> Result<Replay, String> {
+    let bytes = std::fs::read(filename).map_err(|e| format!("{}: {}", filename, e))?;
+    Replay::from_bytes(&bytes).map_err(|e| format!("{}: {}", filename, e))
+}
+
+fn save_replay(filename: &str, replay: &Replay) -> Result<(), String> {
+    std::fs::write(filename, replay.to_bytes()).map_err(|e| format!("{}: {}", filename, e))
+}
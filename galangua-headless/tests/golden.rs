@@ -0,0 +1,141 @@
+// Renders frames of a fixed play on CPU, and compares them with PNG goldens in `tests/golden`.
+//
+// On mismatch, the actual frame and a diff image (differing pixels in red) are written
+// into the directory printed in the message.
+// Set `UPDATE_GOLDEN=1` to overwrite the goldens by the current rendering.
+
+use galangua_core::app::consts::{HEIGHT, WIDTH};
+use galangua_core::app::{GalanguaApp, GameConfig};
+use galangua_core::framework::{AppTrait, RendererTrait, VKey};
+use galangua_core::headless::{load_png, save_png, HeadlessRenderer, HeadlessSystem, HeadlessTimer, SoftwareRenderer};
+
+type App = GalanguaApp<HeadlessTimer, HeadlessSystem>;
+
+const SEED: u64 = 1;
+const START_FRAME: u32 = 4;
+const FIRE_FRAMES: std::ops::Range<u32> = 200..4330;
+
+// Held keys (from, to): dodges until a tractor beam takes the fighter.
+const MOVES: [(u32, u32, VKey); 29] = [
+    (371, 399, VKey::Left), (584, 624, VKey::Right), (634, 667, VKey::Right), (724, 750, VKey::Right),
+    (786, 827, VKey::Left), (862, 899, VKey::Left), (1029, 1037, VKey::Left), (1112, 1122, VKey::Right),
+    (1262, 1301, VKey::Right), (1351, 1400, VKey::Right), (1416, 1453, VKey::Right), (1673, 1719, VKey::Left),
+    (1739, 1794, VKey::Right), (1978, 2004, VKey::Right), (2171, 2177, VKey::Right), (2318, 2337, VKey::Left),
+    (2401, 2430, VKey::Left), (2466, 2519, VKey::Left), (2557, 2566, VKey::Right), (2611, 2643, VKey::Left),
+    (3006, 3021, VKey::Right), (3140, 3183, VKey::Right), (3300, 3320, VKey::Right), (3331, 3351, VKey::Left),
+    (3488, 3494, VKey::Right), (3615, 3670, VKey::Right), (3952, 3985, VKey::Right), (4032, 4054, VKey::Left),
+    (4273, 4321, VKey::Left),
+];
+
+// Frames to compare, in order.
+const SHOTS: [(&str, u32); 4] = [
+    ("title", 3),
+    ("stage1_start", 60),
+    ("capture", 4350),
+    ("game_over", 5630),
+];
+
+// A pixel differs if any channel differs more than this,
+// and a frame fails if more pixels than the limit differ.
+const CHANNEL_TOLERANCE: i32 = 16;
+const MAX_DIFF_PIXELS: usize = 64;
+
+fn key_events(frame: u32) -> Vec<(VKey, bool)> {
+    let mut events = Vec::new();
+    if frame == START_FRAME {
+        events.push((VKey::Space, true));
+    } else if frame == START_FRAME + 1 {
+        events.push((VKey::Space, false));
+    }
+    if FIRE_FRAMES.contains(&frame) {
+        match frame % 16 {
+            8 => events.push((VKey::Space, true)),
+            10 => events.push((VKey::Space, false)),
+            _ => {}
+        }
+    }
+    for &(from, to, vkey) in MOVES.iter() {
+        if frame == from {
+            events.push((vkey, true));
+        } else if frame == to {
+            events.push((vkey, false));
+        }
+    }
+    events
+}
+
+fn count_diff_pixels(expected: &[u8], actual: &[u8], diff: &mut Vec<u8>) -> usize {
+    let mut count = 0;
+    for (e, a) in expected.chunks(4).zip(actual.chunks(4)) {
+        let differs = e.iter().zip(a.iter()).any(|(&x, &y)| (x as i32 - y as i32).abs() > CHANNEL_TOLERANCE);
+        if differs {
+            count += 1;
+            diff.extend_from_slice(&[255, 0, 0, 255]);
+        } else {
+            diff.extend_from_slice(&[a[0] / 4, a[1] / 4, a[2] / 4, 255]);
+        }
+    }
+    count
+}
+
+// Returns an error message on mismatch.
+fn check_golden(name: &str, renderer: &SoftwareRenderer) -> Result<(), String> {
+    let golden_path = format!("{}/tests/golden/{}.png", env!("CARGO_MANIFEST_DIR"), name);
+    if std::env::var("UPDATE_GOLDEN").is_ok() {
+        return renderer.save_png(&golden_path);
+    }
+
+    let (width, height, expected) = load_png(&golden_path)?;
+    let mut diff = Vec::new();
+    let count = if (width as i32, height as i32) == (renderer.width(), renderer.height()) {
+        count_diff_pixels(&expected, renderer.pixels(), &mut diff)
+    } else {
+        usize::MAX
+    };
+    if count <= MAX_DIFF_PIXELS {
+        return Ok(());
+    }
+
+    let out_dir = format!("{}/golden", env!("CARGO_TARGET_TMPDIR"));
+    std::fs::create_dir_all(&out_dir).map_err(|e| format!("{}: {}", out_dir, e))?;
+    renderer.save_png(&format!("{}/{}.actual.png", out_dir, name))?;
+    if !diff.is_empty() {
+        save_png(&format!("{}/{}.diff.png", out_dir, name), width, height, &diff)?;
+    }
+    Err(format!("{}: {} pixels differ, see {}", name, count, out_dir))
+}
+
+#[test]
+fn test_golden_frames() {
+    let assets = format!("{}/../assets", env!("CARGO_MANIFEST_DIR"));
+    let mut software_renderer = SoftwareRenderer::new(WIDTH, HEIGHT);
+    software_renderer.load_textures(&assets, &["chr.png", "font.png"]);
+    software_renderer.load_sprite_sheet(&format!("{}/chr.json", assets));
+    let mut renderer = HeadlessRenderer::new();
+
+    let mut app = App::new(HeadlessTimer::new(), HeadlessSystem::new());
+    app.set_seed(SEED);
+    // The scripted play dodges enemies of the rank by the stage only.
+    app.set_game_config(Some(GameConfig { dynamic_rank: 0, ..GameConfig::default() }));
+    app.set_debug_overlay(false);
+    AppTrait::<HeadlessRenderer>::init(&mut app, &mut renderer);
+
+    let mut errors = Vec::new();
+    let last_frame = SHOTS[SHOTS.len() - 1].1;
+    for frame in 0..=last_frame {
+        for (vkey, down) in key_events(frame) {
+            AppTrait::<HeadlessRenderer>::on_key(&mut app, vkey, down);
+        }
+        assert!(AppTrait::<HeadlessRenderer>::update(&mut app));
+
+        if let Some((name, _)) = SHOTS.iter().find(|(_, f)| *f == frame) {
+            AppTrait::<SoftwareRenderer>::draw(&mut app, &mut software_renderer);
+            if let Err(e) = check_golden(name, &software_renderer) {
+                errors.push(e);
+            }
+        } else {
+            AppTrait::<HeadlessRenderer>::draw(&mut app, &mut renderer);
+        }
+    }
+    assert!(errors.is_empty(), "{}", errors.join("\n"));
+}
@@ -2,14 +2,19 @@ use wasm_bindgen::prelude::*;
 
 use galangua_core::app::GalanguaApp;
 use galangua_core::framework::{AppTrait, VKey};
+use galangua_core::util::replay::Replay;
 
 use super::wasm_renderer::WasmRenderer;
 use super::wasm_system::WasmSystem;
 use super::wasm_timer::WasmTimer;
 
+type AppTimer = WasmTimer<Box<dyn Fn() -> f64>>;
+type AppSystem = WasmSystem<Box<dyn Fn(&str) -> Option<JsValue>>, Box<dyn Fn(&str, JsValue)>>;
+type App = GalanguaApp<AppTimer, AppSystem>;
+
 #[wasm_bindgen]
 pub struct WasmAppFramework {
-    app: Box<dyn AppTrait<WasmRenderer>>,
+    app: App,
     renderer: WasmRenderer,
 }
 
@@ -21,7 +26,7 @@ impl WasmAppFramework {
     ) -> Self {
         web_sys::console::log_1(&"WasmAppFramework#new".into());
 
-        let timer = WasmTimer::new(move || {
+        let timer: AppTimer = WasmTimer::new(Box::new(move || {
             let this = JsValue::NULL;
             if let Ok(v) = get_now_fn.call0(&this) {
                 if let Some(t) = v.as_f64() {
@@ -29,30 +34,37 @@ impl WasmAppFramework {
                 }
             }
             0.0
-        });
-        let system = WasmSystem::new(
-            move |key| {
+        }));
+        let system: AppSystem = WasmSystem::new(
+            Box::new(move |key| {
                 let this = JsValue::NULL;
                 get_item_fn.call1(&this, &JsValue::from(key)).ok()
-            },
-            move |key, value| {
+            }),
+            Box::new(move |key, value| {
                 let this = JsValue::NULL;
                 set_item_fn.call2(&this, &JsValue::from(key), &JsValue::from(value)).unwrap();
-            },
+            }),
         );
         let mut app = GalanguaApp::new(timer, system);
 
-        app.init(&mut renderer);
+        AppTrait::<WasmRenderer>::init(&mut app, &mut renderer);
 
         Self {
-            app: Box::new(app),
+            app,
             renderer,
         }
     }
 
+    // Plays back replay file contents, e.g. from `fetch` or a file input.
+    pub fn load_replay(&mut self, data: &[u8]) -> Result<(), JsValue> {
+        let replay = Replay::from_bytes(data).map_err(JsValue::from)?;
+        self.app.start_replay(replay);
+        Ok(())
+    }
+
     pub fn on_key(&mut self, key_code: &str, down: bool) {
         if let Some(vkey) = to_vkey(key_code) {
-            self.app.on_key(vkey, down);
+            AppTrait::<WasmRenderer>::on_key(&mut self.app, vkey, down);
         }
     }
 
@@ -64,12 +76,12 @@ impl WasmAppFramework {
             _ => None,
         };
         if let Some(vkey) = vkey_opt {
-            self.app.on_key(vkey, down);
+            AppTrait::<WasmRenderer>::on_key(&mut self.app, vkey, down);
         }
     }
 
     pub fn update(&mut self) {
-        self.app.update();
+        AppTrait::<WasmRenderer>::update(&mut self.app);
     }
 
     pub fn draw(&mut self) {
@@ -77,7 +89,22 @@ impl WasmAppFramework {
     }
 }
 
+// `KeyboardEvent.code`, all keys which can be bound in the key config.
 fn to_vkey(key_code: &str) -> Option<VKey> {
+    const LETTERS: [VKey; 26] = [
+        VKey::A, VKey::B, VKey::C, VKey::D, VKey::E, VKey::F, VKey::G, VKey::H, VKey::I,
+        VKey::J, VKey::K, VKey::L, VKey::M, VKey::N, VKey::O, VKey::P, VKey::Q, VKey::R,
+        VKey::S, VKey::T, VKey::U, VKey::V, VKey::W, VKey::X, VKey::Y, VKey::Z,
+    ];
+    const DIGITS: [VKey; 10] = [
+        VKey::Num0, VKey::Num1, VKey::Num2, VKey::Num3, VKey::Num4,
+        VKey::Num5, VKey::Num6, VKey::Num7, VKey::Num8, VKey::Num9,
+    ];
+    const FUNCTIONS: [VKey; 12] = [
+        VKey::F1, VKey::F2, VKey::F3, VKey::F4, VKey::F5, VKey::F6,
+        VKey::F7, VKey::F8, VKey::F9, VKey::F10, VKey::F11, VKey::F12,
+    ];
+
     match key_code {
         "Space" => Some(VKey::Space),
         "Enter" => Some(VKey::Return),
@@ -86,6 +113,24 @@ fn to_vkey(key_code: &str) -> Option<VKey> {
         "ArrowRight" => Some(VKey::Right),
         "ArrowUp" => Some(VKey::Up),
         "ArrowDown" => Some(VKey::Down),
+        _ => {
+            if let Some(rest) = key_code.strip_prefix("Key") {
+                char_index(rest, b'A').and_then(|i| LETTERS.get(i)).copied()
+            } else if let Some(rest) = key_code.strip_prefix("Digit") {
+                char_index(rest, b'0').and_then(|i| DIGITS.get(i)).copied()
+            } else if let Some(rest) = key_code.strip_prefix('F') {
+                rest.parse::<usize>().ok().and_then(|n| FUNCTIONS.get(n.wrapping_sub(1))).copied()
+            } else {
+                None
+            }
+        }
+    }
+}
+
+// Index of a single character from `first`, e.g. "C" from b'A' is 2.
+fn char_index(s: &str, first: u8) -> Option<usize> {
+    match s.as_bytes() {
+        [c] if *c >= first => Some((c - first) as usize),
         _ => None,
     }
 }
@@ -1,10 +1,14 @@
 use wasm_bindgen::prelude::*;
 
+use galangua_core::app::consts::MAX_VOLUME;
 use galangua_core::framework::SystemTrait;
 
 #[wasm_bindgen]
 extern "C" {
     fn play_se(channel: u32, filename: &str);
+    fn set_volume(volume: f64);
+    fn set_window(scale: u32, fullscreen: bool);
+    fn pause_audio(paused: bool);
 }
 
 pub struct WasmSystem<F, G>
@@ -47,7 +51,27 @@ where
         (self.set_item)(key, JsValue::from(value));
     }
 
+    fn get_str(&self, key: &str) -> Option<String> {
+        (self.get_item)(key).and_then(|value| value.as_string())
+    }
+
+    fn set_str(&mut self, key: &str, value: &str) {
+        (self.set_item)(key, JsValue::from(value));
+    }
+
     fn play_se(&mut self, channel: u32, filename: &str) {
         play_se(channel, filename);
     }
+
+    fn set_volume(&mut self, volume: u32) {
+        set_volume(volume as f64 / MAX_VOLUME as f64);
+    }
+
+    fn set_window(&mut self, scale: u32, fullscreen: bool) {
+        set_window(scale, fullscreen);
+    }
+
+    fn pause_audio(&mut self, paused: bool) {
+        pause_audio(paused);
+    }
 }
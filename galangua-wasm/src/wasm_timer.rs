@@ -36,4 +36,8 @@ where
         self.last_time += SEC;
         true
     }
+
+    fn reset(&mut self) {
+        self.last_time = (self.get_now)() as u64;
+    }
 }
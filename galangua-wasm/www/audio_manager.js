@@ -2,15 +2,37 @@ class AudioManager {
   constructor(channelCount) {
     this.audios = {}
     this.audioLoadings = {}
+    this.volume = 1
   }
 
   createContext(channelCount) {
     let audioContext = window.AudioContext || window.webkitAudioContext
     this.context = new audioContext()
+    this.gain = this.context.createGain()
+    this.gain.gain.value = this.volume
+    this.gain.connect(this.context.destination)
 
     this.channels = new Array(channelCount)
   }
 
+  // Can be set before the context is created.
+  setVolume(volume) {
+    this.volume = volume
+    if (this.gain != null) {
+      this.gain.gain.value = volume
+    }
+  }
+
+  pause(paused) {
+    if (this.context != null) {
+      if (paused) {
+        this.context.suspend()
+      } else {
+        this.context.resume()
+      }
+    }
+  }
+
   playSe(channel, filename) {
     if (filename in this.audios) {
      if (channel < this.channels.length) {
@@ -19,7 +41,7 @@ class AudioManager {
         }
 
         const source = this.context.createBufferSource()
-        source.connect(this.context.destination)
+        source.connect(this.gain)
         this.channels[channel] = source
 
         source.buffer = this.audios[filename]
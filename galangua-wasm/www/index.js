@@ -13,19 +13,34 @@ const ENALBE_AUDIO = 'assets/audio/se_get_1'
 
 const CANVAS_ID = 'mycanvas'
 
+// Maximum scale of the canvas set in the options, fit into the window.
+let canvasScale = 3
+
 window.play_se = function play_se(channel, filename) {
   audioManager.playSe(channel, filename)
 }
 
+window.set_volume = function set_volume(volume) {
+  audioManager.setVolume(volume)
+}
+
+// Fullscreen needs a user gesture in browsers, so it is left to the browser.
+window.set_window = function set_window(scale, _fullscreen) {
+  canvasScale = scale
+  fitCanvas()
+}
+
+window.pause_audio = function pause_audio(paused) {
+  audioManager.pause(paused)
+}
+
 function fitCanvas() {
   const canvas = document.getElementById(CANVAS_ID)
-  if (canvas.width / canvas.height >= window.innerWidth / window.innerHeight) {
-    canvas.style.width = `100%`
-    canvas.style.height = `${canvas.height * window.innerWidth / canvas.width}px`
-  } else {
-    canvas.style.height = `100%`
-    canvas.style.width = `${canvas.width * window.innerHeight / canvas.height}px`
-  }
+  const scale = Math.min(canvasScale,
+                         window.innerWidth / canvas.width,
+                         window.innerHeight / canvas.height)
+  canvas.style.width = `${canvas.width * scale}px`
+  canvas.style.height = `${canvas.height * scale}px`
 }
 
 function disableBounce() {
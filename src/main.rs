@@ -5,14 +5,19 @@ mod std_system;
 use counted_array::counted_array;
 use lazy_static::lazy_static;
 use sdl2::keyboard::Keycode;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
+use std::rc::Rc;
 
 use galangua_core::app::consts;
-use galangua_core::app::GalanguaApp;
+use galangua_core::app::{Difficulty, GalanguaApp, StagePack};
 use galangua_core::framework::VKey;
+use galangua_core::util::capture::Capture;
+use galangua_core::util::replay::Replay;
 
 use crate::sdl::SdlAppFramework;
 use crate::sdl::SdlAudio;
+use crate::sdl::WindowMode;
 use crate::std_timer::StdTimer;
 use crate::std_system::StdSystem;
 
@@ -24,32 +29,140 @@ pub fn main() -> Result<(), String> {
         .about("2D shoot'em up game, writen in Rust.
   Move the fighter : Arrow keys (left or right)
   Shoot a bullet   : Space bar
-  Quit the app     : Escape key")
+  Take screenshot  : F12 key
+  Pause (in game)  : Escape key
+  Quit the app     : Escape key (title screen)")
         .arg(clap::Arg::with_name("full")
              .help("Use fullscreen")
              .short("f")
              .long("fullscreen"))
         .arg(clap::Arg::with_name("scale")
-             .help("Specify window scale (default: 3)")
+             .help("Specify window scale (default: 3, or set in the options)")
              .short("s")
              .long("scale")
              .takes_value(true))
+        .arg(clap::Arg::with_name("seed")
+             .help("Specify random seed, same seed and input reproduce the same game")
+             .long("seed")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("stage-pack")
+             .help("Load stage definitions from a JSON file")
+             .long("stage-pack")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("difficulty")
+             .help("Game difficulty: easy, normal, hard or arcade (default: the settings)")
+             .long("difficulty")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("ships")
+             .help("Number of fighters to start with, 1-5 (default: the settings)")
+             .long("ships")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("record")
+             .help("Record input into a replay file")
+             .long("record")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("replay")
+             .help("Play back a replay file")
+             .long("replay")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("capture")
+             .help("Capture frames into an animated GIF (*.gif) or a PNG sequence (*.png)")
+             .long("capture")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("capture-se")
+             .help("Write a timeline of sound effects in the capture, `frame seconds channel file` per line")
+             .long("capture-se")
+             .takes_value(true)
+             .requires("capture"))
+        .arg(clap::Arg::with_name("screenshot-dir")
+             .help("Directory to save screenshots by F12 key (default: screenshots)")
+             .long("screenshot-dir")
+             .takes_value(true))
         .get_matches();
 
-    let fullscreen = matches.is_present("full");
-    let scale = if let Some(scale) = matches.value_of("scale") {
-        String::from(scale).parse().unwrap()
+    let timer = StdTimer::new();
+    let audio = SdlAudio::new(consts::CHANNEL_COUNT, consts::BASE_VOLUME);
+    let mut system = StdSystem::new(audio);
+    let window_mode = Rc::new(Cell::new(WindowMode { scale: 0, fullscreen: false }));
+    system.set_window_mode(window_mode.clone());
+    let capture = if let Some(filename) = matches.value_of("capture") {
+        let mut capture = Capture::create(filename, consts::WIDTH as u32, consts::HEIGHT as u32)?;
+        if let Some(filename) = matches.value_of("capture-se") {
+            capture.set_se_timeline(filename);
+        }
+        let capture = Rc::new(RefCell::new(capture));
+        system.set_capture(capture.clone());
+        Some(capture)
     } else {
-        3
+        None
     };
+    let mut app = GalanguaApp::new(timer, system);
+    if matches.is_present("scale") || matches.is_present("full") {
+        let scale = match matches.value_of("scale") {
+            Some(scale) => scale.parse().map_err(|_| format!("illegal scale: {}", scale))?,
+            None => app.settings().scale,
+        };
+        app.set_window(scale, matches.is_present("full") || app.settings().fullscreen);
+    }
+    if let Some(seed) = matches.value_of("seed") {
+        app.set_seed(seed.parse().map_err(|_| format!("illegal seed: {}", seed))?);
+    }
+    if let Some(filename) = matches.value_of("stage-pack") {
+        app.set_stage_pack(load_stage_pack(filename)?);
+    }
+    if let Some(name) = matches.value_of("difficulty") {
+        app.set_difficulty(Difficulty::from_name(name).ok_or_else(|| format!("illegal difficulty: {}", name))?);
+    }
+    if let Some(ships) = matches.value_of("ships") {
+        app.set_starting_ships(ships.parse().map_err(|_| format!("illegal ships: {}", ships))?);
+    }
+    if let Some(filename) = matches.value_of("replay") {
+        app.start_replay(load_replay(filename)?);
+    }
+    let record = matches.value_of("record");
+    if record.is_some() {
+        app.start_recording();
+    }
 
-    let timer = StdTimer::new();
-    let audio = SdlAudio::new(consts::CHANNEL_COUNT, consts::BASE_VOLUME);
-    let system = StdSystem::new(audio);
-    let app = GalanguaApp::new(timer, system);
     let mut framework = SdlAppFramework::new(app, map_key)?;
-    framework.run(APP_NAME,
-                  consts::WIDTH as u32, consts::HEIGHT as u32, scale, fullscreen)
+    if let Some(capture) = &capture {
+        framework.set_capture(capture.clone());
+    }
+    // Told by the app, from the settings or the command line.
+    let WindowMode { scale, fullscreen } = window_mode.get();
+    framework.set_window_mode(window_mode);
+    if let Some(dir) = matches.value_of("screenshot-dir") {
+        framework.set_screenshot_dir(dir);
+    }
+    let result = framework.run(APP_NAME,
+                               consts::WIDTH as u32, consts::HEIGHT as u32, scale, fullscreen);
+
+    if let Some(filename) = record {
+        if let Some(replay) = framework.app_mut().take_replay() {
+            save_replay(filename, &replay)?;
+        }
+    }
+
+    if let Some(capture) = capture {
+        drop(framework);  // Releases the capture shared with the framework and the system.
+        let capture = Rc::try_unwrap(capture).map_err(|_| String::from("capture still in use"))?;
+        capture.into_inner().finish()?;
+    }
+    result
+}
+
+fn load_stage_pack(filename: &str) -> Result<StagePack, String> {
+    let text = std::fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+    StagePack::from_json(&text).map_err(|e| format!("{}: {}", filename, e))
+}
+
+fn load_replay(filename: &str) -> Result<Replay, String> {
+    let bytes = std::fs::read(filename).map_err(|e| format!("{}: {}", filename, e))?;
+    Replay::from_bytes(&bytes).map_err(|e| format!("{}: {}", filename, e))
+}
+
+fn save_replay(filename: &str, replay: &Replay) -> Result<(), String> {
+    std::fs::write(filename, replay.to_bytes()).map_err(|e| format!("{}: {}", filename, e))
 }
 
 counted_array!(const KEY_MAP_TABLE: [(Keycode, VKey); _] = [
@@ -73,6 +186,11 @@ counted_array!(const KEY_MAP_TABLE: [(Keycode, VKey); _] = [
     (Keycode::Num3, VKey::Num3), (Keycode::Num4, VKey::Num4), (Keycode::Num5, VKey::Num5),
     (Keycode::Num6, VKey::Num6), (Keycode::Num7, VKey::Num7), (Keycode::Num8, VKey::Num8),
     (Keycode::Num9, VKey::Num9),
+
+    (Keycode::F1, VKey::F1), (Keycode::F2, VKey::F2), (Keycode::F3, VKey::F3),
+    (Keycode::F4, VKey::F4), (Keycode::F5, VKey::F5), (Keycode::F6, VKey::F6),
+    (Keycode::F7, VKey::F7), (Keycode::F8, VKey::F8), (Keycode::F9, VKey::F9),
+    (Keycode::F10, VKey::F10), (Keycode::F11, VKey::F11), (Keycode::F12, VKey::F12),
 ]);
 
 lazy_static! {
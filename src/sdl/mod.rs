@@ -4,5 +4,5 @@ mod sdl_audio;
 mod sdl_renderer;
 mod sdl_texture_manager;
 
-pub use self::sdl_app_framework::SdlAppFramework;
+pub use self::sdl_app_framework::{SdlAppFramework, WindowMode};
 pub use self::sdl_audio::SdlAudio;
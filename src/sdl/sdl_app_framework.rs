@@ -1,13 +1,19 @@
+use sdl2::controller::{Axis, Button, GameController};
 use sdl2::event::Event;
 use sdl2::image::InitFlag;
-use sdl2::joystick::Joystick;
+use sdl2::joystick::{HatState, Joystick};
 use sdl2::keyboard::Keycode;
 use sdl2::mixer::{DEFAULT_CHANNELS, AUDIO_S16LSB};
-use sdl2::Sdl;
+use sdl2::{GameControllerSubsystem, JoystickSubsystem, Sdl};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
 use std::thread;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use galangua_core::framework::{AppTrait, VKey};
+use galangua_core::headless::save_png;
+use galangua_core::util::capture::Capture;
 
 use crate::sdl::sdl_renderer::SdlRenderer;
 
@@ -16,12 +22,32 @@ type MapKeyFunc = fn(Keycode) -> Option<VKey>;
 const FPS: u32 = 60;
 const MIN_FPS: u32 = 15;
 
+const SCREENSHOT_KEY: Keycode = Keycode::F12;
+
+// Window requested by the system from the settings.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WindowMode {
+    pub scale: u32,
+    pub fullscreen: bool,
+}
+const DEFAULT_SCREENSHOT_DIR: &str = "screenshots";
+
 pub struct SdlAppFramework<App: AppTrait<SdlRenderer>> {
     sdl_context: Sdl,
     last_update_time: SystemTime,
 
+    // Devices are opened and closed while running, keyed by instance id.
+    joystick_subsystem: Option<JoystickSubsystem>,
+    controller_subsystem: Option<GameControllerSubsystem>,
+    joysticks: HashMap<u32, Joystick>,
+    controllers: HashMap<u32, GameController>,
+
     app: App,
     map_key: MapKeyFunc,
+    capture: Option<Rc<RefCell<Capture>>>,
+    window_mode: Option<Rc<Cell<WindowMode>>>,
+    screenshot_dir: String,
+    screenshot_requested: bool,
 
     #[cfg(debug_assertions)]
     fast_forward: bool,
@@ -34,19 +60,47 @@ impl<App: AppTrait<SdlRenderer>> SdlAppFramework<App> {
         Ok(Self {
             sdl_context,
             last_update_time: SystemTime::now(),
+            joystick_subsystem: None,
+            controller_subsystem: None,
+            joysticks: HashMap::new(),
+            controllers: HashMap::new(),
             app,
             map_key,
+            capture: None,
+            window_mode: None,
+            screenshot_dir: String::from(DEFAULT_SCREENSHOT_DIR),
+            screenshot_requested: false,
 
             #[cfg(debug_assertions)]
             fast_forward: false,
         })
     }
 
+    pub fn app_mut(&mut self) -> &mut App {
+        &mut self.app
+    }
+
+    // Captures each frame presented, shared with the system to record sound effects.
+    pub fn set_capture(&mut self, capture: Rc<RefCell<Capture>>) {
+        self.capture = Some(capture);
+    }
+
+    // Resizes the window when the mode is changed, e.g. by the system from the settings.
+    pub fn set_window_mode(&mut self, window_mode: Rc<Cell<WindowMode>>) {
+        self.window_mode = Some(window_mode);
+    }
+
+    pub fn set_screenshot_dir(&mut self, dir: &str) {
+        self.screenshot_dir = String::from(dir);
+    }
+
     pub fn run(&mut self, title: &str, width: u32, height: u32, scale: u32, fullscreen: bool) -> Result<(), String> {
         let video_subsystem = self.sdl_context.video()?;
         let _image_context = sdl2::image::init(InitFlag::PNG | InitFlag::JPG)?;
 
-        let _joystick = self.set_up_joystick()?;
+        // Devices connected at startup are notified by `JoyDeviceAdded` as well.
+        self.joystick_subsystem = Some(self.sdl_context.joystick()?);
+        self.controller_subsystem = Some(self.sdl_context.game_controller()?);
 
         let mut window_builder = video_subsystem
             .window(title, width * scale, height * scale);
@@ -92,6 +146,7 @@ impl<App: AppTrait<SdlRenderer>> SdlAppFramework<App> {
 
         self.last_update_time = SystemTime::now();
         let mut skip_count = 0;
+        let mut current_mode = WindowMode { scale, fullscreen };
         'running: loop {
             if !self.pump_events()? {
                 break 'running;
@@ -107,7 +162,25 @@ impl<App: AppTrait<SdlRenderer>> SdlAppFramework<App> {
                     break 'running;
                 }
             }
+            if let Some(window_mode) = &self.window_mode {
+                let mode = window_mode.get();
+                if mode != current_mode {
+                    renderer.set_window_mode(width * mode.scale, height * mode.scale, mode.fullscreen)?;
+                    self.sdl_context.mouse().show_cursor(!mode.fullscreen);
+                    current_mode = mode;
+                }
+            }
             self.app.draw(&mut renderer);
+            if let Some(capture) = &self.capture {
+                capture.borrow_mut().add_frame(&renderer.read_pixels()?, step)?;
+            }
+            if self.screenshot_requested {
+                self.screenshot_requested = false;
+                match self.save_screenshot(&renderer, width, height) {
+                    Ok(filenames) => println!("Screenshot saved: {}", filenames.join(", ")),
+                    Err(e) => eprintln!("Screenshot failed: {}", e),
+                }
+            }
             renderer.present();
 
             skip_count = self.wait_frame(Duration::from_micros(1_000_000 / FPS as u64));
@@ -122,7 +195,10 @@ impl<App: AppTrait<SdlRenderer>> SdlAppFramework<App> {
                 Event::Quit { .. } => {
                     return Ok(false);
                 }
-                Event::KeyDown { keycode: Some(key), .. } => {
+                Event::KeyDown { keycode: Some(key), repeat, .. } => {
+                    if key == SCREENSHOT_KEY && !repeat {
+                        self.screenshot_requested = true;
+                    }
                     #[cfg(debug_assertions)]
                     if key == Keycode::LShift {
                         self.fast_forward = true;
@@ -140,16 +216,35 @@ impl<App: AppTrait<SdlRenderer>> SdlAppFramework<App> {
                         self.app.on_key(vkey, false);
                     }
                 }
-                Event::JoyAxisMotion { axis_idx, value: val, .. } => {
-                    let dir = if val > 10_000 { 1 } else if val < -10_000 { -1 } else { 0 };
-                    self.app.on_joystick_axis(axis_idx, dir);
+                Event::JoyDeviceAdded { which, .. } => {
+                    self.open_device(which);
                 }
-                Event::JoyButtonDown { button_idx, .. } => {
+                Event::JoyDeviceRemoved { which, .. } => {
+                    self.close_device(which);
+                }
+                // Game controllers also send joystick events, which are ignored for the mapped ones.
+                Event::JoyAxisMotion { which, axis_idx, value, .. } if !self.controllers.contains_key(&which) => {
+                    self.app.on_joystick_axis(axis_idx, value);
+                }
+                Event::JoyHatMotion { which, hat_idx, state, .. } if !self.controllers.contains_key(&which) => {
+                    let (x, y) = hat_dir(state);
+                    self.app.on_joystick_hat(hat_idx, x, y);
+                }
+                Event::JoyButtonDown { which, button_idx, .. } if !self.controllers.contains_key(&which) => {
                     self.app.on_joystick_button(button_idx, true);
                 }
-                Event::JoyButtonUp { button_idx, .. } => {
+                Event::JoyButtonUp { which, button_idx, .. } if !self.controllers.contains_key(&which) => {
                     self.app.on_joystick_button(button_idx, false);
                 }
+                Event::ControllerAxisMotion { axis, value, .. } => {
+                    self.app.on_joystick_axis(axis as u8, value);
+                }
+                Event::ControllerButtonDown { which, button, .. } => {
+                    self.on_controller_button(which, button, true);
+                }
+                Event::ControllerButtonUp { which, button, .. } => {
+                    self.on_controller_button(which, button, false);
+                }
                 _ => {}
             }
         }
@@ -177,15 +272,172 @@ impl<App: AppTrait<SdlRenderer>> SdlAppFramework<App> {
         }
     }
 
-    fn set_up_joystick(&mut self) -> Result<Option<Joystick>, String> {
-        let joystick_subsystem = self.sdl_context.joystick()?;
-        let available = joystick_subsystem
-            .num_joysticks()
-            .map_err(|e| format!("can't enumerate joysticks: {}", e))?;
-        let joystick = (0..available).find_map(|id| match joystick_subsystem.open(id) {
-            Ok(c) => Some(c),
-            Err(_e) => None,
-        });
-        Ok(joystick)
+    // Saves the screen at the logical size and at window scale, and returns the filenames.
+    fn save_screenshot(&self, renderer: &SdlRenderer, width: u32, height: u32) -> Result<Vec<String>, String> {
+        std::fs::create_dir_all(&self.screenshot_dir)
+            .map_err(|e| format!("{}: {}", self.screenshot_dir, e))?;
+        let base = format!("{}/galangua-{}", self.screenshot_dir, timestamp());
+
+        let logical = format!("{}.png", base);
+        save_png(&logical, width, height, &renderer.read_pixels()?)?;
+
+        let (window_width, window_height, pixels) = renderer.read_window_pixels()?;
+        let window = format!("{}-{}x{}.png", base, window_width, window_height);
+        save_png(&window, window_width, window_height, &pixels)?;
+        Ok(vec![logical, window])
+    }
+
+    // Opens a device by joystick index, as a game controller if SDL has its mapping.
+    fn open_device(&mut self, index: u32) {
+        let (joystick_subsystem, controller_subsystem) = match (&self.joystick_subsystem, &self.controller_subsystem) {
+            (Some(j), Some(c)) => (j, c),
+            _ => return,
+        };
+        if controller_subsystem.is_game_controller(index) {
+            match controller_subsystem.open(index) {
+                Ok(controller) => {
+                    let id = controller.instance_id();
+                    self.joysticks.remove(&id);
+                    self.controllers.entry(id).or_insert(controller);
+                }
+                Err(e) => eprintln!("Can't open game controller {}: {}", index, e),
+            }
+        } else {
+            match joystick_subsystem.open(index) {
+                Ok(joystick) => {
+                    let id = joystick.instance_id();
+                    if !self.controllers.contains_key(&id) {
+                        self.joysticks.entry(id).or_insert(joystick);
+                    }
+                }
+                Err(e) => eprintln!("Can't open joystick {}: {}", index, e),
+            }
+        }
+    }
+
+    // Releases inputs of the device, not to keep moving or firing by ones held on unplugging.
+    fn close_device(&mut self, which: u32) {
+        let counts = if let Some(joystick) = self.joysticks.remove(&which) {
+            (joystick.num_axes(), joystick.num_hats(), joystick.num_buttons())
+        } else if self.controllers.remove(&which).is_some() {
+            (Axis::TriggerRight as u32 + 1, 1, Button::DPadRight as u32 + 1)
+        } else {
+            return;
+        };
+        release_inputs(&mut self.app, counts);
+    }
+
+    // Buttons are passed by the standard layout (A, B, X, Y = 0-3, ...), and the d-pad as hat 0.
+    fn on_controller_button(&mut self, which: u32, button: Button, down: bool) {
+        match button {
+            Button::DPadUp | Button::DPadDown | Button::DPadLeft | Button::DPadRight => {
+                if let Some(controller) = self.controllers.get(&which) {
+                    let dir = |minus, plus| controller.button(plus) as i8 - controller.button(minus) as i8;
+                    let x = dir(Button::DPadLeft, Button::DPadRight);
+                    let y = dir(Button::DPadUp, Button::DPadDown);
+                    self.app.on_joystick_hat(0, x, y);
+                }
+            }
+            _ => self.app.on_joystick_button(button as u8, down),
+        }
+    }
+}
+
+// Neutral events for (axes, hats, buttons) of a device.
+fn release_inputs(app: &mut impl AppTrait<SdlRenderer>, (axes, hats, buttons): (u32, u32, u32)) {
+    for index in 0..axes.min(256) {
+        app.on_joystick_axis(index as u8, 0);
+    }
+    for index in 0..hats.min(256) {
+        app.on_joystick_hat(index as u8, 0, 0);
+    }
+    for index in 0..buttons.min(256) {
+        app.on_joystick_button(index as u8, false);
+    }
+}
+
+fn hat_dir(state: HatState) -> (i8, i8) {
+    match state {
+        HatState::Centered => (0, 0),
+        HatState::Up => (0, -1),
+        HatState::Right => (1, 0),
+        HatState::Down => (0, 1),
+        HatState::Left => (-1, 0),
+        HatState::RightUp => (1, -1),
+        HatState::RightDown => (1, 1),
+        HatState::LeftUp => (-1, -1),
+        HatState::LeftDown => (-1, 1),
+    }
+}
+
+// Current time in UTC, e.g. `20201231-235959-999`.
+fn timestamp() -> String {
+    let elapsed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = elapsed.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let time = secs % 86_400;
+    format!("{:04}{:02}{:02}-{:02}{:02}{:02}-{:03}",
+            year, month, day, time / 3600, time / 60 % 60, time % 60, elapsed.subsec_millis())
+}
+
+// Days since 1970-01-01 to (year, month, day), in the proleptic Gregorian calendar.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Keeps the joystick state the app is told.
+    #[derive(Default)]
+    struct JoyApp {
+        axes: HashMap<u8, i16>,
+        hats: HashMap<u8, (i8, i8)>,
+        buttons: HashMap<u8, bool>,
+    }
+
+    impl AppTrait<SdlRenderer> for JoyApp {
+        fn init(&mut self, _renderer: &mut SdlRenderer) {}
+        fn update(&mut self) -> bool { true }
+        fn draw(&mut self, _renderer: &mut SdlRenderer) {}
+        fn on_key(&mut self, _keycode: VKey, _down: bool) {}
+        fn on_joystick_axis(&mut self, axis_index: u8, value: i16) {
+            self.axes.insert(axis_index, value);
+        }
+        fn on_joystick_hat(&mut self, hat_index: u8, x: i8, y: i8) {
+            self.hats.insert(hat_index, (x, y));
+        }
+        fn on_joystick_button(&mut self, button_index: u8, down: bool) {
+            self.buttons.insert(button_index, down);
+        }
+    }
+
+    #[test]
+    fn test_release_inputs() {
+        let mut app = JoyApp::default();
+        app.on_joystick_axis(1, -20_000);
+        app.on_joystick_hat(0, 1, 0);
+        app.on_joystick_button(3, true);
+
+        release_inputs(&mut app, (2, 1, 4));
+        assert_eq!(vec![(0, 0), (1, 0)], sorted(&app.axes));
+        assert_eq!(vec![(0, (0, 0))], sorted(&app.hats));
+        assert_eq!(vec![(0, false), (1, false), (2, false), (3, false)], sorted(&app.buttons));
+    }
+
+    fn sorted<T: Copy>(map: &HashMap<u8, T>) -> Vec<(u8, T)> {
+        let mut vec: Vec<(u8, T)> = map.iter().map(|(&k, &v)| (k, v)).collect();
+        vec.sort_by_key(|&(k, _)| k);
+        vec
     }
 }
@@ -1,8 +1,11 @@
 use sdl2::mixer::{Music, MAX_VOLUME};
 
+use galangua_core::app::consts;
+
 pub struct SdlAudio {
     channels: Vec<Option<Music<'static>>>,
     base_volume: i32,
+    volume: i32,
 }
 
 impl SdlAudio {
@@ -13,6 +16,21 @@ impl SdlAudio {
         Self {
             channels,
             base_volume: (MAX_VOLUME as f32 * base_volume) as i32,
+            volume: (MAX_VOLUME as f32 * base_volume) as i32,
+        }
+    }
+
+    // `volume` is in 0..=consts::MAX_VOLUME, multiplied to the base volume.
+    pub fn set_volume(&mut self, volume: u32) {
+        self.volume = self.base_volume * volume as i32 / consts::MAX_VOLUME as i32;
+        Music::set_volume(self.volume);
+    }
+
+    pub fn pause(&mut self, paused: bool) {
+        if paused {
+            Music::pause();
+        } else {
+            Music::resume();
         }
     }
 
@@ -21,7 +39,7 @@ impl SdlAudio {
             let path = format!("{}.ogg", filename);
             let music = Music::from_file(path)
                 .expect("play_se: No music flile");
-            Music::set_volume(self.base_volume);
+            Music::set_volume(self.volume);
             music.play(1)
                 .expect("Play music failed");
             self.channels[channel as usize] = Some(music);
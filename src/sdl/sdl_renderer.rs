@@ -1,6 +1,7 @@
-use sdl2::pixels::Color;
+use sdl2::pixels::{Color, PixelFormatEnum};
 use sdl2::rect::{Point, Rect};
 use sdl2::render::WindowCanvas;
+use sdl2::video::FullscreenType;
 use std::collections::HashMap;
 
 use galangua_core::framework::sprite_sheet::{load_sprite_sheet, SpriteSheet};
@@ -30,6 +31,46 @@ impl SdlRenderer {
     pub fn present(&mut self) {
         self.canvas.present();
     }
+
+    // Fullscreen on the desktop resolution, or a window of the size.
+    pub fn set_window_mode(&mut self, width: u32, height: u32, fullscreen: bool) -> Result<(), String> {
+        let window = self.canvas.window_mut();
+        if fullscreen {
+            window.set_fullscreen(FullscreenType::Desktop)
+        } else {
+            window.set_fullscreen(FullscreenType::Off)?;
+            window.set_size(width, height).map_err(|e| e.to_string())
+        }
+    }
+
+    // Reads the screen before `present` at window scale, as (width, height, RGBA).
+    pub fn read_window_pixels(&self) -> Result<(u32, u32, Vec<u8>), String> {
+        let viewport = self.canvas.viewport();
+        let (scale_x, scale_y) = self.canvas.scale();
+        let src = Rect::new((viewport.x() as f32 * scale_x) as i32,
+                            (viewport.y() as f32 * scale_y) as i32,
+                            (viewport.width() as f32 * scale_x) as u32,
+                            (viewport.height() as f32 * scale_y) as u32);
+        let pixels = self.canvas.read_pixels(Some(src), PixelFormatEnum::RGBA32)?;
+        Ok((src.width(), src.height(), pixels))
+    }
+
+    // Same as above, scaled down into the logical size.
+    pub fn read_pixels(&self) -> Result<Vec<u8>, String> {
+        let (width, height) = self.canvas.logical_size();
+        let (src_width, src_height, pixels) = self.read_window_pixels()?;
+
+        let mut rgba = Vec::with_capacity((width * height * 4) as usize);
+        for y in 0..height {
+            let sy = (y * src_height / height) as usize;
+            for x in 0..width {
+                let sx = (x * src_width / width) as usize;
+                let i = (sy * src_width as usize + sx) * 4;
+                rgba.extend_from_slice(&pixels[i..i + 4]);
+            }
+        }
+        Ok(rgba)
+    }
 }
 
 impl RendererTrait for SdlRenderer {
@@ -1,24 +1,66 @@
 use serde_json::Value;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
-use super::sdl::SdlAudio;
+use super::sdl::{SdlAudio, WindowMode};
 
 use galangua_core::framework::SystemTrait;
+use galangua_core::util::capture::Capture;
 
-const SAVE_FILE_NAME: &str = ".savedata.json";
+const APP_DIR_NAME: &str = "galangua";
+const SAVE_FILE_NAME: &str = "savedata.json";
+// Saved in the current directory by older versions, read if no save file yet.
+const LEGACY_SAVE_FILE_NAME: &str = ".savedata.json";
 
 pub struct StdSystem {
     map: HashMap<String, Value>,
+    save_path: PathBuf,
     audio: SdlAudio,
+    capture: Option<Rc<RefCell<Capture>>>,
+    window_mode: Option<Rc<Cell<WindowMode>>>,
 }
 
 impl StdSystem {
     pub fn new(audio: SdlAudio) -> Self {
+        let save_path = config_dir()
+            .map(|dir| dir.join(SAVE_FILE_NAME))
+            .unwrap_or_else(|| PathBuf::from(LEGACY_SAVE_FILE_NAME));
+        let map = if save_path.exists() {
+            load_map(&save_path)
+        } else {
+            load_map(Path::new(LEGACY_SAVE_FILE_NAME))
+        };
+
         StdSystem {
-            map: load_map(SAVE_FILE_NAME),
+            map,
+            save_path,
             audio,
+            capture: None,
+            window_mode: None,
+        }
+    }
+
+    // Records sound effects into the timeline of the capture.
+    pub fn set_capture(&mut self, capture: Rc<RefCell<Capture>>) {
+        self.capture = Some(capture);
+    }
+
+    // Window mode to be applied by the framework.
+    pub fn set_window_mode(&mut self, window_mode: Rc<Cell<WindowMode>>) {
+        self.window_mode = Some(window_mode);
+    }
+
+    // Saves only when changed.
+    fn insert(&mut self, key: &str, value: Value) {
+        if self.map.get(key) != Some(&value) {
+            self.map.insert(String::from(key), value);
+            if let Err(e) = save_map(&self.save_path, &self.map) {
+                eprintln!("{}", e);
+            }
         }
     }
 }
@@ -32,44 +74,90 @@ impl SystemTrait for StdSystem {
     }
 
     fn set_u32(&mut self, key: &str, value: u32) {
-        self.map.insert(String::from(key), Value::Number(serde_json::Number::from(value)));
-        save_map(SAVE_FILE_NAME, &self.map);
+        self.insert(key, Value::Number(serde_json::Number::from(value)));
+    }
+
+    fn get_str(&self, key: &str) -> Option<String> {
+        if let Some(Value::String(string)) = self.map.get(key) {
+            return Some(string.clone());
+        }
+        None
+    }
+
+    fn set_str(&mut self, key: &str, value: &str) {
+        self.insert(key, Value::String(String::from(value)));
     }
 
     fn play_se(&mut self, channel: u32, filename: &str) {
         self.audio.play_se(channel, filename);
+        if let Some(capture) = &self.capture {
+            capture.borrow_mut().add_se(channel, filename);
+        }
+    }
+
+    fn set_volume(&mut self, volume: u32) {
+        self.audio.set_volume(volume);
+    }
+
+    fn set_window(&mut self, scale: u32, fullscreen: bool) {
+        if let Some(window_mode) = &self.window_mode {
+            window_mode.set(WindowMode { scale, fullscreen });
+        }
     }
+
+    fn pause_audio(&mut self, paused: bool) {
+        self.audio.pause(paused);
+    }
+}
+
+// `$XDG_CONFIG_HOME/galangua` (`~/.config/galangua` by default) on Linux,
+// `%APPDATA%\galangua` on Windows, and `~/Library/Application Support/galangua` on macOS.
+fn config_dir() -> Option<PathBuf> {
+    let env_dir = |name: &str| std::env::var_os(name).filter(|s| !s.is_empty()).map(PathBuf::from);
+    let base = if cfg!(target_os = "windows") {
+        env_dir("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        env_dir("HOME").map(|home| home.join("Library/Application Support"))
+    } else {
+        env_dir("XDG_CONFIG_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".config")))
+    };
+    base.map(|dir| dir.join(APP_DIR_NAME))
 }
 
-fn load_map(filename: &str) -> HashMap<String, Value> {
-    if std::path::Path::new(filename).exists() {
-        match std::fs::read_to_string(filename) {
+fn load_map(path: &Path) -> HashMap<String, Value> {
+    if path.exists() {
+        match std::fs::read_to_string(path) {
             Ok(text) => {
                 match serde_json::from_str::<HashMap<String, Value>>(&text) {
                     Ok(deserialized) => {
                         return deserialized;
                     }
                     Err(err) => {
-                        eprintln!("{}", err);
+                        eprintln!("{}: {}", path.display(), err);
                     }
                 }
             }
             Err(err) => {
-                eprintln!("{}", err);
+                eprintln!("{}: {}", path.display(), err);
             }
         };
     }
     HashMap::new()
 }
 
-fn save_map(filename: &str, map: &HashMap<String, Value>) {
-    match serde_json::to_string(map) {
-        Ok(serialized) => {
-            let mut f = File::create(filename).expect("Unable to create file");
-            f.write_all(serialized.as_bytes()).expect("Unable to write data");
-        }
-        Err(message) => {
-            eprintln!("{}", message);
-        }
+// Writes into a temporary file and renames it, not to leave a broken file on a crash.
+fn save_map(path: &Path, map: &HashMap<String, Value>) -> Result<(), String> {
+    let serialized = serde_json::to_string(map).map_err(|e| e.to_string())?;
+    let error = |e: std::io::Error| format!("{}: {}", path.display(), e);
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(error)?;
     }
+
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+    let mut f = File::create(&tmp_path).map_err(error)?;
+    f.write_all(serialized.as_bytes()).map_err(error)?;
+    f.sync_all().map_err(error)?;
+    std::fs::rename(&tmp_path, path).map_err(error)
 }
@@ -24,4 +24,8 @@ impl TimerTrait for StdTimer {
         self.last_time = self.last_time + Duration::from_secs(1);
         true
     }
+
+    fn reset(&mut self) {
+        self.last_time = SystemTime::now();
+    }
 }